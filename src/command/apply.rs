@@ -0,0 +1,144 @@
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+
+use super::open_index;
+use super::print_operations;
+use crate::index::RootIndex;
+use crate::sync;
+use crate::sync::Journal;
+use crate::sync::JournalEntry;
+use crate::sync::JournalRecord;
+use crate::sync::Plan;
+use crate::util::display::percentage;
+use crate::util::output::OutputFormat;
+use crate::util::terminal::clear_line;
+use crate::util::timer::CountdownTimer;
+
+// Returns the journal path used when none is given, next to the plan file.
+fn default_journal_file(plan_file: &Path) -> PathBuf {
+	let mut path = OsString::from(plan_file.as_os_str());
+	path.push(".journal");
+	PathBuf::from(path)
+}
+
+// Removes the journal of a previous run so a new one can be started. Fails if that run was
+// interrupted, since it must be rolled back first.
+pub fn remove_finished_journal<T: JournalRecord>(journal_file: &Path) -> Result<()> {
+	if !journal_file.exists() {
+		return Ok(());
+	}
+	let entries: Vec<T> = Journal::read(journal_file)
+		.with_context(|| format!("Unable to read journal: {}", journal_file.display()))?;
+	if !entries.iter().any(JournalRecord::is_finished) {
+		bail!("A previous run did not finish, undo it first: {}", journal_file.display());
+	}
+	fs::remove_file(journal_file)?;
+	Ok(())
+}
+
+// Applies the plan with a journal, reporting progress. Returns the number of operations applied.
+pub fn apply_plan(
+	plan: &Plan,
+	journal_file: &Path,
+	src_index: Option<&RootIndex>,
+	dst_index: Option<&mut RootIndex>,
+) -> Result<usize> {
+	remove_finished_journal::<JournalEntry>(journal_file)?;
+	let mut current = 0usize;
+	let mut countdown = CountdownTimer::new(Duration::from_secs(1));
+	let total = plan.operations.len();
+	let result = sync::apply(plan, journal_file, src_index, dst_index, |operation| {
+		if countdown.passed() {
+			let percent = percentage(current, total);
+			clear_line();
+			eprint!("Applying {current} of {total} operations ({percent}): {}", operation.path());
+			io::stderr().flush().unwrap();
+		}
+		current += 1;
+	});
+	clear_line();
+	result.with_context(|| {
+		format!(
+			"Unable to apply plan, undo it with `apply --rollback --journal-file={}`",
+			journal_file.display()
+		)
+	})?;
+	Ok(total)
+}
+
+// Saves the index if any operation changed it, then returns the result of the operations.
+//
+// The index is saved even when the operations failed, since it reflects every operation that
+// finished before the failure.
+pub fn save_dirty_index<T>(
+	index: Option<&mut RootIndex>,
+	index_file: Option<&PathBuf>,
+	result: Result<T>,
+) -> Result<T> {
+	if let (Some(index), Some(index_file)) = (index, index_file) {
+		if index.dirty() {
+			eprintln!("Updating index...");
+			index.save(index_file)?;
+		}
+	}
+	result
+}
+
+pub fn apply(
+	plan_file: Option<&PathBuf>,
+	journal_file: Option<&PathBuf>,
+	src_index_file: Option<&PathBuf>,
+	dst_index_file: Option<&PathBuf>,
+	dry_run: bool,
+	rollback: bool,
+	format: OutputFormat,
+) -> Result<()> {
+	let journal_file = match (journal_file, plan_file) {
+		(Some(journal_file), _) => journal_file.clone(),
+		(None, Some(plan_file)) => default_journal_file(plan_file),
+		(None, None) => bail!("Either a plan file or a journal file is required"),
+	};
+	let mut dst_index = open_index(dst_index_file)?;
+
+	let result = if rollback {
+		eprintln!("Rolling back {}...", journal_file.display());
+		let mut current = 0usize;
+		let mut countdown = CountdownTimer::new(Duration::from_secs(1));
+		let result = sync::rollback(&journal_file, dst_index.as_mut(), |operation| {
+			if countdown.passed() {
+				clear_line();
+				eprint!("Undone {current} operations: {}", operation.path());
+				io::stderr().flush().unwrap();
+			}
+			current += 1;
+		});
+		clear_line();
+		result
+			.map(|()| current)
+			.with_context(|| format!("Unable to roll back: {}", journal_file.display()))
+	} else {
+		let Some(plan_file) = plan_file else {
+			bail!("A plan file is required");
+		};
+		let plan = Plan::open(plan_file)
+			.with_context(|| format!("Unable to open plan: {}", plan_file.display()))?;
+		if dry_run {
+			return print_operations(&plan.operations, format);
+		}
+		let src_index = open_index(src_index_file)?;
+		apply_plan(&plan, &journal_file, src_index.as_ref(), dst_index.as_mut())
+	};
+
+	let count = save_dirty_index(dst_index.as_mut(), dst_index_file, result)?;
+	eprintln!("Finished {count} operations!");
+	Ok(())
+}
@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Serialize;
+
+use super::calculate_checksums;
+use crate::index::Allowlist;
+use crate::index::RootIndex;
+use crate::util::display::percentage;
+use crate::util::output::print_records;
+use crate::util::output::OutputFormat;
+use crate::util::output::Record;
+
+/// Serialized form of a dir whose contents all exist under another dir.
+#[derive(Serialize)]
+pub struct ContainedDirRecord<'a> {
+	dir: &'a str,
+	container: &'a str,
+	/// Bytes of the container whose contents are also in the dir.
+	covered_bytes: u128,
+	/// Bytes of every file in the container.
+	container_bytes: u128,
+}
+
+impl Record for ContainedDirRecord<'_> {
+	const CSV_HEADER: &'static [&'static str] = &[
+		"dir",
+		"container",
+		"covered_bytes",
+		"container_bytes",
+	];
+
+	fn csv_rows(&self) -> Vec<Vec<String>> {
+		vec![
+			vec![
+				self.dir.to_string(),
+				self.container.to_string(),
+				self.covered_bytes.to_string(),
+				self.container_bytes.to_string(),
+			],
+		]
+	}
+}
+
+// Returns whether any contained dirs were found.
+pub fn contained(
+	index_file: &PathBuf,
+	allowlist: &Allowlist,
+	match_name: bool,
+	match_created: bool,
+	match_modified: bool,
+	format: OutputFormat,
+) -> Result<bool> {
+	eprintln!("Opening index file...");
+	let mut index = RootIndex::open(index_file)
+		.with_context(|| format!("Unable to open index: {}", index_file.display()))?;
+	calculate_checksums(
+		&mut index,
+		index_file,
+		false,
+		allowlist,
+		match_name,
+		match_created,
+		match_modified,
+	)?;
+
+	eprintln!("Gathering contained dirs...");
+	let contained = index.contained_dirs(allowlist);
+	let records: Vec<_> = contained
+		.iter()
+		.map(|contained| {
+			ContainedDirRecord {
+				dir: contained.dir.meta.path(),
+				container: contained.container.meta.path(),
+				covered_bytes: contained.covered_size,
+				container_bytes: contained.container_size,
+			}
+		})
+		.collect();
+
+	if format != OutputFormat::Text {
+		print_records(format, &records)?;
+	} else if records.is_empty() {
+		println!("No contained dirs found");
+	} else {
+		for record in &records {
+			let percent = percentage(
+				usize::try_from(record.covered_bytes).unwrap_or(usize::MAX),
+				usize::try_from(record.container_bytes).unwrap_or(usize::MAX),
+			);
+			println!("{} is contained in {} ({percent} of it)", record.dir, record.container);
+		}
+	}
+
+	let found = !records.is_empty();
+	if index.dirty() {
+		eprintln!("Updating index with checksums...");
+		index.save(index_file)?;
+	}
+	Ok(found)
+}
@@ -0,0 +1,210 @@
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use serde::Serialize;
+
+use super::calculate_checksums;
+use super::remove_finished_journal;
+use super::save_dirty_index;
+use crate::index::sort_by_keep_rules;
+use crate::index::Allowlist;
+use crate::index::DuplicateOptions;
+use crate::index::KeepRule;
+use crate::index::RootIndex;
+use crate::sync;
+use crate::sync::Dedupe;
+use crate::sync::DedupeEntry;
+use crate::sync::DedupeMode;
+use crate::util::display::percentage;
+use crate::util::output::print_records;
+use crate::util::output::OutputFormat;
+use crate::util::output::Record;
+use crate::util::terminal::clear_line;
+use crate::util::timer::CountdownTimer;
+
+/// Serialized form of a single deduplicated file.
+#[derive(Serialize)]
+pub struct DedupeRecord<'a> {
+	/// One of `hardlink`, `reflink`, `symlink`, `delete` or `quarantine`.
+	action: &'static str,
+	/// Path of the duplicate that is replaced or deleted.
+	path: &'a str,
+	/// Path of the file that is kept.
+	kept: &'a str,
+}
+
+impl Record for DedupeRecord<'_> {
+	const CSV_HEADER: &'static [&'static str] = &[
+		"action",
+		"path",
+		"kept",
+	];
+
+	fn csv_rows(&self) -> Vec<Vec<String>> {
+		vec![
+			vec![
+				self.action.to_string(),
+				self.path.to_string(),
+				self.kept.to_string(),
+			],
+		]
+	}
+}
+
+fn print_dedupes(dedupes: &[&Dedupe], mode: DedupeMode, format: OutputFormat) -> Result<()> {
+	let action = match mode {
+		DedupeMode::Hardlink => "hardlink",
+		DedupeMode::Reflink => "reflink",
+		DedupeMode::Symlink => "symlink",
+		DedupeMode::Delete => "delete",
+		DedupeMode::Quarantine => "quarantine",
+	};
+	let records: Vec<_> = dedupes
+		.iter()
+		.map(|dedupe| {
+			DedupeRecord {
+				action,
+				path: &dedupe.duplicate,
+				kept: &dedupe.kept,
+			}
+		})
+		.collect();
+	if format != OutputFormat::Text {
+		print_records(format, &records)?;
+		return Ok(());
+	}
+	if records.is_empty() {
+		println!("No duplicates found");
+	}
+	for record in records {
+		println!("{} {} (duplicate of {})", record.action, record.path, record.kept);
+	}
+	Ok(())
+}
+
+// Returns the journal path used when none is given, next to the index file.
+fn default_journal_file(index_file: &Path) -> PathBuf {
+	let mut path = OsString::from(index_file.as_os_str());
+	path.push(".journal");
+	PathBuf::from(path)
+}
+
+fn restore(index: &mut RootIndex, journal_file: &Path) -> Result<()> {
+	eprintln!("Restoring duplicates from {}...", journal_file.display());
+	let mut current = 0usize;
+	let mut countdown = CountdownTimer::new(Duration::from_secs(1));
+	let result = sync::undo_dedupe(journal_file, index, |dedupe| {
+		if countdown.passed() {
+			clear_line();
+			eprint!("Restored {current} duplicates: {}", dedupe.duplicate);
+			io::stderr().flush().unwrap();
+		}
+		current += 1;
+	});
+	clear_line();
+	result.with_context(|| format!("Unable to undo: {}", journal_file.display()))?;
+	eprintln!("Restored {current} duplicates!");
+	Ok(())
+}
+
+#[allow(
+	clippy::too_many_arguments,
+	clippy::fn_params_excessive_bools
+)]
+pub fn dedupe(
+	index_file: &PathBuf,
+	mode: DedupeMode,
+	journal_file: Option<&PathBuf>,
+	quarantine_dir: Option<&PathBuf>,
+	allowlist: &Allowlist,
+	keep: &[KeepRule],
+	dry_run: bool,
+	undo: bool,
+	match_name: bool,
+	match_created: bool,
+	match_modified: bool,
+	format: OutputFormat,
+) -> Result<()> {
+	let journal_file = journal_file.cloned().unwrap_or_else(|| default_journal_file(index_file));
+	eprintln!("Opening index file...");
+	let mut index = RootIndex::open(index_file)
+		.with_context(|| format!("Unable to open index: {}", index_file.display()))?;
+
+	let quarantine_dir = if mode == DedupeMode::Quarantine {
+		let Some(quarantine_dir) = quarantine_dir else {
+			bail!("A quarantine directory is required to quarantine duplicates");
+		};
+		if undo {
+			bail!("Quarantined duplicates are restored with the `restore` subcommand");
+		}
+		Some(quarantine_dir)
+	} else {
+		None
+	};
+	if undo {
+		let result = restore(&mut index, &journal_file);
+		return save_dirty_index(Some(&mut index), Some(index_file), result);
+	}
+
+	calculate_checksums(
+		&mut index,
+		index_file,
+		false,
+		allowlist,
+		match_name,
+		match_created,
+		match_modified,
+	)?;
+	let mut groups = index.duplicates(allowlist, &DuplicateOptions::default());
+	for group in &mut groups {
+		sort_by_keep_rules(group, keep, |file| &file.meta);
+	}
+	let dedupes = sync::dedupes(&groups);
+	if dry_run || dedupes.is_empty() {
+		if index.dirty() {
+			eprintln!("Updating index with checksums...");
+			index.save(index_file)?;
+		}
+		return print_dedupes(&dedupes.iter().collect::<Vec<_>>(), mode, format);
+	}
+
+	let mut current = 0usize;
+	let mut countdown = CountdownTimer::new(Duration::from_secs(1));
+	let total = dedupes.len();
+	let notifier = |dedupe: &Dedupe| {
+		if countdown.passed() {
+			let percent = percentage(current, total);
+			clear_line();
+			eprint!("Deduplicating {current} of {total} files ({percent}): {}", dedupe.duplicate);
+			io::stderr().flush().unwrap();
+		}
+		current += 1;
+	};
+	let result = if let Some(quarantine_dir) = quarantine_dir {
+		sync::quarantine(&dedupes, quarantine_dir, &mut index, notifier)
+	} else {
+		remove_finished_journal::<DedupeEntry>(&journal_file)?;
+		sync::dedupe(&dedupes, mode, &journal_file, &mut index, notifier)
+	};
+	clear_line();
+
+	let result = result.with_context(|| {
+		format!("Unable to deduplicate, undo it with --undo: {}", journal_file.display())
+	});
+	let skipped = save_dirty_index(Some(&mut index), Some(index_file), result)?;
+	for dedupe in &skipped {
+		eprintln!("Skipped {}: contents changed since indexing", dedupe.duplicate);
+	}
+	let skipped: HashSet<_> = skipped.iter().map(|dedupe| dedupe.duplicate.as_str()).collect();
+	let done: Vec<_> =
+		dedupes.iter().filter(|dedupe| !skipped.contains(dedupe.duplicate.as_str())).collect();
+	print_dedupes(&done, mode, format)
+}
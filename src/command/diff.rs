@@ -6,21 +6,111 @@ use std::time::Duration;
 
 use anyhow::Context;
 use anyhow::Result;
+use serde::Serialize;
 
 use crate::index::Diff;
 use crate::index::Index;
 use crate::index::RootIndex;
 use crate::util::display::percentage;
+use crate::util::output::print_records;
+use crate::util::output::OutputFormat;
+use crate::util::output::Record;
 use crate::util::terminal::clear_line;
 use crate::util::timer::CountdownTimer;
 
+/// Serialized form of a single difference.
+#[derive(Serialize)]
+pub struct DiffRecord<'a> {
+	/// One of `added`, `removed`, `changed`, `moved`, `copied` or `deduplicated`.
+	kind: &'static str,
+	/// Path of the entry that changed. For copies, this is the new copy.
+	path: &'a str,
+	/// For moves, the path in the index. For copies, the original file. For deduplications, the
+	/// remaining copy.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	related_path: Option<&'a str>,
+}
+
+impl<'a> DiffRecord<'a> {
+	fn from_diff(diff: &'a Diff) -> Self {
+		let (kind, path, related_path) = match diff {
+			Diff::Added(path) => ("added", path, None),
+			Diff::Removed(path) => ("removed", path, None),
+			Diff::Changed(path) => ("changed", path, None),
+			Diff::Moved(path, related) => ("moved", path, Some(related)),
+			Diff::Copied(related, path) => ("copied", path, Some(related)),
+			Diff::Deduplicated(path, related) => ("deduplicated", path, Some(related)),
+		};
+		Self {
+			kind,
+			path,
+			related_path: related_path.map(String::as_str),
+		}
+	}
+}
+
+impl Record for DiffRecord<'_> {
+	const CSV_HEADER: &'static [&'static str] = &[
+		"kind",
+		"path",
+		"related_path",
+	];
+
+	fn csv_rows(&self) -> Vec<Vec<String>> {
+		vec![
+			vec![
+				self.kind.to_string(),
+				self.path.to_string(),
+				self.related_path.unwrap_or_default().to_string(),
+			],
+		]
+	}
+}
+
+// Prints each difference, or that there are none.
+pub(super) fn print_diff(diff_list: &[Diff], format: OutputFormat) -> Result<()> {
+	if format != OutputFormat::Text {
+		let records: Vec<_> = diff_list.iter().map(DiffRecord::from_diff).collect();
+		print_records(format, &records)?;
+		return Ok(());
+	}
+	if diff_list.is_empty() {
+		println!("No changes");
+	}
+	for diff in diff_list {
+		match diff {
+			Diff::Added(name) => {
+				println!("+ {name}");
+			}
+			Diff::Removed(name) => {
+				println!("- {name}");
+			}
+			Diff::Changed(name) => {
+				println!("Δ {name}");
+			}
+			Diff::Moved(from, to) => {
+				println!("📍{from} → {to}");
+			}
+			Diff::Copied(from, to) => {
+				println!("+ {to} (copy of {from})");
+			}
+			Diff::Deduplicated(from, to) => {
+				println!("- {from} (duplicate of {to})");
+			}
+		}
+	}
+	Ok(())
+}
+
+// Returns whether any differences were found.
 pub fn diff(
 	src: &PathBuf,
 	index_file: &PathBuf,
 	match_name: bool,
 	match_created: bool,
 	match_modified: bool,
-) -> Result<()> {
+	format: OutputFormat,
+) -> Result<bool> {
 	let mut index_src = RootIndex::new();
 	let mut index_dst = RootIndex::new();
 
@@ -33,14 +123,14 @@ pub fn diff(
 				last_path = path.to_string();
 				if countdown.passed() {
 					clear_line();
-					print!("Discovered {current} entries: {path}");
-					io::stdout().flush().unwrap();
+					eprint!("Discovered {current} entries: {path}");
+					io::stderr().flush().unwrap();
 				}
 				current += 1;
 			})?;
 			clear_line();
-			print!("Loading index file...");
-			io::stdout().flush().unwrap();
+			eprint!("Loading index file...");
+			io::stderr().flush().unwrap();
 			Ok(())
 		});
 
@@ -48,14 +138,14 @@ pub fn diff(
 		src_thread.join().unwrap()?;
 
 		clear_line();
-		io::stdout().flush().unwrap();
+		io::stderr().flush().unwrap();
 		Ok(())
 	})?;
 
 	let count_src = index_src.entry_count();
 	let count_dst = index_dst.entry_count();
 	let total = count_src + count_dst;
-	println!("Found {total} total entries!");
+	eprintln!("Found {total} total entries!");
 
 	let mut current = 1usize;
 	let mut countdown = CountdownTimer::new(Duration::from_secs(1));
@@ -71,8 +161,8 @@ pub fn diff(
 				if countdown.passed() {
 					let percent = percentage(current, total);
 					clear_line();
-					print!("Comparing {rhs} vs {lhs} ({percent}))...");
-					io::stdout().flush().unwrap();
+					eprint!("Comparing {rhs} vs {lhs} ({percent}))...");
+					io::stderr().flush().unwrap();
 				}
 			},
 			match_name,
@@ -80,27 +170,9 @@ pub fn diff(
 			match_modified,
 		)
 		.with_context(|| format!("Comparison failed during {last_rhs} and {last_lhs}"))?;
-	if diff_list.is_empty() {
-		println!("No changes");
-	}
 	clear_line();
-	println!("Finished comparing {total} entries!");
+	eprintln!("Finished comparing {total} entries!");
 
-	for diff in &diff_list {
-		match diff {
-			Diff::Added(name) => {
-				println!("+ {name}");
-			}
-			Diff::Removed(name) => {
-				println!("- {name}");
-			}
-			Diff::Changed(name) => {
-				println!("Δ {name}");
-			}
-			Diff::Moved(from, to) => {
-				println!("📍{from} → {to}");
-			}
-		}
-	}
-	Ok(())
+	print_diff(&diff_list, format)?;
+	Ok(!diff_list.is_empty())
 }
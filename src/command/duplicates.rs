@@ -3,19 +3,118 @@ use std::io::Write;
 use std::path::PathBuf;
 use std::time::Duration;
 
+use anyhow::bail;
 use anyhow::Context;
 use anyhow::Result;
+use serde::Serialize;
 
+use crate::index::model::File;
+use crate::index::model::Metadata;
+use crate::index::outermost_duplicate_dirs;
+use crate::index::potential_file_matches_across;
+use crate::index::sort_by_keep_rules;
+use crate::index::wasted_size;
 use crate::index::Allowlist;
 use crate::index::ChecksumCalculator;
+use crate::index::DuplicateOptions;
 use crate::index::Index;
+use crate::index::KeepRule;
 use crate::index::RootIndex;
+use crate::util::display::format_size;
 use crate::util::display::percentage;
+use crate::util::output::print_records;
+use crate::util::output::OutputFormat;
+use crate::util::output::Record;
+use crate::util::script::Script;
+use crate::util::script::ScriptFormat;
 use crate::util::terminal::clear_line;
 use crate::util::timer::CountdownTimer;
 
+/// Serialized form of a group of duplicate files or dirs.
+#[derive(Serialize)]
+pub struct DuplicateGroupRecord<'a> {
+	group: usize,
+	/// Size of each entry in the group.
+	size: u128,
+	copies: usize,
+	/// Bytes freed by keeping only one entry.
+	wasted_bytes: u128,
+	/// Path of the entry to keep, chosen by the keep rules.
+	keep: &'a str,
+	/// Paths of every entry in the group, starting with the one to keep.
+	paths: Vec<&'a str>,
+	/// Index file holding each entry, in the same order as the paths.
+	indexes: Vec<String>,
+}
+
+impl Record for DuplicateGroupRecord<'_> {
+	const CSV_HEADER: &'static [&'static str] = &[
+		"group",
+		"size",
+		"copies",
+		"wasted_bytes",
+		"path",
+		"index",
+		"keep",
+	];
+
+	fn csv_rows(&self) -> Vec<Vec<String>> {
+		self.paths
+			.iter()
+			.zip(&self.indexes)
+			.enumerate()
+			.map(|(i, (path, index))| {
+				vec![
+					self.group.to_string(),
+					self.size.to_string(),
+					self.copies.to_string(),
+					self.wasted_bytes.to_string(),
+					(*path).to_string(),
+					index.clone(),
+					(i == 0).to_string(),
+				]
+			})
+			.collect()
+	}
+}
+
+// Runs the calculator, counting processed entries from `current` out of `total`. If an index file
+// is given, the index is periodically saved to it so progress is not lost.
+pub(super) fn run_calculator(
+	calculator: &mut ChecksumCalculator<'_>,
+	index_file: Option<&PathBuf>,
+	current: &mut usize,
+	total: usize,
+) -> Result<()> {
+	let mut render_countdown = CountdownTimer::new(Duration::from_secs(1));
+	let mut snapshotting_countdown = CountdownTimer::new(Duration::from_mins(1));
+
+	while let Some(file) = calculator.next() {
+		let path = file?.meta.path();
+		*current += 1;
+		if render_countdown.passed() {
+			let percent = percentage(*current, total);
+			clear_line();
+			eprint!("Processed {current} of {total} entries ({percent})...: {path}");
+			io::stderr().flush().unwrap();
+		}
+		let Some(index_file) = index_file else {
+			continue;
+		};
+		if snapshotting_countdown.passed() && calculator.index_mut().root_mut().dirty() {
+			clear_line();
+			eprintln!("Snapshotting index...");
+			calculator.index_mut().root_mut().save(index_file)?;
+		}
+	}
+	Ok(())
+}
+
+// Calculates the checksums needed to find duplicate files or dirs, periodically saving the index so
+// progress is not lost.
 #[allow(clippy::fn_params_excessive_bools)]
-pub fn duplicates(
+pub fn calculate_checksums(
+	index: &mut RootIndex,
 	index_file: &PathBuf,
 	dirs: bool,
 	allowlist: &Allowlist,
@@ -23,14 +122,10 @@ pub fn duplicates(
 	match_created: bool,
 	match_modified: bool,
 ) -> Result<()> {
-	println!("Opening index file...");
-	let mut index = RootIndex::open(index_file)
-		.with_context(|| format!("Unable to open index: {}", index_file.display()))?;
-
 	let total = index.file_count();
 	let sub_index = &mut index.all_mut();
 	let mut calculator = if dirs {
-		println!("Comparing dirs...");
+		eprintln!("Comparing dirs...");
 		ChecksumCalculator::with_dir_match(
 			sub_index,
 			allowlist,
@@ -39,7 +134,7 @@ pub fn duplicates(
 			match_modified,
 		)
 	} else {
-		println!("Comparing files...");
+		eprintln!("Comparing files...");
 		ChecksumCalculator::with_file_match(
 			sub_index,
 			allowlist,
@@ -49,58 +144,264 @@ pub fn duplicates(
 		)
 	};
 
+	run_calculator(&mut calculator, Some(index_file), &mut 0, total)?;
+	clear_line();
+	index.calculate_dir_checksums();
+	Ok(())
+}
+
+// Calculates the checksums needed to find duplicate files across the indexes, periodically saving
+// each index so progress is not lost.
+fn calculate_checksums_across(
+	indexes: &mut [RootIndex],
+	index_files: &[PathBuf],
+	allowlist: &Allowlist,
+	match_name: bool,
+	match_created: bool,
+	match_modified: bool,
+) -> Result<()> {
+	eprintln!("Comparing files...");
+	let file_lists: Vec<_> = indexes.iter().map(|index| index.files.as_slice()).collect();
+	let mut queues = vec![Vec::new(); indexes.len()];
+	for (index_pos, file_index) in potential_file_matches_across(
+		&file_lists,
+		allowlist,
+		match_name,
+		match_created,
+		match_modified,
+	) {
+		queues[index_pos].push(file_index);
+	}
+
+	let total = indexes.iter().map(Index::file_count).sum();
 	let mut current = 0;
-	let mut render_countdown = CountdownTimer::new(Duration::from_secs(1));
-	let mut snapshotting_countdown = CountdownTimer::new(Duration::from_secs(60));
+	for ((index, index_file), queue) in indexes.iter_mut().zip(index_files).zip(queues) {
+		let sub_index = &mut index.all_mut();
+		let mut calculator = ChecksumCalculator::with_files(sub_index, queue);
+		run_calculator(&mut calculator, Some(index_file), &mut current, total)?;
+		index.calculate_dir_checksums();
+	}
+	clear_line();
+	Ok(())
+}
 
-	while let Some(file) = calculator.next() {
-		let path = file?.meta.path();
-		current += 1;
-		if render_countdown.passed() {
-			let percent = percentage(current, total);
-			clear_line();
-			print!("Processed {current} of {total} entries ({percent})...: {path}");
-			io::stdout().flush().unwrap();
-		}
-		if snapshotting_countdown.passed() && calculator.index_mut().root_mut().dirty() {
-			clear_line();
-			println!("Snapshotting index...");
-			calculator.index_mut().root_mut().save(index_file)?;
+// Writes a script which deletes every file of each group except the one to keep.
+fn duplicates_script(groups: &[Vec<&File>], format: ScriptFormat) -> Script {
+	let mut script = Script::new(format);
+	for (i, group) in groups.iter().enumerate() {
+		let Some((kept, duplicates)) = group.split_first() else {
+			continue;
+		};
+		script.blank();
+		script.comment(&format!("Duplicate group {i}, keeping {}", kept.meta.path()));
+		for duplicate in duplicates {
+			if duplicate.linked.as_deref() == Some(kept.meta.path()) {
+				script.comment(&format!(
+					"Skipped {}: linked to the kept file",
+					duplicate.meta.path()
+				));
+				continue;
+			}
+			script.remove_file(duplicate.meta.path(), &duplicate.checksum);
 		}
 	}
+	script
+}
 
-	clear_line();
-	println!("Gathering duplicates...");
+// A group of duplicate entries, starting with the one to keep.
+struct DuplicateGroup<'a> {
+	// Size of each entry.
+	size: u128,
+	// Position of the index holding each entry, and its path.
+	entries: Vec<(usize, &'a str)>,
+}
 
-	let duplicates: Vec<_> = if dirs {
-		index
-			.duplicate_dirs(allowlist)
-			.iter()
-			.map(|dir_list| dir_list.iter().map(|dir| dir.meta.path()).collect::<Vec<_>>())
-			.collect()
-	} else {
-		index
-			.duplicates(allowlist)
-			.iter()
-			.map(|file_list| file_list.iter().map(|file| file.meta.path()).collect())
-			.collect()
-	};
+impl DuplicateGroup<'_> {
+	// Returns the space freed by keeping only one entry.
+	fn wasted_size(&self) -> u128 {
+		wasted_size(self.size, self.entries.len())
+	}
+}
 
-	if duplicates.is_empty() {
+// Prints groups of duplicates along with the space freed by keeping only one entry of each. Index
+// files are named in text only when there are several.
+fn print_duplicates(
+	duplicates: &[DuplicateGroup<'_>],
+	index_files: &[PathBuf],
+	format: OutputFormat,
+) -> Result<()> {
+	let index_name = |index_pos: usize| index_files[index_pos].display().to_string();
+	if format != OutputFormat::Text {
+		let records: Vec<_> = duplicates
+			.iter()
+			.enumerate()
+			.map(|(i, group)| {
+				DuplicateGroupRecord {
+					group: i,
+					size: group.size,
+					copies: group.entries.len(),
+					wasted_bytes: group.wasted_size(),
+					keep: group.entries[0].1,
+					paths: group.entries.iter().map(|(_, path)| *path).collect(),
+					indexes: group
+						.entries
+						.iter()
+						.map(|(index_pos, _)| index_name(*index_pos))
+						.collect(),
+				}
+			})
+			.collect();
+		print_records(format, &records)?;
+	} else if duplicates.is_empty() {
 		println!("No duplicates found");
 	} else {
-		for (i, file_list) in duplicates.iter().enumerate() {
-			println!("Duplicate group {i}:");
-			for file in file_list {
-				println!("- {file}");
+		let mut total_wasted = 0;
+		for (i, group) in duplicates.iter().enumerate() {
+			total_wasted += group.wasted_size();
+			println!(
+				"Duplicate group {i}: {} copies of {}, {} reclaimable",
+				group.entries.len(),
+				format_size(group.size),
+				format_size(group.wasted_size())
+			);
+			for (j, (index_pos, path)) in group.entries.iter().enumerate() {
+				let keep = if j == 0 {
+					" (keep)"
+				} else {
+					""
+				};
+				if index_files.len() > 1 {
+					println!("- {path}{keep} [{}]", index_name(*index_pos));
+				} else {
+					println!("- {path}{keep}");
+				}
 			}
 		}
+		println!("{} reclaimable in total.", format_size(total_wasted));
 	}
+	Ok(())
+}
 
-	if index.dirty() {
-		println!("Updating index with checksums...");
-		index.save(index_file)?;
+// Returns whether any duplicates were found. Each group starts with the entry to keep according to
+// the keep rules. Files are matched across all the indexes, while dirs are only found in a single
+// one. Groups of dirs inside other duplicate dirs are skipped unless all levels are requested, and
+// strict dirs must also have the same layout. Groups of files are filtered and sorted by the
+// options. If a script format is given, a script deleting the others is printed instead.
+#[allow(
+	clippy::too_many_arguments,
+	clippy::fn_params_excessive_bools
+)]
+pub fn duplicates(
+	index_files: &[PathBuf],
+	dirs: bool,
+	all_levels: bool,
+	strict: bool,
+	allowlist: &Allowlist,
+	options: &DuplicateOptions,
+	keep: &[KeepRule],
+	match_name: bool,
+	match_created: bool,
+	match_modified: bool,
+	script: Option<ScriptFormat>,
+	format: OutputFormat,
+) -> Result<bool> {
+	if dirs && script.is_some() {
+		bail!("Scripts can only be written for duplicate files");
+	}
+	if script.is_some() && index_files.len() > 1 {
+		bail!("Scripts can only be written for a single index");
+	}
+	if dirs && index_files.len() > 1 {
+		bail!("Duplicate dirs can only be found in a single index");
+	}
+	eprintln!("Opening index files...");
+	let mut indexes = Vec::new();
+	for index_file in index_files {
+		indexes.push(
+			RootIndex::open(index_file)
+				.with_context(|| format!("Unable to open index: {}", index_file.display()))?,
+		);
+	}
+	if let [index] = indexes.as_mut_slice() {
+		calculate_checksums(
+			index,
+			&index_files[0],
+			dirs,
+			allowlist,
+			match_name,
+			match_created,
+			match_modified,
+		)?;
+	} else {
+		calculate_checksums_across(
+			&mut indexes,
+			index_files,
+			allowlist,
+			match_name,
+			match_created,
+			match_modified,
+		)?;
 	}
 
-	Ok(())
+	eprintln!("Gathering duplicates...");
+
+	let found = if dirs {
+		let index = &indexes[0];
+		let mut dir_groups = index.duplicate_dirs(allowlist, strict);
+		if !all_levels {
+			dir_groups = outermost_duplicate_dirs(dir_groups);
+		}
+		let mut groups: Vec<Vec<&Metadata>> =
+			dir_groups.iter().map(|group| group.iter().map(|dir| &dir.meta).collect()).collect();
+		for group in &mut groups {
+			sort_by_keep_rules(group, keep, |meta| *meta);
+		}
+		let duplicates: Vec<_> = groups
+			.iter()
+			.map(|group| {
+				DuplicateGroup {
+					size: index
+						.sub_index(group[0].path())
+						.map_or(0, |sub_index| sub_index.file_size()),
+					entries: group.iter().map(|meta| (0, meta.path())).collect(),
+				}
+			})
+			.collect();
+		print_duplicates(&duplicates, index_files, format)?;
+		!duplicates.is_empty()
+	} else {
+		let mut groups = RootIndex::duplicates_across(&indexes, allowlist, options);
+		for group in &mut groups {
+			sort_by_keep_rules(group, keep, |(_, file)| &file.meta);
+		}
+		if let Some(script) = script {
+			let groups: Vec<Vec<&File>> =
+				groups.iter().map(|group| group.iter().map(|(_, file)| *file).collect()).collect();
+			print!("{}", duplicates_script(&groups, script).text());
+		} else {
+			let duplicates: Vec<_> = groups
+				.iter()
+				.map(|group| {
+					DuplicateGroup {
+						size: u128::from(group[0].1.size),
+						entries: group
+							.iter()
+							.map(|(index_pos, file)| (*index_pos, file.meta.path()))
+							.collect(),
+					}
+				})
+				.collect();
+			print_duplicates(&duplicates, index_files, format)?;
+		}
+		!groups.is_empty()
+	};
+
+	for (index, index_file) in indexes.iter_mut().zip(index_files) {
+		if index.dirty() {
+			eprintln!("Updating index with checksums...");
+			index.save(index_file)?;
+		}
+	}
+
+	Ok(found)
 }
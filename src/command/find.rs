@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::index::FindQuery;
+use crate::index::FoundEntry;
+use crate::index::RootIndex;
+use crate::util::output::print_records;
+use crate::util::output::OutputFormat;
+use crate::util::output::Record;
+
+/// Serialized form of an indexed entry matching a query.
+#[derive(Serialize)]
+pub struct FoundEntryRecord<'a> {
+	/// Either `file` or `dir`.
+	kind: &'static str,
+	path: &'a str,
+	/// Size of a file.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	size: Option<u64>,
+	/// Seconds since the Unix epoch.
+	modified: u64,
+	/// SHA-512 of a file, or of the contents of a dir, if calculated.
+	#[serde(skip_serializing_if = "str::is_empty")]
+	checksum: &'a str,
+}
+
+impl Record for FoundEntryRecord<'_> {
+	const CSV_HEADER: &'static [&'static str] = &[
+		"kind",
+		"path",
+		"size",
+		"modified",
+		"checksum",
+	];
+
+	fn csv_rows(&self) -> Vec<Vec<String>> {
+		vec![
+			vec![
+				self.kind.to_string(),
+				self.path.to_string(),
+				self.size.map(|size| size.to_string()).unwrap_or_default(),
+				self.modified.to_string(),
+				self.checksum.to_string(),
+			],
+		]
+	}
+}
+
+// Prints the indexed entries matching the query. Only the index is read, so nothing is checked on
+// disk.
+pub fn find(index_file: &PathBuf, query: &FindQuery, format: OutputFormat) -> Result<()> {
+	eprintln!("Opening index file...");
+	let index = RootIndex::open(index_file)
+		.with_context(|| format!("Unable to open index: {}", index_file.display()))?;
+
+	let found = index.find(query);
+	if format != OutputFormat::Text {
+		let records: Vec<_> = found
+			.iter()
+			.map(|entry| {
+				let (kind, size, checksum) = match entry {
+					FoundEntry::File(file) => ("file", Some(file.size), &file.checksum),
+					FoundEntry::Dir(dir) => ("dir", None, &dir.checksum),
+				};
+				let meta = entry.meta();
+				FoundEntryRecord {
+					kind,
+					path: meta.path(),
+					size,
+					modified: meta
+						.modified_time()
+						.duration_since(SystemTime::UNIX_EPOCH)
+						.map_or(0, |duration| duration.as_secs()),
+					checksum: &checksum.sha512,
+				}
+			})
+			.collect();
+		print_records(format, &records)?;
+		return Ok(());
+	}
+	for entry in &found {
+		match entry {
+			FoundEntry::File(file) => println!("{}", file.meta.path()),
+			FoundEntry::Dir(dir) => println!("{}/", dir.meta.path()),
+		}
+	}
+	Ok(())
+}
@@ -0,0 +1,187 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use serde::Serialize;
+
+use super::diff::print_diff;
+use crate::index::model::File;
+use crate::index::snapshot_diff;
+use crate::index::Diff;
+use crate::index::History;
+use crate::index::RootIndex;
+use crate::util::display::format_size;
+use crate::util::output::print_records;
+use crate::util::output::OutputFormat;
+use crate::util::output::Record;
+use crate::util::timer::format_time;
+
+/// Serialized form of a snapshot in the history of an index, with what changed since the previous
+/// one.
+#[derive(Serialize)]
+pub struct SnapshotRecord {
+	snapshot: usize,
+	/// Seconds since the Unix epoch when the snapshot was first indexed.
+	time: u64,
+	files: usize,
+	bytes: u128,
+	/// Files added or copied since the previous snapshot.
+	added: usize,
+	/// Files removed or deduplicated since the previous snapshot.
+	removed: usize,
+	changed: usize,
+	moved: usize,
+}
+
+impl Record for SnapshotRecord {
+	const CSV_HEADER: &'static [&'static str] = &[
+		"snapshot",
+		"time",
+		"files",
+		"bytes",
+		"added",
+		"removed",
+		"changed",
+		"moved",
+	];
+
+	fn csv_rows(&self) -> Vec<Vec<String>> {
+		vec![
+			vec![
+				self.snapshot.to_string(),
+				self.time.to_string(),
+				self.files.to_string(),
+				self.bytes.to_string(),
+				self.added.to_string(),
+				self.removed.to_string(),
+				self.changed.to_string(),
+				self.moved.to_string(),
+			],
+		]
+	}
+}
+
+// Opens an index file, failing unless it keeps a history.
+fn open_with_history(index_file: &PathBuf) -> Result<RootIndex> {
+	eprintln!("Opening index file...");
+	let index = RootIndex::open(index_file)
+		.with_context(|| format!("Unable to open index: {}", index_file.display()))?;
+	if index.history().is_none() {
+		bail!("The index has no history, re-index it with --history to start one");
+	}
+	Ok(index)
+}
+
+// Restores a snapshot of the index, failing if it does not exist.
+fn restore(index: &RootIndex, history: &History, snapshot: usize) -> Result<RootIndex> {
+	let Some(restored) = index.snapshot(snapshot) else {
+		bail!("Invalid snapshot {snapshot}, expected one from 0 to {}", history.len() - 1);
+	};
+	Ok(restored)
+}
+
+// Creates the record of a snapshot from its files, counting the differences with the previous one.
+fn snapshot_record(
+	snapshot: usize,
+	time: SystemTime,
+	files: &[File],
+	diff_list: &[Diff],
+) -> SnapshotRecord {
+	let count = |matches: fn(&Diff) -> bool| diff_list.iter().filter(|diff| matches(diff)).count();
+	SnapshotRecord {
+		snapshot,
+		time: time.duration_since(SystemTime::UNIX_EPOCH).map_or(0, |duration| duration.as_secs()),
+		files: files.len(),
+		bytes: files.iter().map(|file| u128::from(file.size)).sum(),
+		added: count(|diff| matches!(diff, Diff::Added(_) | Diff::Copied(..))),
+		removed: count(|diff| matches!(diff, Diff::Removed(_) | Diff::Deduplicated(..))),
+		changed: count(|diff| matches!(diff, Diff::Changed(_))),
+		moved: count(|diff| matches!(diff, Diff::Moved(..))),
+	}
+}
+
+// Prints the snapshots kept in an index, oldest first, with what changed since the previous one.
+pub fn history_list(index_file: &PathBuf, format: OutputFormat) -> Result<()> {
+	let index = open_with_history(index_file)?;
+	let history = index.history().unwrap();
+
+	// Walks back from the current state, undoing one snapshot at a time and diffing each state with
+	// the one before it.
+	let mut files = index.files.clone();
+	let mut dirs = index.dirs.clone();
+	let mut records = Vec::with_capacity(history.len());
+	for (snapshot, past) in history.snapshots.iter().enumerate().rev() {
+		let later_files = files.clone();
+		past.delta.apply(&mut files, &mut dirs);
+		let diff_list = snapshot_diff(&later_files, &files);
+		let time = history.time(snapshot + 1).unwrap();
+		records.push(snapshot_record(snapshot + 1, time, &later_files, &diff_list));
+	}
+	records.push(snapshot_record(0, history.time(0).unwrap(), &files, &[]));
+	records.reverse();
+
+	if format != OutputFormat::Text {
+		print_records(format, &records)?;
+		return Ok(());
+	}
+	for record in &records {
+		let time = history.time(record.snapshot).unwrap();
+		let current = if record.snapshot + 1 == history.len() {
+			" (current)"
+		} else {
+			""
+		};
+		print!(
+			"Snapshot {}{current}: {}, {} files, {}",
+			record.snapshot,
+			format_time(time),
+			record.files,
+			format_size(record.bytes)
+		);
+		if record.snapshot == 0 {
+			println!();
+		} else {
+			println!(
+				", {} added, {} removed, {} changed, {} moved",
+				record.added, record.removed, record.changed, record.moved
+			);
+		}
+	}
+	Ok(())
+}
+
+// Returns whether the snapshots differ. Without a later snapshot, the earlier one is compared with
+// the current state. Only the indexed metadata is compared, since past contents cannot be read.
+pub fn history_diff(
+	index_file: &PathBuf,
+	from: usize,
+	to: Option<usize>,
+	format: OutputFormat,
+) -> Result<bool> {
+	let index = open_with_history(index_file)?;
+	let history = index.history().unwrap();
+	let from_index = restore(&index, history, from)?;
+	let to_index = restore(&index, history, to.unwrap_or(history.len() - 1))?;
+
+	let diff_list = snapshot_diff(&to_index.files, &from_index.files);
+	print_diff(&diff_list, format)?;
+	Ok(!diff_list.is_empty())
+}
+
+// Writes a past snapshot as a standalone index file without history.
+pub fn history_restore(
+	index_file: &PathBuf,
+	snapshot: usize,
+	restore_file: &PathBuf,
+) -> Result<()> {
+	if restore_file == index_file {
+		bail!("The snapshot must be restored to another file than the index");
+	}
+	let index = open_with_history(index_file)?;
+	let mut restored = restore(&index, index.history().unwrap(), snapshot)?;
+	eprintln!("Saving snapshot {snapshot}...");
+	restored.save(restore_file)?;
+	Ok(())
+}
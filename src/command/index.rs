@@ -1,7 +1,9 @@
+use std::fs;
 use std::io;
 use std::io::Write;
 use std::path::PathBuf;
 use std::time::Duration;
+use std::time::SystemTime;
 
 use anyhow::Context;
 use anyhow::Result;
@@ -10,7 +12,9 @@ use crate::index::RootIndex;
 use crate::util::terminal::clear_line;
 use crate::util::timer::CountdownTimer;
 
-pub fn index(src: &PathBuf, index_file: &PathBuf, sha_512: bool) -> Result<()> {
+// Indexes the source into the index file. With history, the previous state of the index is kept
+// as a snapshot whenever it changed.
+pub fn index(src: &PathBuf, index_file: &PathBuf, sha_512: bool, history: bool) -> Result<()> {
 	let mut current = 0usize;
 	let mut countdown = CountdownTimer::new(Duration::from_secs(1));
 	let mut last_path = String::new();
@@ -18,31 +22,43 @@ pub fn index(src: &PathBuf, index_file: &PathBuf, sha_512: bool) -> Result<()> {
 		last_path = path.to_string();
 		if countdown.passed() {
 			clear_line();
-			print!("Discovered {current} entries: {path}");
-			io::stdout().flush().unwrap();
+			eprint!("Discovered {current} entries: {path}");
+			io::stderr().flush().unwrap();
 		}
 		current += 1;
 	};
 
-	let mut index = if index_file.exists() {
-		println!("Opening index file...");
+	let (mut index, previous) = if index_file.exists() {
+		eprintln!("Opening index file...");
 		let mut index = RootIndex::open(index_file)
 			.with_context(|| format!("Unable to open index: {}", index_file.display()))?;
-		println!("Updating index file...");
+		if history {
+			index.enable_history(fs::metadata(index_file)?.modified()?);
+		}
+		let previous = index.history().map(|_| (index.files.clone(), index.dirs.clone()));
+		eprintln!("Updating index file...");
 		index.add(std::path::absolute(src)?, update_fn)?;
-		index
+		(index, previous)
 	} else {
-		println!("Reading files...");
-		RootIndex::from_path(std::path::absolute(src)?, update_fn)?
+		eprintln!("Reading files...");
+		let mut index = RootIndex::from_path(std::path::absolute(src)?, update_fn)?;
+		if history {
+			index.enable_history(SystemTime::now());
+		}
+		(index, None)
 	};
 	clear_line();
-	println!("Discovered {current} total entries!");
+	eprintln!("Discovered {current} total entries!");
 
 	if sha_512 {
-		println!("Updating checksums...");
+		eprintln!("Updating checksums...");
 		index.calculate_all()?;
 	}
-	println!("Saving index file...");
+	index.calculate_dir_checksums();
+	if let Some((files, dirs)) = previous {
+		index.record_snapshot(&files, &dirs, SystemTime::now());
+	}
+	eprintln!("Saving index file...");
 	index.save(index_file)?;
 	Ok(())
 }
@@ -0,0 +1,140 @@
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Serialize;
+
+use super::duplicates::run_calculator;
+use crate::index::potential_lookup_matches;
+use crate::index::Allowlist;
+use crate::index::ChecksumCalculator;
+use crate::index::Lookup;
+use crate::index::RootIndex;
+use crate::util::output::print_records;
+use crate::util::output::OutputFormat;
+use crate::util::output::Record;
+use crate::util::terminal::clear_line;
+use crate::util::timer::CountdownTimer;
+
+/// Serialized form of a source file looked up in an index.
+#[derive(Serialize)]
+pub struct LookupRecord<'a> {
+	path: &'a str,
+	/// One of `present`, `different` or `new`.
+	status: &'static str,
+	/// For present files, the indexed files with the same contents. For different files, the
+	/// indexed files with the same name.
+	matches: Vec<&'a str>,
+}
+
+impl Record for LookupRecord<'_> {
+	const CSV_HEADER: &'static [&'static str] = &[
+		"path",
+		"status",
+		"matches",
+	];
+
+	fn csv_rows(&self) -> Vec<Vec<String>> {
+		vec![
+			vec![
+				self.path.to_string(),
+				self.status.to_string(),
+				self.matches.join(";"),
+			],
+		]
+	}
+}
+
+// Returns whether any source file is new, with neither its contents nor its name in the index.
+// Only files whose size is in the index are hashed, and the index is saved with the checksums it
+// gained.
+pub fn lookup(
+	src: &PathBuf,
+	index_file: &PathBuf,
+	allowlist: &Allowlist,
+	format: OutputFormat,
+) -> Result<bool> {
+	eprintln!("Opening index file...");
+	let mut index = RootIndex::open(index_file)
+		.with_context(|| format!("Unable to open index: {}", index_file.display()))?;
+
+	let mut current = 0usize;
+	let mut countdown = CountdownTimer::new(Duration::from_secs(1));
+	let mut src_index = RootIndex::from_path(std::path::absolute(src)?, |path| {
+		if countdown.passed() {
+			clear_line();
+			eprint!("Discovered {current} entries: {path}");
+			io::stderr().flush().unwrap();
+		}
+		current += 1;
+	})
+	.with_context(|| format!("Unable to read source: {}", src.display()))?;
+	clear_line();
+
+	eprintln!("Comparing files...");
+	let (src_queue, index_queue) =
+		potential_lookup_matches(&src_index.files, &index.files, allowlist);
+	let total = src_queue.len() + index_queue.len();
+	let mut current = 0;
+	run_calculator(
+		&mut ChecksumCalculator::with_files(&mut index.all_mut(), index_queue),
+		Some(index_file),
+		&mut current,
+		total,
+	)?;
+	run_calculator(
+		&mut ChecksumCalculator::with_files(&mut src_index.all_mut(), src_queue),
+		None,
+		&mut current,
+		total,
+	)?;
+	clear_line();
+	index.calculate_dir_checksums();
+
+	let records: Vec<_> = index
+		.lookup(&src_index, allowlist)
+		.into_iter()
+		.map(|(file, lookup)| {
+			let (status, matches) = match lookup {
+				Lookup::Present(matches) => ("present", matches),
+				Lookup::Different(matches) => ("different", matches),
+				Lookup::New => ("new", Vec::new()),
+			};
+			LookupRecord {
+				path: file.meta.path(),
+				status,
+				matches: matches.iter().map(|file| file.meta.path()).collect(),
+			}
+		})
+		.collect();
+
+	if format != OutputFormat::Text {
+		print_records(format, &records)?;
+	} else if records.is_empty() {
+		println!("No files found");
+	} else {
+		for record in &records {
+			match record.status {
+				"present" => println!("{}: present at {}", record.path, record.matches.join(", ")),
+				"different" => {
+					println!(
+						"{}: same name, different content as {}",
+						record.path,
+						record.matches.join(", ")
+					);
+				}
+				_ => println!("{}: new", record.path),
+			}
+		}
+	}
+
+	let found = records.iter().any(|record| record.status == "new");
+	if index.dirty() {
+		eprintln!("Updating index with checksums...");
+		index.save(index_file)?;
+	}
+	Ok(found)
+}
@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+
+use super::apply_plan;
+use super::open_index;
+use super::print_operations;
+use super::relative_index;
+use super::save_dirty_index;
+use crate::index::Index;
+use crate::sync::Plan;
+use crate::util::output::OutputFormat;
+
+// Returns the journal path used when none is given, next to the destination.
+fn default_journal_file(dst: &PathBuf) -> Result<PathBuf> {
+	let dst = std::path::absolute(dst)?;
+	let name = dst
+		.file_name()
+		.with_context(|| format!("A journal file is required to mirror to {}", dst.display()))?;
+	Ok(dst.with_file_name(format!("{}.journal", name.to_string_lossy())))
+}
+
+#[allow(
+	clippy::too_many_arguments,
+	clippy::fn_params_excessive_bools
+)]
+pub fn mirror(
+	src: &PathBuf,
+	dst: &PathBuf,
+	journal_file: Option<&PathBuf>,
+	src_index_file: Option<&PathBuf>,
+	dst_index_file: Option<&PathBuf>,
+	delete: bool,
+	dry_run: bool,
+	match_name: bool,
+	match_created: bool,
+	match_modified: bool,
+	format: OutputFormat,
+) -> Result<()> {
+	let src_index = open_index(src_index_file)?;
+	let mut dst_index = open_index(dst_index_file)?;
+	let mut src_relative = relative_index(src, src_index.as_ref())?;
+	let mut dst_relative = relative_index(dst, dst_index.as_ref())?;
+
+	let total = src_relative.entry_count() + dst_relative.entry_count();
+	eprintln!("Comparing {total} entries...");
+	let diff_list = src_relative.diff(
+		&mut dst_relative,
+		|_, _| {},
+		match_name,
+		match_created,
+		match_modified,
+	)?;
+
+	let mut plan = Plan::new(&src_relative, &dst_relative, &diff_list);
+	if !delete {
+		plan.keep_extras();
+	}
+	if dry_run {
+		return print_operations(&plan.operations, format);
+	}
+
+	let journal_file = match journal_file {
+		Some(journal_file) => journal_file.clone(),
+		None => default_journal_file(dst)?,
+	};
+	let result = apply_plan(&plan, &journal_file, src_index.as_ref(), dst_index.as_mut());
+	let count = save_dirty_index(dst_index.as_mut(), dst_index_file, result)?;
+	eprintln!("Finished {count} operations!");
+	print_operations(&plan.operations, format)
+}
@@ -1,11 +1,33 @@
+mod apply;
+mod contained;
+mod dedupe;
 mod diff;
 mod duplicates;
+mod find;
+mod history;
 mod index;
+mod lookup;
+mod mirror;
+mod plan;
+mod quarantine;
 mod redundant;
+mod similar_dirs;
 mod stats;
+mod tree;
 
+pub use apply::*;
+pub use contained::*;
+pub use dedupe::*;
 pub use diff::*;
 pub use duplicates::*;
+pub use find::*;
+pub use history::*;
 pub use index::*;
+pub use lookup::*;
+pub use mirror::*;
+pub use plan::*;
+pub use quarantine::*;
 pub use redundant::*;
+pub use similar_dirs::*;
 pub use stats::*;
+pub use tree::*;
@@ -0,0 +1,154 @@
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::index::Index;
+use crate::index::RootIndex;
+use crate::sync::Operation;
+use crate::sync::Plan;
+use crate::util::output::print_records;
+use crate::util::output::OutputFormat;
+use crate::util::output::Record;
+use crate::util::terminal::clear_line;
+use crate::util::timer::CountdownTimer;
+
+/// Serialized form of a single planned operation.
+#[derive(Serialize)]
+pub struct OperationRecord<'a> {
+	/// One of `mkdir`, `copy`, `overwrite`, `move`, `delete` or `rmdir`.
+	operation: &'static str,
+	/// Path that the operation creates or changes, relative to the destination.
+	path: &'a str,
+	/// For moves, the current path relative to the destination.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	from: Option<&'a str>,
+}
+
+impl<'a> OperationRecord<'a> {
+	pub fn from_operation(operation: &'a Operation) -> Self {
+		let (name, from) = match operation {
+			Operation::Mkdir(_) => ("mkdir", None),
+			Operation::Copy(_) => ("copy", None),
+			Operation::Overwrite(_) => ("overwrite", None),
+			Operation::Move(from, _) => ("move", Some(from.as_str())),
+			Operation::Delete(_) => ("delete", None),
+			Operation::Rmdir(_) => ("rmdir", None),
+		};
+		Self {
+			operation: name,
+			path: operation.path(),
+			from,
+		}
+	}
+}
+
+impl Record for OperationRecord<'_> {
+	const CSV_HEADER: &'static [&'static str] = &[
+		"operation",
+		"path",
+		"from",
+	];
+
+	fn csv_rows(&self) -> Vec<Vec<String>> {
+		vec![
+			vec![
+				self.operation.to_string(),
+				self.path.to_string(),
+				self.from.unwrap_or_default().to_string(),
+			],
+		]
+	}
+}
+
+// Prints the operations in the given format.
+pub fn print_operations(operations: &[Operation], format: OutputFormat) -> Result<()> {
+	let records: Vec<_> = operations.iter().map(OperationRecord::from_operation).collect();
+	if format != OutputFormat::Text {
+		print_records(format, &records)?;
+		return Ok(());
+	}
+	if records.is_empty() {
+		println!("Nothing to do");
+	}
+	for record in records {
+		if let Some(from) = record.from {
+			println!("{} {from} → {}", record.operation, record.path);
+		} else {
+			println!("{} {}", record.operation, record.path);
+		}
+	}
+	Ok(())
+}
+
+// Opens the index file if given.
+pub fn open_index(index_file: Option<&PathBuf>) -> Result<Option<RootIndex>> {
+	let Some(index_file) = index_file else {
+		return Ok(None);
+	};
+	eprintln!("Opening index file {}...", index_file.display());
+	let index = RootIndex::open(index_file)
+		.with_context(|| format!("Unable to open index: {}", index_file.display()))?;
+	Ok(Some(index))
+}
+
+// Returns the index of the given path, with entries relative to it. Uses the opened index if
+// given, otherwise reads the path from the filesystem.
+pub fn relative_index(path: &PathBuf, index: Option<&RootIndex>) -> Result<RootIndex> {
+	let path = std::path::absolute(path)?;
+	let relative = if let Some(index) = index {
+		index.relative(&path)
+	} else if path.exists() {
+		let mut current = 0usize;
+		let mut countdown = CountdownTimer::new(Duration::from_secs(1));
+		let index = RootIndex::from_path(&path, |path| {
+			if countdown.passed() {
+				clear_line();
+				eprint!("Discovered {current} entries: {path}");
+				io::stderr().flush().unwrap();
+			}
+			current += 1;
+		})?;
+		clear_line();
+		eprintln!("Discovered {current} entries in {}", path.display());
+		index.relative(&path)
+	} else {
+		return Ok(RootIndex::with_root(&path));
+	};
+	let Some(relative) = relative else {
+		bail!("Directory is not indexed: {}", path.display());
+	};
+	Ok(relative)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn plan(
+	src: &PathBuf,
+	dst: &PathBuf,
+	plan_file: &PathBuf,
+	src_index_file: Option<&PathBuf>,
+	dst_index_file: Option<&PathBuf>,
+	match_name: bool,
+	match_created: bool,
+	match_modified: bool,
+	format: OutputFormat,
+) -> Result<()> {
+	let mut src_index = relative_index(src, open_index(src_index_file)?.as_ref())?;
+	let mut dst_index = relative_index(dst, open_index(dst_index_file)?.as_ref())?;
+
+	let total = src_index.entry_count() + dst_index.entry_count();
+	eprintln!("Comparing {total} entries...");
+	let diff_list =
+		src_index.diff(&mut dst_index, |_, _| {}, match_name, match_created, match_modified)?;
+
+	let plan = Plan::new(&src_index, &dst_index, &diff_list);
+	eprintln!("Saving plan file...");
+	plan.save(plan_file)
+		.with_context(|| format!("Unable to save plan: {}", plan_file.display()))?;
+	print_operations(&plan.operations, format)
+}
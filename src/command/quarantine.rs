@@ -0,0 +1,60 @@
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+use anyhow::Result;
+
+use super::open_index;
+use super::save_dirty_index;
+use crate::sync;
+use crate::util::terminal::clear_line;
+use crate::util::timer::CountdownTimer;
+
+pub fn restore(quarantine_dir: &Path, index_file: Option<&PathBuf>) -> Result<()> {
+	let mut index = open_index(index_file)?;
+	eprintln!("Restoring quarantined files from {}...", quarantine_dir.display());
+	let mut current = 0usize;
+	let mut countdown = CountdownTimer::new(Duration::from_secs(1));
+	let result = sync::restore(quarantine_dir, index.as_mut(), |file| {
+		if countdown.passed() {
+			clear_line();
+			eprint!("Restored {current} files: {}", file.original);
+			io::stderr().flush().unwrap();
+		}
+		current += 1;
+	});
+	clear_line();
+
+	let result = result.with_context(|| {
+		format!("Unable to restore quarantined files: {}", quarantine_dir.display())
+	});
+	let skipped = save_dirty_index(index.as_mut(), index_file, result)?;
+	for file in &skipped {
+		eprintln!("Skipped {}: path is taken", file.original);
+	}
+	eprintln!("Restored {current} files!");
+	Ok(())
+}
+
+pub fn purge(quarantine_dir: &Path, older_than: Duration) -> Result<()> {
+	eprintln!("Purging quarantined files from {}...", quarantine_dir.display());
+	let mut current = 0usize;
+	let mut countdown = CountdownTimer::new(Duration::from_secs(1));
+	let result = sync::purge(quarantine_dir, older_than, |file| {
+		if countdown.passed() {
+			clear_line();
+			eprint!("Deleted {current} files: {}", file.quarantined);
+			io::stderr().flush().unwrap();
+		}
+		current += 1;
+	});
+	clear_line();
+	let count = result.with_context(|| {
+		format!("Unable to purge quarantined files: {}", quarantine_dir.display())
+	})?;
+	eprintln!("Deleted {count} files!");
+	Ok(())
+}
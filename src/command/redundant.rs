@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::io;
 use std::io::Write;
 use std::path::PathBuf;
@@ -5,29 +6,128 @@ use std::time::Duration;
 
 use anyhow::Context;
 use anyhow::Result;
+use serde::Serialize;
 
 use crate::index::Allowlist;
 use crate::index::ChecksumCalculator;
+use crate::index::DuplicateOptions;
 use crate::index::Index;
 use crate::index::RootIndex;
 use crate::util::display::percentage;
+use crate::util::output::print_records;
+use crate::util::output::OutputFormat;
+use crate::util::output::Record;
+use crate::util::script::Script;
+use crate::util::script::ScriptFormat;
 use crate::util::terminal::clear_line;
 use crate::util::timer::CountdownTimer;
 
+/// Serialized form of a redundant file and the other files with its content.
+#[derive(Serialize)]
+pub struct RedundantFileRecord {
+	path: String,
+	duplicates: Vec<String>,
+}
+
+/// Serialized form of a dir whose contents are duplicated elsewhere.
+#[derive(Serialize)]
+pub struct RedundantDirRecord {
+	dir: String,
+	files: Vec<RedundantFileRecord>,
+	dirs: Vec<String>,
+}
+
+impl Record for RedundantDirRecord {
+	const CSV_HEADER: &'static [&'static str] = &[
+		"dir",
+		"kind",
+		"path",
+		"duplicates",
+	];
+
+	fn csv_rows(&self) -> Vec<Vec<String>> {
+		let files = self.files.iter().map(|file| {
+			vec![
+				self.dir.clone(),
+				"file".to_string(),
+				file.path.clone(),
+				file.duplicates.join(";"),
+			]
+		});
+		let dirs = self.dirs.iter().map(|dir| {
+			vec![
+				self.dir.clone(),
+				"dir".to_string(),
+				dir.clone(),
+				String::new(),
+			]
+		});
+		files.chain(dirs).collect()
+	}
+}
+
+// Writes a script which deletes the redundant dirs. A file is kept when every other copy of it was
+// deleted earlier in the script, along with the dirs containing it.
+fn redundant_script(
+	records: &[RedundantDirRecord],
+	index: &RootIndex,
+	format: ScriptFormat,
+) -> Script {
+	let mut script = Script::new(format);
+	let mut removed_files = HashSet::new();
+	let mut removed_dirs = HashSet::new();
+	for record in records {
+		script.blank();
+		script.comment(&format!("Redundant dir {}", record.dir));
+		for file in &record.files {
+			if removed_files.contains(&file.path) {
+				continue;
+			}
+			if file.duplicates.iter().all(|duplicate| removed_files.contains(duplicate)) {
+				script.comment(&format!("Kept {}: every other copy is deleted above", file.path));
+				continue;
+			}
+			let Some(indexed) = index.file(&file.path) else {
+				continue;
+			};
+			script.comment(&format!("Duplicate of {}", file.duplicates.join(", ")));
+			script.remove_file(&file.path, &indexed.checksum);
+			removed_files.insert(file.path.clone());
+		}
+		if !record.files.iter().all(|file| removed_files.contains(&file.path)) {
+			script.comment(&format!("Kept {}: not every file inside is deleted", record.dir));
+			continue;
+		}
+		// Longer paths are removed first, so nested dirs are empty before their parents.
+		let mut dirs: Vec<_> = record.dirs.iter().chain([&record.dir]).collect();
+		dirs.sort_by_key(|dir| std::cmp::Reverse(dir.len()));
+		for dir in dirs {
+			if removed_dirs.insert(dir.clone()) {
+				script.remove_dir(dir);
+			}
+		}
+	}
+	script
+}
+
+// Returns whether any redundant dirs were found. If a script format is given, a script deleting
+// them is printed instead.
 pub fn redundant(
 	index_file: &PathBuf,
 	allowlist: &Allowlist,
 	match_name: bool,
 	match_created: bool,
 	match_modified: bool,
-) -> Result<()> {
-	println!("Opening index file...");
+	script: Option<ScriptFormat>,
+	format: OutputFormat,
+) -> Result<bool> {
+	eprintln!("Opening index file...");
 	let mut index = RootIndex::open(index_file)
 		.with_context(|| format!("Unable to open index: {}", index_file.display()))?;
 
 	let total = index.file_count();
 	let sub_index = &mut index.all_mut();
-	println!("Comparing files...");
+	eprintln!("Comparing files...");
 	let mut calculator = ChecksumCalculator::with_file_match(
 		sub_index,
 		allowlist,
@@ -38,7 +138,7 @@ pub fn redundant(
 
 	let mut current = 0;
 	let mut render_countdown = CountdownTimer::new(Duration::from_secs(1));
-	let mut snapshotting_countdown = CountdownTimer::new(Duration::from_secs(60));
+	let mut snapshotting_countdown = CountdownTimer::new(Duration::from_mins(1));
 
 	while let Some(file) = calculator.next() {
 		let path = file?.meta.path();
@@ -46,27 +146,25 @@ pub fn redundant(
 		if render_countdown.passed() {
 			let percent = percentage(current, total);
 			clear_line();
-			print!("Processed {current} of {total} entries ({percent})...: {path}");
-			io::stdout().flush().unwrap();
+			eprint!("Processed {current} of {total} entries ({percent})...: {path}");
+			io::stderr().flush().unwrap();
 		}
 		if snapshotting_countdown.passed() && calculator.index_mut().root_mut().dirty() {
 			clear_line();
-			println!("Snapshotting index...");
+			eprintln!("Snapshotting index...");
 			calculator.index_mut().root_mut().save(index_file)?;
 		}
 	}
 
 	clear_line();
-	println!("Gathering duplicates...");
-	let duplicates = index.duplicates(allowlist);
+	eprintln!("Gathering duplicates...");
+	let duplicates = index.duplicates(allowlist, &DuplicateOptions::default());
 
-	if duplicates.is_empty() {
-		println!("No duplicates found");
-	} else {
+	let mut records = Vec::new();
+	if !duplicates.is_empty() {
 		let files = duplicates.clone().into_iter().flatten().cloned().collect();
 		// TODO: Might need to insert empty directories too.
 		let duplicate_index = RootIndex::with_files(files);
-		println!("Redundant files: ");
 		for dir in &duplicate_index.dirs {
 			let Some(sub_index_original) = index.sub_index(dir.meta.path()) else {
 				continue;
@@ -75,36 +173,60 @@ pub fn redundant(
 				continue;
 			};
 			if sub_index_original.matches(&sub_index_duplicate) {
-				println!("- {}", dir.meta.path());
+				let mut record = RedundantDirRecord {
+					dir: dir.meta.path().to_string(),
+					files: Vec::new(),
+					dirs: Vec::new(),
+				};
 				for file in sub_index_duplicate.files {
 					'duplicate_finder: for duplicate_list in &duplicates {
 						for duplicate in duplicate_list {
 							if file.meta.path() == duplicate.meta.path() {
-								println!(
-									"  - duplicate {}: {:?}",
-									file.meta.path(),
-									duplicate_list
+								record.files.push(RedundantFileRecord {
+									path: file.meta.path().to_string(),
+									duplicates: duplicate_list
 										.iter()
 										.map(|file| file.meta.path())
 										.filter(|path| path != &file.meta.path())
-										.collect::<Vec<_>>()
-								);
+										.map(str::to_string)
+										.collect(),
+								});
 								break 'duplicate_finder;
 							}
 						}
 					}
 				}
 				for dir in sub_index_duplicate.dirs {
-					println!("  - dir {}", dir.meta.path());
+					record.dirs.push(dir.meta.path().to_string());
 				}
+				records.push(record);
+			}
+		}
+	}
+
+	if let Some(script) = script {
+		print!("{}", redundant_script(&records, &index, script).text());
+	} else if format != OutputFormat::Text {
+		print_records(format, &records)?;
+	} else if duplicates.is_empty() {
+		println!("No duplicates found");
+	} else {
+		println!("Redundant files: ");
+		for record in &records {
+			println!("- {}", record.dir);
+			for file in &record.files {
+				println!("  - duplicate {}: {:?}", file.path, file.duplicates);
+			}
+			for dir in &record.dirs {
+				println!("  - dir {dir}");
 			}
 		}
 	}
 
 	if index.dirty() {
-		println!("Updating index with checksums...");
+		eprintln!("Updating index with checksums...");
 		index.save(index_file)?;
 	}
 
-	Ok(())
+	Ok(!records.is_empty())
 }
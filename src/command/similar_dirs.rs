@@ -0,0 +1,135 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Serialize;
+
+use super::calculate_checksums;
+use crate::index::model::File;
+use crate::index::Allowlist;
+use crate::index::RootIndex;
+use crate::util::display::percentage;
+use crate::util::output::print_records;
+use crate::util::output::OutputFormat;
+use crate::util::output::Record;
+
+/// Serialized form of a pair of dirs which share most of their contents.
+#[derive(Serialize)]
+pub struct SimilarDirsRecord<'a> {
+	dir: &'a str,
+	other: &'a str,
+	/// Bytes of the distinct contents found in both dirs.
+	shared_bytes: u128,
+	/// Bytes of the distinct contents found in either dir.
+	total_bytes: u128,
+	/// Files in `dir` whose contents are not in `other`.
+	only_in_dir: Vec<&'a str>,
+	/// Files in `other` whose contents are not in `dir`.
+	only_in_other: Vec<&'a str>,
+}
+
+impl Record for SimilarDirsRecord<'_> {
+	const CSV_HEADER: &'static [&'static str] = &[
+		"dir",
+		"other",
+		"shared_bytes",
+		"total_bytes",
+		"only_in",
+		"path",
+	];
+
+	fn csv_rows(&self) -> Vec<Vec<String>> {
+		let row = |only_in: &str, path: &str| {
+			vec![
+				self.dir.to_string(),
+				self.other.to_string(),
+				self.shared_bytes.to_string(),
+				self.total_bytes.to_string(),
+				only_in.to_string(),
+				path.to_string(),
+			]
+		};
+		let mut rows: Vec<_> = self
+			.only_in_dir
+			.iter()
+			.map(|path| row("dir", path))
+			.chain(self.only_in_other.iter().map(|path| row("other", path)))
+			.collect();
+		if rows.is_empty() {
+			rows.push(row("", ""));
+		}
+		rows
+	}
+}
+
+fn paths<'a>(files: &[&'a File]) -> Vec<&'a str> {
+	files.iter().map(|file| file.meta.path()).collect()
+}
+
+// Returns whether any similar dirs were found.
+#[allow(clippy::fn_params_excessive_bools)]
+pub fn similar_dirs(
+	index_file: &PathBuf,
+	allowlist: &Allowlist,
+	threshold: u8,
+	match_name: bool,
+	match_created: bool,
+	match_modified: bool,
+	format: OutputFormat,
+) -> Result<bool> {
+	eprintln!("Opening index file...");
+	let mut index = RootIndex::open(index_file)
+		.with_context(|| format!("Unable to open index: {}", index_file.display()))?;
+	calculate_checksums(
+		&mut index,
+		index_file,
+		false,
+		allowlist,
+		match_name,
+		match_created,
+		match_modified,
+	)?;
+
+	eprintln!("Gathering similar dirs...");
+	let similar = index.similar_dirs(allowlist, threshold);
+	let records: Vec<_> = similar
+		.iter()
+		.map(|similar| {
+			SimilarDirsRecord {
+				dir: similar.dir.meta.path(),
+				other: similar.other.meta.path(),
+				shared_bytes: similar.shared_size,
+				total_bytes: similar.total_size,
+				only_in_dir: paths(&similar.only_in_dir),
+				only_in_other: paths(&similar.only_in_other),
+			}
+		})
+		.collect();
+
+	if format != OutputFormat::Text {
+		print_records(format, &records)?;
+	} else if records.is_empty() {
+		println!("No similar dirs found");
+	} else {
+		for record in &records {
+			let percent = percentage(
+				usize::try_from(record.shared_bytes).unwrap_or(usize::MAX),
+				usize::try_from(record.total_bytes).unwrap_or(usize::MAX),
+			);
+			println!("{} and {} are {percent} the same:", record.dir, record.other);
+			for path in &record.only_in_dir {
+				println!("- {path}");
+			}
+			for path in &record.only_in_other {
+				println!("+ {path}");
+			}
+		}
+	}
+
+	let found = !records.is_empty();
+	if index.dirty() {
+		eprintln!("Updating index with checksums...");
+		index.save(index_file)?;
+	}
+	Ok(found)
+}
@@ -1,21 +1,245 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fs;
 use std::io;
+use std::io::Read;
 use std::io::Write;
 use std::path::PathBuf;
 use std::time::Duration;
+use std::time::SystemTime;
 
 use anyhow::bail;
 use anyhow::Context;
 use anyhow::Result;
+use serde::Serialize;
 
-use crate::index::Index;
+use crate::index::age_buckets;
+use crate::index::content_type_buckets;
+use crate::index::dir_growth;
+use crate::index::extension_buckets;
+use crate::index::model::resolve_lossy_path;
+use crate::index::model::File;
+use crate::index::new_extensions;
+use crate::index::size_buckets;
+use crate::index::sniff_content_type;
+use crate::index::total_growth;
+use crate::index::Allowlist;
+use crate::index::Bucket;
+use crate::index::DirGrowth;
 use crate::index::RootIndex;
+use crate::index::SubIndex;
+use crate::index::SNIFF_LEN;
+use crate::util::display::format_size;
+use crate::util::display::percentage;
+use crate::util::output::print_records;
+use crate::util::output::OutputFormat;
+use crate::util::output::Record;
 use crate::util::terminal::clear_line;
 use crate::util::timer::CountdownTimer;
 
+/// Serialized form of the statistics of an index.
+#[derive(Serialize)]
+pub struct StatsRecord {
+	entries: usize,
+	files: usize,
+	dirs: usize,
+	bytes: u128,
+	/// Bytes of files which share their contents with another file through a link.
+	linked_bytes: u128,
+}
+
+impl Record for StatsRecord {
+	const CSV_HEADER: &'static [&'static str] = &[
+		"entries",
+		"files",
+		"dirs",
+		"bytes",
+		"linked_bytes",
+	];
+
+	fn csv_rows(&self) -> Vec<Vec<String>> {
+		vec![
+			vec![
+				self.entries.to_string(),
+				self.files.to_string(),
+				self.dirs.to_string(),
+				self.bytes.to_string(),
+				self.linked_bytes.to_string(),
+			],
+		]
+	}
+}
+
+/// Serialized form of a group of files in a breakdown of an index. The `total` group also has the
+/// rest of the stats of the index.
+#[derive(Serialize)]
+pub struct StatsBucketRecord<'a> {
+	/// One of `total`, `extension`, `content_type`, `size` or `age`.
+	group: &'static str,
+	bucket: &'a str,
+	files: usize,
+	bytes: u128,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	entries: Option<usize>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	dirs: Option<usize>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	linked_bytes: Option<u128>,
+}
+
+impl Record for StatsBucketRecord<'_> {
+	const CSV_HEADER: &'static [&'static str] = &[
+		"group",
+		"bucket",
+		"files",
+		"bytes",
+		"entries",
+		"dirs",
+		"linked_bytes",
+	];
+
+	fn csv_rows(&self) -> Vec<Vec<String>> {
+		vec![
+			vec![
+				self.group.to_string(),
+				self.bucket.to_string(),
+				self.files.to_string(),
+				self.bytes.to_string(),
+				self.entries.map_or_else(String::new, |entries| entries.to_string()),
+				self.dirs.map_or_else(String::new, |dirs| dirs.to_string()),
+				self.linked_bytes.map_or_else(String::new, |bytes| bytes.to_string()),
+			],
+		]
+	}
+}
+
+/// Serialized form of how the files of an index, a dir or an extension changed between two indexes.
+#[derive(Serialize)]
+pub struct StatsChangeRecord<'a> {
+	/// One of `total`, `dir` or `extension`. Extensions are only listed when they are new.
+	kind: &'static str,
+	name: &'a str,
+	old_files: usize,
+	new_files: usize,
+	old_bytes: u128,
+	new_bytes: u128,
+	added_files: usize,
+	removed_files: usize,
+	added_bytes: u128,
+	removed_bytes: u128,
+}
+
+impl<'a> StatsChangeRecord<'a> {
+	fn from_growth(kind: &'static str, name: &'a str, growth: &DirGrowth<'_>) -> Self {
+		Self {
+			kind,
+			name,
+			old_files: growth.old_files,
+			new_files: growth.new_files,
+			old_bytes: growth.old_size,
+			new_bytes: growth.new_size,
+			added_files: growth.added_files,
+			removed_files: growth.removed_files,
+			added_bytes: growth.added_bytes,
+			removed_bytes: growth.removed_bytes,
+		}
+	}
+}
+
+impl Record for StatsChangeRecord<'_> {
+	const CSV_HEADER: &'static [&'static str] = &[
+		"kind",
+		"name",
+		"old_files",
+		"new_files",
+		"old_bytes",
+		"new_bytes",
+		"added_files",
+		"removed_files",
+		"added_bytes",
+		"removed_bytes",
+	];
+
+	fn csv_rows(&self) -> Vec<Vec<String>> {
+		vec![
+			vec![
+				self.kind.to_string(),
+				self.name.to_string(),
+				self.old_files.to_string(),
+				self.new_files.to_string(),
+				self.old_bytes.to_string(),
+				self.new_bytes.to_string(),
+				self.added_files.to_string(),
+				self.removed_files.to_string(),
+				self.added_bytes.to_string(),
+				self.removed_bytes.to_string(),
+			],
+		]
+	}
+}
+
+// Returns the content type of a file from its first bytes, or `unreadable` if it cannot be read.
+fn read_content_type(path: &str) -> &'static str {
+	let Some(path) = resolve_lossy_path(path) else {
+		return "unreadable";
+	};
+	let mut bytes = Vec::with_capacity(SNIFF_LEN);
+	let read =
+		fs::File::open(path).and_then(|file| file.take(SNIFF_LEN as u64).read_to_end(&mut bytes));
+	if read.is_err() {
+		return "unreadable";
+	}
+	sniff_content_type(&bytes)
+}
+
+// Returns the content type of each file, reading the start of each one.
+fn sniff_content_types(files: &[&File]) -> Vec<&'static str> {
+	let total = files.len();
+	let mut countdown = CountdownTimer::new(Duration::from_secs(1));
+	let mut content_types = Vec::with_capacity(total);
+	for (current, file) in files.iter().enumerate() {
+		let path = file.meta.path();
+		if countdown.passed() {
+			let percent = percentage(current, total);
+			clear_line();
+			eprint!("Sniffed {current} of {total} files ({percent})...: {path}");
+			io::stderr().flush().unwrap();
+		}
+		content_types.push(read_content_type(path));
+	}
+	clear_line();
+	content_types
+}
+
+// Groups files by extension, size and age, and by content type if sniffing.
+fn breakdown_groups(files: &[&File], sniff: bool) -> Vec<(&'static str, Vec<Bucket>)> {
+	let mut groups = vec![("extension", extension_buckets(files))];
+	if sniff {
+		let content_types = sniff_content_types(files);
+		groups.push(("content_type", content_type_buckets(files, &content_types)));
+	}
+	groups.push(("size", size_buckets(files)));
+	groups.push(("age", age_buckets(files, SystemTime::now())));
+	groups
+}
+
+fn print_buckets(title: &str, buckets: &[Bucket]) {
+	println!("By {title}:");
+	for bucket in buckets {
+		println!("- {}: {} files, {}", bucket.name, bucket.files, format_size(bucket.size));
+	}
+}
+
+// Prints the statistics of the allowed entries. With a breakdown, files are also grouped by
+// extension, size and age, and by the content type sniffed from their first bytes if requested.
 pub fn stats(
 	src: Option<&PathBuf>,
 	index_file: Option<&PathBuf>,
 	dir: Option<&PathBuf>,
+	allowlist: &Allowlist,
+	breakdown: bool,
+	sniff: bool,
+	format: OutputFormat,
 ) -> Result<()> {
 	let mut current = 0usize;
 	let mut countdown = CountdownTimer::new(Duration::from_secs(1));
@@ -24,8 +248,8 @@ pub fn stats(
 		last_path = path.to_string();
 		if countdown.passed() {
 			clear_line();
-			print!("Discovered {current} entries: {path}");
-			io::stdout().flush().unwrap();
+			eprint!("Discovered {current} entries: {path}");
+			io::stderr().flush().unwrap();
 		}
 		current += 1;
 	};
@@ -52,20 +276,184 @@ pub fn stats(
 	} else {
 		index.all()
 	};
-	let count = sub_index.entry_count();
-	println!("Found {count} total entries!");
-	let file_count = sub_index.file_count();
-	println!("{file_count} files.");
-	let dir_count = sub_index.dir_count();
-	println!("{dir_count} directories.");
-	let dir_count = sub_index.file_size();
-	println!("{dir_count} bytes.");
+	let files: Vec<_> =
+		sub_index.files.iter().filter(|file| allowlist.is_allowed(&file.meta.path)).collect();
+	let dir_count =
+		sub_index.dirs.iter().filter(|dir| allowlist.is_allowed(&dir.meta.path)).count();
+	let record = StatsRecord {
+		entries: files.len() + dir_count,
+		files: files.len(),
+		dirs: dir_count,
+		bytes: files.iter().map(|file| u128::from(file.size)).sum(),
+		linked_bytes: files
+			.iter()
+			.filter(|file| file.linked.is_some())
+			.map(|file| u128::from(file.size))
+			.sum(),
+	};
+
+	let groups = if breakdown {
+		breakdown_groups(&files, sniff)
+	} else {
+		Vec::new()
+	};
+
+	if format == OutputFormat::Text {
+		println!("Found {} total entries!", record.entries);
+		println!("{} files.", record.files);
+		println!("{} directories.", record.dirs);
+		println!("{} bytes.", record.bytes);
+		if record.linked_bytes > 0 {
+			println!("{} bytes saved by links.", record.linked_bytes);
+		}
+		for (group, buckets) in &groups {
+			print_buckets(&group.replace('_', " "), buckets);
+		}
+	} else if breakdown {
+		let total = StatsBucketRecord {
+			group: "total",
+			bucket: "all",
+			files: record.files,
+			bytes: record.bytes,
+			entries: Some(record.entries),
+			dirs: Some(record.dirs),
+			linked_bytes: Some(record.linked_bytes),
+		};
+		let buckets = groups.iter().flat_map(|(group, buckets)| {
+			buckets.iter().map(|bucket| {
+				StatsBucketRecord {
+					group,
+					bucket: &bucket.name,
+					files: bucket.files,
+					bytes: bucket.size,
+					entries: None,
+					dirs: None,
+					linked_bytes: None,
+				}
+			})
+		});
+		let records: Vec<_> = [total].into_iter().chain(buckets).collect();
+		print_records(format, &records)?;
+	} else {
+		print_records(format, &[record])?;
+	}
 
 	if let Some(path) = index_file {
 		if index.dirty() {
-			println!("Saving index...");
+			eprintln!("Saving index...");
 			index.save(path)?;
 		}
 	}
 	Ok(())
 }
+
+// Formats the bytes and files added and removed, followed by the old and new totals.
+fn format_growth(growth: &DirGrowth<'_>) -> String {
+	format!(
+		"+{} / -{}, +{} / -{} files ({} to {}, {} to {} files)",
+		format_size(growth.added_bytes),
+		format_size(growth.removed_bytes),
+		growth.added_files,
+		growth.removed_files,
+		format_size(growth.old_size),
+		format_size(growth.new_size),
+		growth.old_files,
+		growth.new_files
+	)
+}
+
+fn print_growth(title: &str, dirs: &[&DirGrowth<'_>]) {
+	if dirs.is_empty() {
+		return;
+	}
+	println!("{title}:");
+	for dir in dirs {
+		println!("- {}: {}", dir.path, format_growth(dir));
+	}
+}
+
+// Prints how an index changed since an older one: the totals, the folders in which the most bytes
+// were added and removed, and the extensions which no older file had. Files are matched by path
+// only, without comparing checksums, so a moved file counts as removed and added.
+pub fn compare_stats(
+	index_file: &PathBuf,
+	compare: &PathBuf,
+	dir: Option<&PathBuf>,
+	top: usize,
+	format: OutputFormat,
+) -> Result<()> {
+	eprintln!("Opening index files...");
+	let old_index = RootIndex::open(index_file)
+		.with_context(|| format!("Unable to open index: {}", index_file.display()))?;
+	let new_index = RootIndex::open(compare)
+		.with_context(|| format!("Unable to open index: {}", compare.display()))?;
+
+	let (old, new) = if let Some(dir) = dir {
+		// A dir missing from one index counts as empty in it.
+		let empty = || {
+			SubIndex {
+				files: &[],
+				dirs: &[],
+			}
+		};
+		match (old_index.sub_index(dir), new_index.sub_index(dir)) {
+			(None, None) => bail!("Invalid directory"),
+			(old, new) => (old.unwrap_or_else(empty), new.unwrap_or_else(empty)),
+		}
+	} else {
+		(old_index.all(), new_index.all())
+	};
+
+	let total = total_growth(&old, &new);
+	let growth = dir_growth(&old, &new);
+	// Sorting is stable, so ties stay sorted by path.
+	let mut growing: Vec<_> = growth.iter().filter(|dir| dir.added_bytes > 0).collect();
+	growing.sort_by_key(|dir| Reverse(dir.added_bytes));
+	growing.truncate(top);
+	let mut shrinking: Vec<_> = growth.iter().filter(|dir| dir.removed_bytes > 0).collect();
+	shrinking.sort_by_key(|dir| Reverse(dir.removed_bytes));
+	shrinking.truncate(top);
+	let old_files: Vec<_> = old.files.iter().collect();
+	let new_files: Vec<_> = new.files.iter().collect();
+	let extensions = new_extensions(&old_files, &new_files);
+
+	if format == OutputFormat::Text {
+		println!("Total: {}.", format_growth(&total));
+		print_growth("Top growing folders, by bytes added", &growing);
+		print_growth("Top shrinking folders, by bytes removed", &shrinking);
+		if !extensions.is_empty() {
+			println!("New extensions:");
+			for bucket in &extensions {
+				println!("- {}: {} files, {}", bucket.name, bucket.files, format_size(bucket.size));
+			}
+		}
+		return Ok(());
+	}
+
+	let total = StatsChangeRecord::from_growth("total", "all", &total);
+	// A dir can be both among the growing and the shrinking ones, but is listed once.
+	let mut listed = HashSet::new();
+	let dirs = growing
+		.iter()
+		.chain(&shrinking)
+		.filter(|dir| listed.insert(dir.path))
+		.map(|dir| StatsChangeRecord::from_growth("dir", dir.path, dir));
+	// Files with a new extension cannot have been in the older index, so they were all added.
+	let extensions = extensions.iter().map(|bucket| {
+		StatsChangeRecord {
+			kind: "extension",
+			name: &bucket.name,
+			old_files: 0,
+			new_files: bucket.files,
+			old_bytes: 0,
+			new_bytes: bucket.size,
+			added_files: bucket.files,
+			removed_files: 0,
+			added_bytes: bucket.size,
+			removed_bytes: 0,
+		}
+	});
+	let records: Vec<_> = [total].into_iter().chain(dirs).chain(extensions).collect();
+	print_records(format, &records)?;
+	Ok(())
+}
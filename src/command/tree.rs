@@ -0,0 +1,180 @@
+use std::path::PathBuf;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::index::dir_usages;
+use crate::index::largest_files;
+use crate::index::tree_order;
+use crate::index::Index;
+use crate::index::RootIndex;
+use crate::index::TreeSort;
+use crate::util::display::format_size;
+use crate::util::display::percentage;
+use crate::util::output::print_records;
+use crate::util::output::OutputFormat;
+use crate::util::output::Record;
+
+/// Serialized form of a dir in a tree, or of one of the largest files.
+#[derive(Serialize)]
+pub struct TreeEntryRecord<'a> {
+	/// Either `file` or `dir`.
+	kind: &'static str,
+	path: &'a str,
+	/// Depth of a dir in the tree, starting at 0.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	depth: Option<usize>,
+	bytes: u128,
+	/// Files inside a dir, recursively.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	files: Option<usize>,
+	/// Dirs inside a dir, recursively.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	dirs: Option<usize>,
+	/// Bytes of the parent dir, or of the whole tree for the top dirs.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	parent_bytes: Option<u128>,
+}
+
+impl Record for TreeEntryRecord<'_> {
+	const CSV_HEADER: &'static [&'static str] = &[
+		"kind",
+		"path",
+		"depth",
+		"bytes",
+		"files",
+		"dirs",
+		"parent_bytes",
+	];
+
+	fn csv_rows(&self) -> Vec<Vec<String>> {
+		let optional = |value: Option<String>| value.unwrap_or_default();
+		vec![
+			vec![
+				self.kind.to_string(),
+				self.path.to_string(),
+				optional(self.depth.map(|depth| depth.to_string())),
+				self.bytes.to_string(),
+				optional(self.files.map(|files| files.to_string())),
+				optional(self.dirs.map(|dirs| dirs.to_string())),
+				optional(self.parent_bytes.map(|bytes| bytes.to_string())),
+			],
+		]
+	}
+}
+
+fn print_text(records: &[TreeEntryRecord<'_>], top: bool) {
+	if top {
+		println!("Largest dirs:");
+	}
+	let mut printed_files = false;
+	for record in records {
+		let size = format_size(record.bytes);
+		if record.kind == "file" {
+			if !printed_files {
+				println!("Largest files:");
+				printed_files = true;
+			}
+			println!("- {}: {size}", record.path);
+			continue;
+		}
+		let counts = format!(
+			"{} files, {} dirs",
+			record.files.unwrap_or_default(),
+			record.dirs.unwrap_or_default()
+		);
+		let share = match record.parent_bytes {
+			Some(parent_bytes) if parent_bytes > 0 => {
+				let percent = percentage(
+					usize::try_from(record.bytes).unwrap_or(usize::MAX),
+					usize::try_from(parent_bytes).unwrap_or(usize::MAX),
+				);
+				format!(" ({percent})")
+			}
+			_ => String::new(),
+		};
+		if top {
+			println!("- {}/: {size}, {counts}", record.path);
+		} else {
+			let depth = record.depth.unwrap_or_default();
+			// Nested dirs are shown by name under their parent.
+			let name = if depth == 0 {
+				record.path
+			} else {
+				record.path.rsplit('/').next().unwrap_or(record.path)
+			};
+			println!("{}{name}/: {size}{share}, {counts}", "  ".repeat(depth));
+		}
+	}
+}
+
+// Prints the recursive size and counts of each dir in the index or inside the given dir, down to
+// the maximum depth. If a count is given, the largest dirs and files are printed instead.
+pub fn tree(
+	index_file: &PathBuf,
+	dir: Option<&PathBuf>,
+	max_depth: Option<usize>,
+	sort: TreeSort,
+	top: Option<usize>,
+	format: OutputFormat,
+) -> Result<()> {
+	eprintln!("Opening index file...");
+	let index = RootIndex::open(index_file)
+		.with_context(|| format!("Unable to open index: {}", index_file.display()))?;
+	let sub_index = if let Some(dir) = dir {
+		let Some(sub_index) = index.sub_index(dir) else {
+			bail!("Invalid directory");
+		};
+		sub_index
+	} else {
+		index.all()
+	};
+
+	let usages = dir_usages(&sub_index);
+	let dir_record = |usage_index: usize| {
+		let usage = &usages[usage_index];
+		TreeEntryRecord {
+			kind: "dir",
+			path: usage.dir.meta.path(),
+			depth: Some(usage.depth),
+			bytes: usage.size,
+			files: Some(usage.file_count),
+			dirs: Some(usage.dir_count),
+			parent_bytes: Some(
+				usage.parent.map_or_else(|| sub_index.file_size(), |parent| usages[parent].size),
+			),
+		}
+	};
+	let records: Vec<_> = if let Some(count) = top {
+		let mut largest_dirs: Vec<_> = (0..usages.len()).collect();
+		largest_dirs.sort_by_key(|usage_index| std::cmp::Reverse(usages[*usage_index].size));
+		largest_dirs.truncate(count);
+		let files = largest_files(&sub_index, count).into_iter().map(|file| {
+			TreeEntryRecord {
+				kind: "file",
+				path: file.meta.path(),
+				depth: None,
+				bytes: u128::from(file.size),
+				files: None,
+				dirs: None,
+				parent_bytes: None,
+			}
+		});
+		largest_dirs.into_iter().map(dir_record).chain(files).collect()
+	} else {
+		tree_order(&usages, sort, max_depth).into_iter().map(dir_record).collect()
+	};
+
+	if format == OutputFormat::Text {
+		if records.is_empty() {
+			println!("No dirs found");
+		} else {
+			print_text(&records, top.is_some());
+		}
+	} else {
+		print_records(format, &records)?;
+	}
+	Ok(())
+}
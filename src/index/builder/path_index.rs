@@ -40,6 +40,7 @@ impl PathIndexBuilder {
 			},
 			size: 0,
 			checksum: Checksum::new(),
+			linked: None,
 		};
 		if let Some(parent) = file.meta.parent() {
 			self.add_dir(parent);
@@ -64,6 +65,7 @@ impl PathIndexBuilder {
 				modified_time: SystemTime::UNIX_EPOCH,
 				hidden: false,
 			},
+			checksum: Checksum::new(),
 		};
 		if let Some(parent) = dir.meta.parent() {
 			self.add_dir(parent);
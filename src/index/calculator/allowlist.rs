@@ -7,7 +7,6 @@ pub struct Allowlist {
 }
 
 impl Allowlist {
-	#[cfg(test)]
 	pub(crate) fn allow_all() -> Self {
 		Self {
 			allow: Vec::new(),
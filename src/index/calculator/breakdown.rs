@@ -0,0 +1,165 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use crate::index::model::File;
+
+/// Files grouped under a name, such as an extension or a range of sizes.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Bucket {
+	pub name: String,
+	pub files: usize,
+	pub size: u128,
+}
+
+impl Bucket {
+	fn new(name: impl Into<String>) -> Self {
+		Self {
+			name: name.into(),
+			files: 0,
+			size: 0,
+		}
+	}
+
+	fn add(&mut self, file: &File) {
+		self.files += 1;
+		self.size += u128::from(file.size);
+	}
+}
+
+// Groups files by their names, from the largest group, with ties sorted by name.
+fn buckets_by<'a>(named_files: impl IntoIterator<Item = (String, &'a File)>) -> Vec<Bucket> {
+	let mut buckets = HashMap::<String, Bucket>::new();
+	for (name, file) in named_files {
+		buckets.entry(name.clone()).or_insert_with(|| Bucket::new(name)).add(file);
+	}
+	let mut buckets: Vec<_> = buckets.into_values().collect();
+	buckets.sort_by(|lhs, rhs| {
+		Reverse(lhs.size).cmp(&Reverse(rhs.size)).then(lhs.name.cmp(&rhs.name))
+	});
+	buckets
+}
+
+// Groups files into ranges, in the given order. Each range has the exclusive upper bound of its
+// values, and values past the last range go in the last one.
+fn buckets_in_ranges<T: PartialOrd>(
+	files: &[&File],
+	ranges: &[(&str, T)],
+	value: impl Fn(&File) -> T,
+) -> Vec<Bucket> {
+	let mut buckets: Vec<_> = ranges.iter().map(|(name, _)| Bucket::new(*name)).collect();
+	for file in files {
+		let value = value(file);
+		let range_index =
+			ranges.iter().position(|(_, end)| value < *end).unwrap_or(ranges.len() - 1);
+		buckets[range_index].add(file);
+	}
+	buckets
+}
+
+/// Returns the lowercase extension of a name, or `None` for names without one such as `.bashrc`.
+pub fn extension(name: &str) -> Option<String> {
+	match name.rsplit_once('.') {
+		Some((stem, extension)) if !stem.is_empty() && !extension.is_empty() => {
+			Some(extension.to_lowercase())
+		}
+		_ => None,
+	}
+}
+
+/// Groups files by extension, from the largest group.
+pub fn extension_buckets(files: &[&File]) -> Vec<Bucket> {
+	buckets_by(
+		files.iter().map(|file| {
+			(extension(file.meta.name()).unwrap_or_else(|| "(none)".to_string()), *file)
+		}),
+	)
+}
+
+/// Groups files by content type, from the largest group, given the content type of each file.
+pub fn content_type_buckets(files: &[&File], content_types: &[&str]) -> Vec<Bucket> {
+	buckets_by(content_types.iter().map(ToString::to_string).zip(files.iter().copied()))
+}
+
+const KIB: u64 = 1024;
+const MIB: u64 = 1024 * KIB;
+const GIB: u64 = 1024 * MIB;
+
+/// Groups files into ranges of sizes, from the smallest.
+pub fn size_buckets(files: &[&File]) -> Vec<Bucket> {
+	let ranges = [
+		("empty", 1),
+		("< 1 KiB", KIB),
+		("< 1 MiB", MIB),
+		("< 100 MiB", 100 * MIB),
+		("< 1 GiB", GIB),
+		(">= 1 GiB", u64::MAX),
+	];
+	buckets_in_ranges(files, &ranges, |file| file.size)
+}
+
+/// Groups files into ranges of time since they were modified, from the newest. Files modified after
+/// now count as the newest.
+pub fn age_buckets(files: &[&File], now: SystemTime) -> Vec<Bucket> {
+	const DAY: u64 = 24 * 60 * 60;
+	let ranges = [
+		("< 1 day", Duration::from_secs(DAY)),
+		("< 1 week", Duration::from_secs(7 * DAY)),
+		("< 30 days", Duration::from_secs(30 * DAY)),
+		("< 1 year", Duration::from_secs(365 * DAY)),
+		("< 5 years", Duration::from_secs(5 * 365 * DAY)),
+		(">= 5 years", Duration::MAX),
+	];
+	buckets_in_ranges(files, &ranges, |file| {
+		now.duration_since(file.meta.modified_time()).unwrap_or_default()
+	})
+}
+
+/// Number of bytes read from the start of a file to sniff its content type.
+pub const SNIFF_LEN: usize = 512;
+
+/// Returns the content type of a file from the magic bytes at its start.
+pub fn sniff_content_type(bytes: &[u8]) -> &'static str {
+	const SIGNATURES: &[(usize, &[u8], &str)] = &[
+		(0, b"\xFF\xD8\xFF", "image/jpeg"),
+		(0, b"\x89PNG\r\n\x1A\n", "image/png"),
+		(0, b"GIF8", "image/gif"),
+		(0, b"BM", "image/bmp"),
+		(0, b"II*\0", "image/tiff"),
+		(0, b"MM\0*", "image/tiff"),
+		(8, b"WEBP", "image/webp"),
+		// HEIC files also start with an `ftyp` box, so their brand is checked first.
+		(8, b"heic", "image/heic"),
+		(4, b"ftyp", "video/mp4"),
+		(8, b"AVI ", "video/x-msvideo"),
+		(0, b"\x1A\x45\xDF\xA3", "video/x-matroska"),
+		(8, b"WAVE", "audio/wav"),
+		(0, b"ID3", "audio/mpeg"),
+		(0, b"fLaC", "audio/flac"),
+		(0, b"OggS", "audio/ogg"),
+		(0, b"%PDF-", "application/pdf"),
+		(0, b"PK\x03\x04", "application/zip"),
+		(0, b"\x1F\x8B", "application/gzip"),
+		(0, b"7z\xBC\xAF\x27\x1C", "application/x-7z-compressed"),
+		(0, b"Rar!\x1A\x07", "application/vnd.rar"),
+		(0, b"SQLite format 3\0", "application/vnd.sqlite3"),
+		(0, b"\x7FELF", "application/x-elf"),
+		(0, b"MZ", "application/x-msdownload"),
+	];
+	if bytes.is_empty() {
+		return "empty";
+	}
+	for (offset, signature, content_type) in SIGNATURES {
+		if bytes.get(*offset..offset + signature.len()) == Some(signature) {
+			return content_type;
+		}
+	}
+	// A multi-byte character may be cut off at the end of the sniffed bytes, which has no error
+	// length.
+	let utf8 = std::str::from_utf8(bytes).map_or_else(|e| e.error_len().is_none(), |_| true);
+	if utf8 && !bytes.contains(&0) {
+		return "text/plain";
+	}
+	"application/octet-stream"
+}
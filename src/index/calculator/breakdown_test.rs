@@ -0,0 +1,90 @@
+use std::time::Duration;
+use std::time::SystemTime;
+
+use super::age_buckets;
+use super::extension;
+use super::extension_buckets;
+use super::size_buckets;
+use super::sniff_content_type;
+use super::test_fixtures::create_file;
+use super::Bucket;
+
+const DAY: u64 = 24 * 60 * 60;
+
+fn summary(buckets: &[Bucket]) -> Vec<(&str, usize, u128)> {
+	buckets.iter().map(|bucket| (bucket.name.as_str(), bucket.files, bucket.size)).collect()
+}
+
+#[test]
+fn extensions() {
+	assert_eq!(extension("a.JPG"), Some("jpg".to_string()));
+	assert_eq!(extension("a.tar.gz"), Some("gz".to_string()));
+	assert_eq!(extension(".bashrc"), None);
+	assert_eq!(extension("Makefile"), None);
+	assert_eq!(extension("a."), None);
+
+	let files = [
+		create_file("d/a.jpg", 10, 0, ""),
+		create_file("d/b.JPG", 20, 0, ""),
+		create_file("d/c.txt", 40, 0, ""),
+		create_file("d/.bashrc", 1, 0, ""),
+	];
+	let files: Vec<_> = files.iter().collect();
+	assert_eq!(
+		summary(&extension_buckets(&files)),
+		vec![
+			("txt", 1, 40),
+			("jpg", 2, 30),
+			("(none)", 1, 1)
+		]
+	);
+}
+
+#[test]
+fn size_and_age_ranges() {
+	let now = SystemTime::UNIX_EPOCH + Duration::from_secs(10 * 365 * DAY);
+	let files = [
+		create_file("a", 0, 10 * 365 * DAY, ""),
+		create_file("b", 1023, 10 * 365 * DAY - 2 * DAY, ""),
+		create_file("c", 1024, 0, ""),
+		// Modified after now, such as with a wrong clock.
+		create_file("d", 5 << 30, 11 * 365 * DAY, ""),
+	];
+	let files: Vec<_> = files.iter().collect();
+	assert_eq!(
+		summary(&size_buckets(&files)),
+		vec![
+			("empty", 1, 0),
+			("< 1 KiB", 1, 1023),
+			("< 1 MiB", 1, 1024),
+			("< 100 MiB", 0, 0),
+			("< 1 GiB", 0, 0),
+			(">= 1 GiB", 1, 5 << 30),
+		]
+	);
+	let ages: Vec<_> = age_buckets(&files, now).iter().map(|bucket| bucket.files).collect();
+	assert_eq!(
+		ages,
+		vec![
+			2,
+			1,
+			0,
+			0,
+			0,
+			1
+		]
+	);
+}
+
+#[test]
+fn sniffed_content_types() {
+	assert_eq!(sniff_content_type(b""), "empty");
+	assert_eq!(sniff_content_type(b"\xFF\xD8\xFF\xE0rest"), "image/jpeg");
+	assert_eq!(sniff_content_type(b"\0\0\0\x18ftypheic"), "image/heic");
+	assert_eq!(sniff_content_type(b"\0\0\0\x18ftypisom"), "video/mp4");
+	assert_eq!(sniff_content_type(b"RIFF\0\0\0\0WEBPVP8 "), "image/webp");
+	assert_eq!(sniff_content_type("héllo".as_bytes()), "text/plain");
+	// A character cut off by the sniffed length is still text.
+	assert_eq!(sniff_content_type(&"hé".as_bytes()[..2]), "text/plain");
+	assert_eq!(sniff_content_type(b"\x01\xFFbinary\0"), "application/octet-stream");
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use super::diff::FileStep;
+use super::diff::FileWalk;
+use super::extension;
+use super::extension_buckets;
+use super::Bucket;
+use crate::index::model::parent_str;
+use crate::index::model::File;
+use crate::index::SubIndex;
+
+/// Recursive totals of a dir in an older and a newer index, which are zero where it is missing,
+/// along with the bytes and files added and removed inside it. Files are matched by path, and a
+/// file whose size changed adds or removes the difference without being counted as added or
+/// removed.
+#[derive(Default)]
+pub struct DirGrowth<'a> {
+	pub path: &'a str,
+	pub old_size: u128,
+	pub new_size: u128,
+	pub old_files: usize,
+	pub new_files: usize,
+	pub added_bytes: u128,
+	pub removed_bytes: u128,
+	pub added_files: usize,
+	pub removed_files: usize,
+}
+
+impl DirGrowth<'_> {
+	fn changed(&self) -> bool {
+		self.added_bytes != 0
+			|| self.removed_bytes != 0
+			|| self.added_files != 0
+			|| self.removed_files != 0
+	}
+
+	fn add(&mut self, other: &DirGrowth<'_>) {
+		self.old_size += other.old_size;
+		self.new_size += other.new_size;
+		self.old_files += other.old_files;
+		self.new_files += other.new_files;
+		self.added_bytes += other.added_bytes;
+		self.removed_bytes += other.removed_bytes;
+		self.added_files += other.added_files;
+		self.removed_files += other.removed_files;
+	}
+}
+
+// Returns the growth of each file, walking both file lists sorted by path.
+fn file_growths<'a>(old: &'a [File], new: &'a [File]) -> Vec<DirGrowth<'a>> {
+	let mut growths = Vec::with_capacity(old.len().max(new.len()));
+	let mut walk = FileWalk::default();
+	while let Some(step) = walk.next(old, new) {
+		let growth = match step {
+			FileStep::SelfOnly(old_index) => {
+				let file = &old[old_index];
+				let size = u128::from(file.size);
+				DirGrowth {
+					path: file.meta.path(),
+					old_size: size,
+					old_files: 1,
+					removed_bytes: size,
+					removed_files: 1,
+					..DirGrowth::default()
+				}
+			}
+			FileStep::OtherOnly(new_index) => {
+				let file = &new[new_index];
+				let size = u128::from(file.size);
+				DirGrowth {
+					path: file.meta.path(),
+					new_size: size,
+					new_files: 1,
+					added_bytes: size,
+					added_files: 1,
+					..DirGrowth::default()
+				}
+			}
+			FileStep::Both(old_index, new_index) => {
+				let old_size = u128::from(old[old_index].size);
+				let new_size = u128::from(new[new_index].size);
+				DirGrowth {
+					path: new[new_index].meta.path(),
+					old_size,
+					new_size,
+					old_files: 1,
+					new_files: 1,
+					added_bytes: new_size.saturating_sub(old_size),
+					removed_bytes: old_size.saturating_sub(new_size),
+					..DirGrowth::default()
+				}
+			}
+		};
+		growths.push(growth);
+	}
+	growths
+}
+
+/// Returns the totals of every file of the indexes, with an empty path.
+pub fn total_growth<'a>(old: &SubIndex<'a>, new: &SubIndex<'a>) -> DirGrowth<'a> {
+	let mut total = DirGrowth::default();
+	for growth in file_growths(old.files, new.files) {
+		total.add(&growth);
+	}
+	total
+}
+
+/// Returns the dirs in which files were added, removed or resized between the indexes, sorted by
+/// path.
+pub fn dir_growth<'a>(old: &SubIndex<'a>, new: &SubIndex<'a>) -> Vec<DirGrowth<'a>> {
+	let mut growth_by_path: HashMap<&str, DirGrowth<'a>> = old
+		.dirs
+		.iter()
+		.chain(new.dirs)
+		.map(|dir| {
+			let path = dir.meta.path();
+			(
+				path,
+				DirGrowth {
+					path,
+					..DirGrowth::default()
+				},
+			)
+		})
+		.collect();
+	for growth in file_growths(old.files, new.files) {
+		// Adds the file to each dir containing it, up to the top dirs of the indexes.
+		let mut parent = parent_str(growth.path);
+		while let Some(dir) = parent.and_then(|path| growth_by_path.get_mut(path)) {
+			dir.add(&growth);
+			parent = parent_str(dir.path);
+		}
+	}
+
+	let mut changed: Vec<_> = growth_by_path.into_values().filter(DirGrowth::changed).collect();
+	changed.sort_by(|lhs, rhs| lhs.path.cmp(rhs.path));
+	changed
+}
+
+/// Groups the newer files by extension, keeping only the extensions which no older file has.
+pub fn new_extensions(old: &[&File], new: &[&File]) -> Vec<Bucket> {
+	let old_extensions: HashSet<_> =
+		old.iter().filter_map(|file| extension(file.meta.name())).collect();
+	let new: Vec<_> = new
+		.iter()
+		.copied()
+		.filter(|file| {
+			extension(file.meta.name())
+				.is_some_and(|extension| !old_extensions.contains(&extension))
+		})
+		.collect();
+	extension_buckets(&new)
+}
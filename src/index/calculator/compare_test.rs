@@ -0,0 +1,105 @@
+use super::dir_growth;
+use super::new_extensions;
+use super::test_fixtures::create_dir;
+use super::test_fixtures::create_file;
+use super::total_growth;
+use super::DirGrowth;
+use crate::index::SubIndex;
+
+// Returns the path, old and new sizes, bytes added and removed, and files added and removed.
+fn summary<'a>(growth: &DirGrowth<'a>) -> (&'a str, u128, u128, u128, u128, usize, usize) {
+	(
+		growth.path,
+		growth.old_size,
+		growth.new_size,
+		growth.added_bytes,
+		growth.removed_bytes,
+		growth.added_files,
+		growth.removed_files,
+	)
+}
+
+#[test]
+fn growth_per_dir() {
+	let old_files = vec![
+		create_file("r/gone/a.txt", 5, 0, ""),
+		create_file("r/same/b.txt", 10, 0, ""),
+		create_file("r/shrunk/c.txt", 20, 0, ""),
+	];
+	let old_dirs = vec![
+		create_dir("r"),
+		create_dir("r/gone"),
+		create_dir("r/same"),
+		create_dir("r/shrunk"),
+	];
+	let new_files = vec![
+		create_file("r/new/d.heic", 100, 0, ""),
+		create_file("r/same/b.txt", 10, 0, ""),
+		create_file("r/shrunk/c.txt", 2, 0, ""),
+	];
+	let new_dirs = vec![
+		create_dir("r"),
+		create_dir("r/new"),
+		create_dir("r/same"),
+		create_dir("r/shrunk"),
+	];
+	let old = SubIndex {
+		files: &old_files,
+		dirs: &old_dirs,
+	};
+	let new = SubIndex {
+		files: &new_files,
+		dirs: &new_dirs,
+	};
+	let growth: Vec<_> = dir_growth(&old, &new).iter().map(summary).collect();
+	assert_eq!(
+		growth,
+		vec![
+			("r", 35, 112, 100, 23, 1, 1),
+			("r/gone", 5, 0, 0, 5, 0, 1),
+			("r/new", 0, 100, 100, 0, 1, 0),
+			("r/shrunk", 20, 2, 0, 18, 0, 0),
+		]
+	);
+	assert_eq!(summary(&total_growth(&old, &new)), ("", 35, 112, 100, 23, 1, 1));
+
+	let old_files: Vec<_> = old_files.iter().collect();
+	let new_files: Vec<_> = new_files.iter().collect();
+	let extensions: Vec<_> = new_extensions(&old_files, &new_files)
+		.iter()
+		.map(|bucket| (bucket.name.clone(), bucket.files, bucket.size))
+		.collect();
+	assert_eq!(extensions, vec![("heic".to_string(), 1, 100)]);
+}
+
+#[test]
+fn growth_with_equal_adds_and_removes() {
+	let old_files = vec![
+		create_file("r/churn/a.txt", 50, 0, ""),
+		create_file("r/same.txt", 10, 0, ""),
+	];
+	let new_files = vec![
+		create_file("r/churn/b.txt", 50, 0, ""),
+		create_file("r/same.txt", 10, 0, ""),
+	];
+	let dirs = vec![
+		create_dir("r"),
+		create_dir("r/churn"),
+	];
+	let old = SubIndex {
+		files: &old_files,
+		dirs: &dirs,
+	};
+	let new = SubIndex {
+		files: &new_files,
+		dirs: &dirs,
+	};
+	let growth: Vec<_> = dir_growth(&old, &new).iter().map(summary).collect();
+	assert_eq!(
+		growth,
+		vec![
+			("r", 60, 60, 50, 50, 1, 1),
+			("r/churn", 50, 50, 50, 50, 1, 1),
+		]
+	);
+}
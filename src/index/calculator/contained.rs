@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use super::Allowlist;
+use crate::index::model::parent_str;
+use crate::index::model::Checksum;
+use crate::index::model::Dir;
+use crate::index::store::SliceIndex;
+use crate::index::store::SortedSliceIndexOpts;
+use crate::index::Index;
+use crate::index::SubIndex;
+
+/// A dir whose file contents all exist somewhere under another dir.
+pub struct ContainedDir<'a> {
+	pub dir: &'a Dir,
+	pub container: &'a Dir,
+	/// Size of the files in the container whose contents are also in the dir.
+	pub covered_size: u128,
+	/// Size of all files in the container.
+	pub container_size: u128,
+}
+
+// Returns the indices of the dirs containing the path, from the innermost one.
+pub(super) fn ancestor_dirs(index: &SubIndex<'_>, path: &str) -> Vec<usize> {
+	let mut ancestors = Vec::new();
+	let mut current = parent_str(path);
+	while let Some(dir) = current {
+		if let Some(dir_index) = index.dir_index(dir) {
+			ancestors.push(dir_index);
+		}
+		current = parent_str(dir);
+	}
+	ancestors
+}
+
+// Returns the checksums of the files in the dir, or `None` if one of them has no checksum and so
+// has no duplicate. Empty files are ignored.
+fn dir_checksums<'a>(sub_index: &SubIndex<'a>) -> Option<HashSet<&'a Checksum>> {
+	let mut checksums = HashSet::new();
+	for file in sub_index.files {
+		if file.size == 0 {
+			continue;
+		}
+		if file.checksum.is_empty() {
+			return None;
+		}
+		checksums.insert(&file.checksum);
+	}
+	Some(checksums)
+}
+
+/// Finds the dirs whose file contents all exist under another dir, which neither contains them nor
+/// is inside them. Only the innermost containers are returned, and a dir is skipped if its parent
+/// is already contained in the same dir. Files must have checksums calculated to be matched.
+pub fn contained_dirs<'a>(index: &SubIndex<'a>, allowlist: &Allowlist) -> Vec<ContainedDir<'a>> {
+	let mut ancestors_by_checksum = HashMap::<&Checksum, HashSet<usize>>::new();
+	for file in index.files {
+		if file.size == 0 || file.checksum.is_empty() {
+			continue;
+		}
+		ancestors_by_checksum
+			.entry(&file.checksum)
+			.or_default()
+			.extend(ancestor_dirs(index, file.meta.path()));
+	}
+
+	let dirs = index.dirs;
+	let mut containers_by_dir = HashMap::<usize, Vec<usize>>::new();
+	let mut contained_dirs = Vec::new();
+	for (dir_index, dir) in dirs.iter().enumerate() {
+		if !allowlist.is_allowed(&dir.meta.path) {
+			continue;
+		}
+		let Some(checksums) = dir_checksums(&index.sub_index(dir_index)) else {
+			continue;
+		};
+		let mut candidate_sets: Vec<_> =
+			checksums.iter().filter_map(|checksum| ancestors_by_checksum.get(checksum)).collect();
+		if candidate_sets.is_empty() {
+			continue;
+		}
+		candidate_sets.sort_by_key(|candidates| candidates.len());
+		let mut candidates: Vec<usize> = candidate_sets[0]
+			.iter()
+			.copied()
+			.filter(|candidate| candidate_sets[1..].iter().all(|set| set.contains(candidate)))
+			.filter(|candidate| {
+				let path = dirs[*candidate].meta.path();
+				*candidate != dir_index
+					&& !dir.meta.is_child_of(path)
+					&& !dirs[*candidate].meta.is_child_of(dir.meta.path())
+			})
+			.collect();
+		// Containers of another container also contain the dir, so only the innermost are kept.
+		let all_candidates = candidates.clone();
+		candidates.retain(|candidate| {
+			let path = dirs[*candidate].meta.path();
+			!all_candidates.iter().any(|other| dirs[*other].meta.is_child_of(path))
+		});
+		candidates.sort_unstable();
+		containers_by_dir.insert(dir_index, candidates.clone());
+
+		let parent_containers = parent_str(dir.meta.path())
+			.and_then(|parent| containers_by_dir.get(&index.dir_index(parent)?));
+		for container_index in candidates {
+			if parent_containers.is_some_and(|containers| containers.contains(&container_index)) {
+				continue;
+			}
+			let container = index.sub_index(container_index);
+			let covered_size = container
+				.files()
+				.iter()
+				.filter(|file| checksums.contains(&file.checksum))
+				.map(|file| u128::from(file.size))
+				.sum();
+			contained_dirs.push(ContainedDir {
+				dir,
+				container: &dirs[container_index],
+				covered_size,
+				container_size: container.file_size(),
+			});
+		}
+	}
+	contained_dirs
+}
@@ -0,0 +1,110 @@
+use super::contained_dirs;
+use super::test_fixtures::create_dir;
+use super::test_fixtures::create_file;
+use crate::index::calculator::Allowlist;
+use crate::index::model::Dir;
+use crate::index::model::File;
+use crate::index::SubIndex;
+
+// Returns the contained dir paths along with their container and coverage.
+fn contained(files: &[File], dirs: &[Dir]) -> Vec<(String, String, u128, u128)> {
+	let index = SubIndex {
+		files,
+		dirs,
+	};
+	contained_dirs(&index, &Allowlist::allow_all())
+		.into_iter()
+		.map(|contained| {
+			(
+				contained.dir.meta.path().to_string(),
+				contained.container.meta.path().to_string(),
+				contained.covered_size,
+				contained.container_size,
+			)
+		})
+		.collect()
+}
+
+#[test]
+fn contained_subset() {
+	let files = vec![
+		create_file("new/a", 1, 0, "a"),
+		create_file("new/b", 2, 0, "b"),
+		create_file("new/c", 3, 0, "c"),
+		create_file("old/a", 1, 0, "a"),
+		create_file("old/b", 2, 0, "b"),
+	];
+	let dirs = vec![
+		create_dir("new"),
+		create_dir("old"),
+	];
+	assert_eq!(contained(&files, &dirs), vec![("old".to_string(), "new".to_string(), 3, 6)]);
+}
+
+#[test]
+fn contained_across_subdirs() {
+	let files = vec![
+		create_file("new/x/a", 1, 0, "a"),
+		create_file("new/y/b", 2, 0, "b"),
+		create_file("old/a", 1, 0, "a"),
+		create_file("old/b", 2, 0, "b"),
+	];
+	let dirs = vec![
+		create_dir("new"),
+		create_dir("new/x"),
+		create_dir("new/y"),
+		create_dir("old"),
+	];
+	assert_eq!(
+		contained(&files, &dirs),
+		vec![
+			("new".to_string(), "old".to_string(), 3, 3),
+			("old".to_string(), "new".to_string(), 3, 3),
+		]
+	);
+}
+
+#[test]
+fn contained_skips_missing_and_unchecked() {
+	let files = vec![
+		create_file("new/a", 1, 0, "a"),
+		create_file("old/a", 1, 0, "a"),
+		create_file("old/b", 2, 0, "b"),
+		create_file("other/a", 1, 0, "a"),
+		create_file("other/c", 2, 0, ""),
+	];
+	let dirs = vec![
+		create_dir("new"),
+		create_dir("old"),
+		create_dir("other"),
+	];
+	assert_eq!(
+		contained(&files, &dirs),
+		vec![
+			("new".to_string(), "old".to_string(), 1, 3),
+			("new".to_string(), "other".to_string(), 1, 3),
+		]
+	);
+}
+
+#[test]
+fn contained_skips_nested() {
+	let files = vec![
+		create_file("new/a", 1, 0, "a"),
+		create_file("new/b", 2, 0, "b"),
+		create_file("old/a", 1, 0, "a"),
+		create_file("old/sub/b", 2, 0, "b"),
+	];
+	let dirs = vec![
+		create_dir("new"),
+		create_dir("old"),
+		create_dir("old/sub"),
+	];
+	assert_eq!(
+		contained(&files, &dirs),
+		vec![
+			("new".to_string(), "old".to_string(), 3, 3),
+			("old".to_string(), "new".to_string(), 3, 3),
+		]
+	);
+}
@@ -1,180 +1,414 @@
+use std::cmp::Ordering;
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::io;
+use std::path::Path;
 
+use super::diff_copies;
+use super::diff_dirs;
+use crate::index::model::name_str;
+use crate::index::model::parent_str;
 use crate::index::model::Checksum;
 use crate::index::model::File;
 use crate::index::model::NativeFileReader;
 use crate::index::BUF_SIZE;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, PartialOrd, Ord)]
 pub enum Diff {
 	Added(String),
 	Removed(String),
 	Changed(String),
 	Moved(String, String),
+	// A new file whose content already existed in the other index, from the original to the copy.
+	Copied(String, String),
+	// A removed file whose content still exists, from the removed file to the remaining copy.
+	Deduplicated(String, String),
 }
 
-#[allow(
-	clippy::too_many_lines,
-	clippy::too_many_arguments
-)]
-pub fn diff(
-	self_files: &mut [File],
-	self_dirty: &mut bool,
-	other_files: &mut [File],
-	other_dirty: &mut bool,
-	mut notifier: impl FnMut(&str, &str),
-	match_name: bool,
-	match_created: bool,
-	match_modified: bool,
-) -> io::Result<Vec<Diff>> {
-	let mut buf = Vec::with_capacity(BUF_SIZE);
-	let mut diff_list = Vec::new();
-	let mut file_index_self = 0;
-	let mut file_index_other = 0;
+// One side of a comparison.
+pub struct DiffSide<'a> {
+	pub files: &'a mut [File],
+	pub dirty: &'a mut bool,
+	// Directory that the file paths are relative to, which is empty for absolute paths.
+	pub root: &'a Path,
+}
 
-	let mut file_index_self_by_checksum = HashMap::<(Checksum, u64), Vec<usize>>::new();
-	let mut file_index_other_by_checksum = HashMap::<(Checksum, u64), Vec<usize>>::new();
-	loop {
-		if file_index_self == self_files.len() {
-			for file in &other_files[file_index_other..] {
-				if file.checksum.is_empty() {
-					diff_list.push(Diff::Removed(file.meta.path().to_string()));
-				} else {
-					file_index_other_by_checksum
-						.entry((file.checksum.clone(), file.size))
-						.or_default()
-						.push(file_index_other);
-				}
-			}
-			break;
+impl<'a> DiffSide<'a> {
+	#[cfg(test)]
+	pub fn new(files: &'a mut [File], dirty: &'a mut bool) -> Self {
+		Self {
+			files,
+			dirty,
+			root: Path::new(""),
 		}
-		if file_index_other == other_files.len() {
-			for file in &self_files[file_index_self..] {
-				if file.checksum.is_empty() {
-					diff_list.push(Diff::Added(file.meta.path().to_string()));
-				} else {
-					file_index_self_by_checksum
-						.entry((file.checksum.clone(), file.size))
-						.or_default()
-						.push(file_index_self);
-				}
-			}
-			break;
+	}
+
+	pub fn with_root(files: &'a mut [File], dirty: &'a mut bool, root: &'a Path) -> Self {
+		Self {
+			files,
+			dirty,
+			root,
+		}
+	}
+
+	// Calculates the checksum of the given file if it is missing.
+	pub(super) fn calculate(&mut self, file_index: usize, buf: &mut Vec<u8>) -> io::Result<()> {
+		let file = &mut self.files[file_index];
+		if file.checksum.is_empty() {
+			file.checksum.calculate(&NativeFileReader, self.root.join(file.meta.path()), buf)?;
+			*self.dirty = true;
+		}
+		Ok(())
+	}
+}
+
+fn components(path: &str) -> impl Iterator<Item = &str> {
+	path.split('/').filter(|component| !component.is_empty())
+}
+
+// Returns how many directories apart the parents of both paths are.
+fn parent_distance(lhs: &str, rhs: &str) -> usize {
+	let lhs_parent = parent_str(lhs).unwrap_or_default();
+	let rhs_parent = parent_str(rhs).unwrap_or_default();
+	let common = components(lhs_parent)
+		.zip(components(rhs_parent))
+		.take_while(|(lhs, rhs)| lhs == rhs)
+		.count();
+	components(lhs_parent).count() + components(rhs_parent).count() - common * 2
+}
+
+// Returns the number of bytes shared at the start and at the end of both paths.
+fn path_similarity(lhs: &str, rhs: &str) -> usize {
+	let prefix = lhs.bytes().zip(rhs.bytes()).take_while(|(lhs, rhs)| lhs == rhs).count();
+	let suffix =
+		lhs.bytes().rev().zip(rhs.bytes().rev()).take_while(|(lhs, rhs)| lhs == rhs).count();
+	prefix + suffix
+}
+
+// Scores how likely a file moved between both paths, preferring identical names, then closer
+// parent directories, then similar paths.
+pub(super) fn move_score(self_path: &str, other_path: &str) -> (bool, Reverse<usize>, usize) {
+	(
+		name_str(self_path) == name_str(other_path),
+		Reverse(parent_distance(self_path, other_path)),
+		path_similarity(self_path, other_path),
+	)
+}
+
+// Pairs beyond this count are not scored, and files are paired in path order instead, so many
+// copies of the same content do not take quadratic time.
+const MAX_SCORED_PAIRS: usize = 10_000;
+
+// Returns the candidate pairs between both lists of positions, best first.
+fn ranked_pairs(
+	list_self: &[usize],
+	list_other: &[usize],
+	score: impl Fn(usize, usize) -> (bool, Reverse<usize>, usize),
+) -> Vec<(usize, usize)> {
+	if list_self.is_empty() || list_other.is_empty() {
+		return Vec::new();
+	}
+	if list_self.len().saturating_mul(list_other.len()) > MAX_SCORED_PAIRS {
+		// Every position of the longer list gets a candidate, cycling through the shorter one.
+		let count = list_self.len().max(list_other.len());
+		return (0..count)
+			.map(|k| (list_self[k % list_self.len()], list_other[k % list_other.len()]))
+			.collect();
+	}
+	let mut scored_pairs = Vec::with_capacity(list_self.len() * list_other.len());
+	for &index_self in list_self {
+		for &index_other in list_other {
+			scored_pairs.push((score(index_self, index_other), index_self, index_other));
 		}
+	}
+	scored_pairs.sort_by(|(lhs, ..), (rhs, ..)| rhs.cmp(lhs));
+	scored_pairs.into_iter().map(|(_, index_self, index_other)| (index_self, index_other)).collect()
+}
+
+// Pairs the candidates in order, skipping the ones whose files were already paired.
+fn pair_in_order(
+	pairs: Vec<(usize, usize)>,
+	paired_self: &mut [bool],
+	paired_other: &mut [bool],
+) -> Vec<(usize, usize)> {
+	let mut moves = Vec::new();
+	for (index_self, index_other) in pairs {
+		if paired_self[index_self] || paired_other[index_other] {
+			continue;
+		}
+		paired_self[index_self] = true;
+		paired_other[index_other] = true;
+		moves.push((index_self, index_other));
+	}
+	moves
+}
 
-		let file_self = &mut self_files[file_index_self];
-		let file_other = &mut other_files[file_index_other];
-		notifier(file_self.meta.path(), file_other.meta.path());
-
-		match file_self.meta.path().cmp(file_other.meta.path()) {
-			std::cmp::Ordering::Less => {
-				if file_self.checksum.is_empty() {
-					diff_list.push(Diff::Added(file_self.meta.path().to_string()));
-				} else {
-					file_index_self_by_checksum
-						.entry((file_self.checksum.clone(), file_self.size))
-						.or_default()
-						.push(file_index_self);
-				}
-				file_index_self += 1;
+// Returns the positions which were not paired.
+fn unpaired(paired: &[bool]) -> Vec<usize> {
+	paired.iter().enumerate().filter(|(_, paired)| !**paired).map(|(index, _)| index).collect()
+}
+
+// Pairs files with the same content by their best matching paths, reporting each pair as moved.
+// Leftover files on either side are reported as copies or deduplications of their best match.
+fn pair_moves(
+	self_files: &[File],
+	path_list_self: &[usize],
+	other_files: &[File],
+	path_list_other: &[usize],
+) -> Vec<Diff> {
+	let self_path = |index: usize| self_files[path_list_self[index]].meta.path();
+	let other_path = |index: usize| other_files[path_list_other[index]].meta.path();
+	let score =
+		|index_self, index_other| move_score(self_path(index_self), other_path(index_other));
+
+	// Files with the same name are paired first, since they score above any other pair.
+	let mut lists_by_name = HashMap::<&str, (Vec<usize>, Vec<usize>)>::new();
+	for index_self in 0..path_list_self.len() {
+		lists_by_name.entry(name_str(self_path(index_self))).or_default().0.push(index_self);
+	}
+	for index_other in 0..path_list_other.len() {
+		lists_by_name.entry(name_str(other_path(index_other))).or_default().1.push(index_other);
+	}
+	let mut paired_self = vec![false; path_list_self.len()];
+	let mut paired_other = vec![false; path_list_other.len()];
+	let mut moves = Vec::new();
+	for (list_self, list_other) in lists_by_name.values() {
+		let pairs = ranked_pairs(list_self, list_other, score);
+		moves.extend(pair_in_order(pairs, &mut paired_self, &mut paired_other));
+	}
+	let pairs = ranked_pairs(&unpaired(&paired_self), &unpaired(&paired_other), score);
+	moves.extend(pair_in_order(pairs, &mut paired_self, &mut paired_other));
+
+	let mut diff_list: Vec<_> = moves
+		.into_iter()
+		.map(|(index_self, index_other)| {
+			Diff::Moved(self_path(index_self).to_string(), other_path(index_other).to_string())
+		})
+		.collect();
+
+	// Only one side has leftover files, which are matched with any file of the other side.
+	let all_self: Vec<_> = (0..path_list_self.len()).collect();
+	let all_other: Vec<_> = (0..path_list_other.len()).collect();
+	let mut best_self = vec![None; path_list_self.len()];
+	for (index_self, index_other) in ranked_pairs(&unpaired(&paired_self), &all_other, score) {
+		best_self[index_self].get_or_insert(index_other);
+	}
+	let mut best_other = vec![None; path_list_other.len()];
+	for (index_self, index_other) in ranked_pairs(&all_self, &unpaired(&paired_other), score) {
+		best_other[index_other].get_or_insert(index_self);
+	}
+
+	for index_self in unpaired(&paired_self) {
+		let Some(index_other) = best_self[index_self] else {
+			diff_list.push(Diff::Added(self_path(index_self).to_string()));
+			continue;
+		};
+		diff_list.push(Diff::Copied(
+			other_path(index_other).to_string(),
+			self_path(index_self).to_string(),
+		));
+	}
+	for index_other in unpaired(&paired_other) {
+		let Some(index_self) = best_other[index_other] else {
+			diff_list.push(Diff::Removed(other_path(index_other).to_string()));
+			continue;
+		};
+		diff_list.push(Diff::Deduplicated(
+			other_path(index_other).to_string(),
+			self_path(index_self).to_string(),
+		));
+	}
+	diff_list
+}
+
+// A step through two lists of files sorted by path.
+pub(super) enum FileStep {
+	// A file only found in the first list.
+	SelfOnly(usize),
+	// A file only found in the second list.
+	OtherOnly(usize),
+	// Files at the same path in both lists.
+	Both(usize, usize),
+}
+
+// Walks two lists of files sorted by path, one path at a time. The lists are passed to each step
+// so their files can be changed in between.
+#[derive(Default)]
+pub(super) struct FileWalk {
+	self_index: usize,
+	other_index: usize,
+}
+
+impl FileWalk {
+	// Returns the paths the walk is at, while both lists have files left.
+	pub(super) fn paths<'a>(
+		&self,
+		self_files: &'a [File],
+		other_files: &'a [File],
+	) -> Option<(&'a str, &'a str)> {
+		let file_self = self_files.get(self.self_index)?;
+		let file_other = other_files.get(self.other_index)?;
+		Some((file_self.meta.path(), file_other.meta.path()))
+	}
+
+	pub(super) fn next(&mut self, self_files: &[File], other_files: &[File]) -> Option<FileStep> {
+		let ordering = match (self_files.get(self.self_index), other_files.get(self.other_index)) {
+			(Some(file_self), Some(file_other)) => {
+				file_self.meta.path().cmp(file_other.meta.path())
 			}
-			std::cmp::Ordering::Greater => {
-				if file_other.checksum.is_empty() {
-					diff_list.push(Diff::Removed(file_other.meta.path().to_string()));
-				} else {
-					file_index_other_by_checksum
-						.entry((file_other.checksum.clone(), file_other.size))
-						.or_default()
-						.push(file_index_other);
-				}
-				file_index_other += 1;
+			(Some(_), None) => Ordering::Less,
+			(None, Some(_)) => Ordering::Greater,
+			(None, None) => return None,
+		};
+		let step = match ordering {
+			Ordering::Less => {
+				self.self_index += 1;
+				FileStep::SelfOnly(self.self_index - 1)
 			}
-			std::cmp::Ordering::Equal => {
-				file_index_self += 1;
-				file_index_other += 1;
-
-				if file_self.size != file_other.size {
-					diff_list.push(Diff::Changed(file_self.meta.path().to_string()));
-					continue;
-				}
-
-				if match_name {
-					continue;
-				}
-				if match_created && file_self.meta.created_time() == file_other.meta.created_time()
-				{
-					continue;
-				}
-				if match_modified
-					&& file_self.meta.modified_time() == file_other.meta.modified_time()
-				{
-					continue;
-				}
-
-				if file_self.checksum.is_empty() {
-					file_self.checksum.calculate(
-						&NativeFileReader,
-						file_self.meta.path(),
-						&mut buf,
-					)?;
-					*self_dirty = true;
-				}
-				if file_other.checksum.is_empty() {
-					file_other.checksum.calculate(
-						&NativeFileReader,
-						file_self.meta.path(),
-						&mut buf,
-					)?;
-					*other_dirty = true;
-				}
-
-				if file_self.checksum != file_other.checksum {
-					diff_list.push(Diff::Changed(file_self.meta.path().to_string()));
-				}
+			Ordering::Greater => {
+				self.other_index += 1;
+				FileStep::OtherOnly(self.other_index - 1)
 			}
-		}
+			Ordering::Equal => {
+				self.self_index += 1;
+				self.other_index += 1;
+				FileStep::Both(self.self_index - 1, self.other_index - 1)
+			}
+		};
+		Some(step)
+	}
+}
+
+// Files only found on one side. Files with checksums are bucketed by content so they can be paired
+// into moves, and the others are reported right away.
+#[derive(Default)]
+pub(super) struct UnmatchedFiles {
+	self_by_checksum: HashMap<(Checksum, u64), Vec<usize>>,
+	other_by_checksum: HashMap<(Checksum, u64), Vec<usize>>,
+}
+
+fn add_unmatched(
+	by_checksum: &mut HashMap<(Checksum, u64), Vec<usize>>,
+	files: &[File],
+	file_index: usize,
+	diff_list: &mut Vec<Diff>,
+	unpaired: fn(String) -> Diff,
+) {
+	let file = &files[file_index];
+	if file.checksum.is_empty() {
+		diff_list.push(unpaired(file.meta.path().to_string()));
+	} else {
+		by_checksum.entry((file.checksum.clone(), file.size)).or_default().push(file_index);
+	}
+}
+
+impl UnmatchedFiles {
+	pub(super) fn add_self(
+		&mut self,
+		files: &[File],
+		file_index: usize,
+		diff_list: &mut Vec<Diff>,
+	) {
+		add_unmatched(&mut self.self_by_checksum, files, file_index, diff_list, Diff::Added);
 	}
 
-	for (checksum, path_list_self) in file_index_self_by_checksum {
-		if let Some(path_list_other) = file_index_other_by_checksum.remove(&checksum) {
-			if path_list_self.len() == path_list_other.len() {
-				for (file_index_self, file_index_other) in
-					path_list_self.iter().zip(path_list_other)
-				{
-					let file_self = &mut self_files[*file_index_self];
-					let file_other = &mut other_files[file_index_other];
-
-					diff_list.push(Diff::Moved(
-						file_self.meta.path().to_string(),
-						file_other.meta.path().to_string(),
-					));
-				}
+	pub(super) fn add_other(
+		&mut self,
+		files: &[File],
+		file_index: usize,
+		diff_list: &mut Vec<Diff>,
+	) {
+		add_unmatched(&mut self.other_by_checksum, files, file_index, diff_list, Diff::Removed);
+	}
+
+	// Pairs the files with the same content into moves, reporting the rest as added or removed.
+	pub(super) fn pair(
+		mut self,
+		self_files: &[File],
+		other_files: &[File],
+		diff_list: &mut Vec<Diff>,
+	) {
+		for (checksum, path_list_self) in self.self_by_checksum {
+			if let Some(path_list_other) = self.other_by_checksum.remove(&checksum) {
+				diff_list.extend(pair_moves(
+					self_files,
+					&path_list_self,
+					other_files,
+					&path_list_other,
+				));
 				continue;
 			}
-
 			for file_index in path_list_self {
-				let file = &mut self_files[file_index];
-				diff_list.push(Diff::Added(file.meta.path().to_string()));
+				diff_list.push(Diff::Added(self_files[file_index].meta.path().to_string()));
 			}
-			for file_index in path_list_other {
-				let file = &mut other_files[file_index];
-				diff_list.push(Diff::Removed(file.meta.path().to_string()));
+		}
+		for (_, path_list) in self.other_by_checksum {
+			for file_index in path_list {
+				diff_list.push(Diff::Removed(other_files[file_index].meta.path().to_string()));
 			}
+		}
+	}
+}
+
+pub fn diff(
+	mut self_side: DiffSide<'_>,
+	mut other_side: DiffSide<'_>,
+	mut notifier: impl FnMut(&str, &str),
+	match_name: bool,
+	match_created: bool,
+	match_modified: bool,
+) -> io::Result<Vec<Diff>> {
+	let mut buf = Vec::with_capacity(BUF_SIZE);
+	let mut diff_list = Vec::new();
+	let mut unmatched = UnmatchedFiles::default();
+	let mut walk = FileWalk::default();
+	loop {
+		if let Some((path_self, path_other)) = walk.paths(self_side.files, other_side.files) {
+			notifier(path_self, path_other);
+		}
+		let (file_index_self, file_index_other) = match walk.next(self_side.files, other_side.files)
+		{
+			None => break,
+			Some(FileStep::SelfOnly(file_index)) => {
+				unmatched.add_self(self_side.files, file_index, &mut diff_list);
+				continue;
+			}
+			Some(FileStep::OtherOnly(file_index)) => {
+				unmatched.add_other(other_side.files, file_index, &mut diff_list);
+				continue;
+			}
+			Some(FileStep::Both(file_index_self, file_index_other)) => {
+				(file_index_self, file_index_other)
+			}
+		};
+
+		let file_self = &self_side.files[file_index_self];
+		let file_other = &other_side.files[file_index_other];
+		if file_self.size != file_other.size {
+			diff_list.push(Diff::Changed(file_self.meta.path().to_string()));
 			continue;
 		}
 
-		for file_index in path_list_self {
-			let file = &mut self_files[file_index];
-			diff_list.push(Diff::Added(file.meta.path().to_string()));
+		if match_name {
+			continue;
 		}
-	}
-	for (_, path_list) in file_index_other_by_checksum {
-		for file_index in path_list {
-			let file = &mut other_files[file_index];
-			diff_list.push(Diff::Removed(file.meta.path().to_string()));
+		if match_created && file_self.meta.created_time() == file_other.meta.created_time() {
+			continue;
+		}
+		if match_modified && file_self.meta.modified_time() == file_other.meta.modified_time() {
+			continue;
+		}
+
+		self_side.calculate(file_index_self, &mut buf)?;
+		other_side.calculate(file_index_other, &mut buf)?;
+		let file_self = &self_side.files[file_index_self];
+		let file_other = &other_side.files[file_index_other];
+		if file_self.checksum != file_other.checksum {
+			diff_list.push(Diff::Changed(file_self.meta.path().to_string()));
 		}
 	}
+	unmatched.pair(self_side.files, other_side.files, &mut diff_list);
 
-	Ok(diff_list)
+	let diff_list = diff_copies(diff_list, &mut self_side, &mut other_side, &mut buf)?;
+	diff_dirs(diff_list, &mut self_side, &mut other_side, &mut buf)
 }
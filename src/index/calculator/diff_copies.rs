@@ -0,0 +1,94 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io;
+
+use super::diff::move_score;
+use super::diff_dirs::same_content;
+use super::Diff;
+use super::DiffSide;
+
+// Finds added files whose content already exists in the other index. Files matching a removed file
+// are reported as moved, and files matching any other file are reported as copied. Checksums are
+// only calculated for files whose sizes match.
+pub fn diff_copies(
+	diff_list: Vec<Diff>,
+	self_side: &mut DiffSide<'_>,
+	other_side: &mut DiffSide<'_>,
+	buf: &mut Vec<u8>,
+) -> io::Result<Vec<Diff>> {
+	let mut file_index_other_by_size = HashMap::<u64, Vec<usize>>::new();
+	for (file_index, file) in other_side.files.iter().enumerate() {
+		if file.size != 0 {
+			file_index_other_by_size.entry(file.size).or_default().push(file_index);
+		}
+	}
+	let mut removed = HashSet::new();
+	for diff in &diff_list {
+		if let Diff::Removed(path) = diff {
+			if let Ok(file_index) =
+				other_side.files.binary_search_by(|file| file.meta.path().cmp(path))
+			{
+				removed.insert(file_index);
+			}
+		}
+	}
+
+	let mut result = Vec::with_capacity(diff_list.len());
+	let mut moved = HashSet::new();
+	for diff in diff_list {
+		let Diff::Added(path) = diff else {
+			result.push(diff);
+			continue;
+		};
+		let Ok(file_index_self) =
+			self_side.files.binary_search_by(|file| file.meta.path().cmp(&path))
+		else {
+			result.push(Diff::Added(path));
+			continue;
+		};
+		let Some(candidates) = file_index_other_by_size.get(&self_side.files[file_index_self].size)
+		else {
+			result.push(Diff::Added(path));
+			continue;
+		};
+
+		// Prefer files that were removed, since those are moves rather than copies.
+		let mut candidates: Vec<_> =
+			candidates.iter().copied().filter(|file_index| !moved.contains(file_index)).collect();
+		candidates.sort_by_cached_key(|file_index| {
+			Reverse((
+				removed.contains(file_index),
+				move_score(&path, other_side.files[*file_index].meta.path()),
+			))
+		});
+
+		let mut found = None;
+		for file_index_other in candidates {
+			if same_content(self_side, file_index_self, other_side, file_index_other, buf)? {
+				found = Some(file_index_other);
+				break;
+			}
+		}
+		let Some(file_index_other) = found else {
+			result.push(Diff::Added(path));
+			continue;
+		};
+		let other_path = other_side.files[file_index_other].meta.path().to_string();
+		if removed.contains(&file_index_other) && moved.insert(file_index_other) {
+			result.push(Diff::Moved(path, other_path));
+		} else {
+			result.push(Diff::Copied(other_path, path));
+		}
+	}
+
+	if moved.is_empty() {
+		return Ok(result);
+	}
+	let moved_paths: HashSet<_> =
+		moved.into_iter().map(|file_index| other_side.files[file_index].meta.path()).collect();
+	Ok(result
+		.into_iter()
+		.filter(|diff| !matches!(diff, Diff::Removed(path) if moved_paths.contains(path.as_str())))
+		.collect())
+}
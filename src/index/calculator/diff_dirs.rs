@@ -0,0 +1,275 @@
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io;
+use std::ops::Range;
+
+use super::Diff;
+use super::DiffSide;
+use crate::index::model::name_str;
+use crate::index::model::parent_str;
+use crate::index::model::File;
+
+// A directory which moved as a whole, along with its files that were found on both sides.
+struct DirMove {
+	self_path: String,
+	other_path: String,
+	// Pairs of self and other file indices with the same path relative to the moved directory.
+	file_pairs: Vec<(usize, usize)>,
+}
+
+// Returns every parent directory of the given path.
+fn ancestors(path: &str) -> impl Iterator<Item = &str> {
+	std::iter::successors(parent_str(path), |path| parent_str(path))
+}
+
+// Returns the range of files that are recursively inside the given directory.
+fn child_file_range(files: &[File], dir: &str) -> Range<usize> {
+	let prefix = format!("{dir}/");
+	let start = files.partition_point(|file| file.meta.path() < prefix.as_str());
+	let mut end = start;
+	for file in &files[start..] {
+		if !file.meta.is_child_of(dir) {
+			break;
+		}
+		end += 1;
+	}
+	start..end
+}
+
+fn is_inside(path: &str, dir: &str) -> bool {
+	path.starts_with(dir) && path.as_bytes().get(dir.len()) == Some(&b'/')
+}
+
+// Adds the pairs of directories that could explain a file moving from `other_path` to `self_path`.
+// Each ancestor pair is a candidate as long as the directory names along the way are the same.
+fn record_candidates(
+	candidates: &mut BTreeSet<(String, String)>,
+	self_path: &str,
+	other_path: &str,
+) {
+	if name_str(self_path) != name_str(other_path) {
+		return;
+	}
+	for (self_dir, other_dir) in ancestors(self_path).zip(ancestors(other_path)) {
+		if self_dir == other_dir {
+			break;
+		}
+		candidates.insert((self_dir.to_string(), other_dir.to_string()));
+		if name_str(self_dir) != name_str(other_dir) {
+			break;
+		}
+	}
+}
+
+// Compares the content of both files, calculating their checksums only if their sizes match. Files
+// that no longer exist are never considered the same.
+pub(super) fn same_content(
+	self_side: &mut DiffSide<'_>,
+	file_index_self: usize,
+	other_side: &mut DiffSide<'_>,
+	file_index_other: usize,
+	buf: &mut Vec<u8>,
+) -> io::Result<bool> {
+	if self_side.files[file_index_self].size != other_side.files[file_index_other].size {
+		return Ok(false);
+	}
+	if !calculate_if_exists(self_side, file_index_self, buf)? {
+		return Ok(false);
+	}
+	if !calculate_if_exists(other_side, file_index_other, buf)? {
+		return Ok(false);
+	}
+	Ok(self_side.files[file_index_self].checksum == other_side.files[file_index_other].checksum)
+}
+
+// Calculates the checksum of the file if it is missing. Returns whether the checksum is known,
+// which is not the case for files that no longer exist on the filesystem.
+fn calculate_if_exists(
+	side: &mut DiffSide<'_>,
+	file_index: usize,
+	buf: &mut Vec<u8>,
+) -> io::Result<bool> {
+	match side.calculate(file_index, buf) {
+		Ok(()) => Ok(true),
+		Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+		Err(e) => Err(e),
+	}
+}
+
+// Pairs the files of both directories by their relative path. Returns `None` unless most of the
+// files have the same content on both sides.
+fn pair_dir_files(
+	self_side: &mut DiffSide<'_>,
+	other_side: &mut DiffSide<'_>,
+	(self_dir, other_dir): (&str, &str),
+	buf: &mut Vec<u8>,
+) -> io::Result<Option<Vec<(usize, usize)>>> {
+	let self_range = child_file_range(self_side.files, self_dir);
+	let other_range = child_file_range(other_side.files, other_dir);
+	let mut file_pairs = Vec::new();
+	let mut total = 0usize;
+	let mut matched = 0usize;
+	let mut self_index = self_range.start;
+	let mut other_index = other_range.start;
+	while self_index < self_range.end || other_index < other_range.end {
+		total += 1;
+		if self_index == self_range.end {
+			other_index += 1;
+			continue;
+		}
+		if other_index == other_range.end {
+			self_index += 1;
+			continue;
+		}
+		let self_relative = &self_side.files[self_index].meta.path()[self_dir.len()..];
+		let other_relative = &other_side.files[other_index].meta.path()[other_dir.len()..];
+		match self_relative.cmp(other_relative) {
+			std::cmp::Ordering::Less => {
+				self_index += 1;
+			}
+			std::cmp::Ordering::Greater => {
+				other_index += 1;
+			}
+			std::cmp::Ordering::Equal => {
+				if same_content(self_side, self_index, other_side, other_index, buf)? {
+					matched += 1;
+				}
+				file_pairs.push((self_index, other_index));
+				self_index += 1;
+				other_index += 1;
+			}
+		}
+	}
+	if matched * 2 <= total {
+		return Ok(None);
+	}
+	Ok(Some(file_pairs))
+}
+
+// Returns the pairs of directories that could explain the files which moved or were re-added.
+fn dir_candidates(diff_list: &[Diff]) -> BTreeSet<(String, String)> {
+	let mut removed_by_name = HashMap::<&str, Vec<&str>>::new();
+	for diff in diff_list {
+		if let Diff::Removed(path) = diff {
+			removed_by_name.entry(name_str(path)).or_default().push(path);
+		}
+	}
+	let mut candidates = BTreeSet::new();
+	for diff in diff_list {
+		match diff {
+			Diff::Moved(self_path, other_path) => {
+				record_candidates(&mut candidates, self_path, other_path);
+			}
+			Diff::Added(self_path) => {
+				let Some(removed_list) = removed_by_name.get(name_str(self_path)) else {
+					continue;
+				};
+				for other_path in removed_list {
+					record_candidates(&mut candidates, self_path, other_path);
+				}
+			}
+			_ => {}
+		}
+	}
+	candidates
+}
+
+// Replaces the per-file differences of directories that moved as a whole with a single
+// `Diff::Moved` for each directory, keeping the differences of the files inside of them.
+//
+// A directory is considered moved when it only exists on one side, a directory of the same name
+// only exists on the other side, and most of their files have the same relative path and content.
+pub fn diff_dirs(
+	diff_list: Vec<Diff>,
+	self_side: &mut DiffSide<'_>,
+	other_side: &mut DiffSide<'_>,
+	buf: &mut Vec<u8>,
+) -> io::Result<Vec<Diff>> {
+	let candidates = dir_candidates(&diff_list);
+	if candidates.is_empty() {
+		return Ok(diff_list);
+	}
+
+	let self_dirs: HashSet<_> = self_side
+		.files
+		.iter()
+		.flat_map(|file| ancestors(file.meta.path()))
+		.map(str::to_owned)
+		.collect();
+	let other_dirs: HashSet<_> = other_side
+		.files
+		.iter()
+		.flat_map(|file| ancestors(file.meta.path()))
+		.map(str::to_owned)
+		.collect();
+
+	// Candidates are sorted by path, so parents are always evaluated before their children.
+	let mut dir_moves = Vec::<DirMove>::new();
+	for (self_dir, other_dir) in candidates {
+		if other_dirs.contains(&self_dir) || self_dirs.contains(&other_dir) {
+			continue;
+		}
+		let nested = dir_moves.iter().any(|dir_move| {
+			is_inside(&self_dir, &dir_move.self_path) || is_inside(&other_dir, &dir_move.other_path)
+		});
+		if nested {
+			continue;
+		}
+
+		let Some(file_pairs) = pair_dir_files(self_side, other_side, (&self_dir, &other_dir), buf)?
+		else {
+			continue;
+		};
+		dir_moves.push(DirMove {
+			self_path: self_dir,
+			other_path: other_dir,
+			file_pairs,
+		});
+	}
+	if dir_moves.is_empty() {
+		return Ok(diff_list);
+	}
+
+	let mut paired_self = HashSet::new();
+	let mut paired_other = HashSet::new();
+	for dir_move in &dir_moves {
+		for (self_index, other_index) in &dir_move.file_pairs {
+			paired_self.insert(self_side.files[*self_index].meta.path());
+			paired_other.insert(other_side.files[*other_index].meta.path());
+		}
+	}
+
+	let mut result = Vec::with_capacity(diff_list.len());
+	for diff in diff_list {
+		match diff {
+			Diff::Added(ref path) if paired_self.contains(path.as_str()) => {}
+			Diff::Removed(ref path) if paired_other.contains(path.as_str()) => {}
+			Diff::Copied(_, ref path) if paired_self.contains(path.as_str()) => {}
+			Diff::Deduplicated(ref path, _) if paired_other.contains(path.as_str()) => {}
+			Diff::Moved(self_path, other_path) => {
+				let self_paired = paired_self.contains(self_path.as_str());
+				let other_paired = paired_other.contains(other_path.as_str());
+				if self_paired && !other_paired {
+					result.push(Diff::Removed(other_path));
+				} else if !self_paired && other_paired {
+					result.push(Diff::Added(self_path));
+				} else if !self_paired && !other_paired {
+					result.push(Diff::Moved(self_path, other_path));
+				}
+			}
+			diff => result.push(diff),
+		}
+	}
+	for dir_move in dir_moves {
+		for (self_index, other_index) in dir_move.file_pairs {
+			let file_self = &self_side.files[self_index];
+			let file_other = &other_side.files[other_index];
+			if file_self.size != file_other.size || file_self.checksum != file_other.checksum {
+				result.push(Diff::Changed(file_self.meta.path().to_string()));
+			}
+		}
+		result.push(Diff::Moved(dir_move.self_path, dir_move.other_path));
+	}
+	Ok(result)
+}
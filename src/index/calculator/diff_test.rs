@@ -2,6 +2,7 @@ use std::time::Duration;
 use std::time::SystemTime;
 
 use super::diff;
+use super::DiffSide;
 use crate::index::model::Checksum;
 use crate::index::model::File;
 use crate::index::model::Metadata;
@@ -18,9 +19,18 @@ fn create_file(name: &str, size: u64, created: u64, modified: u64) -> File {
 		},
 		size,
 		checksum: Checksum::new(),
+		linked: None,
 	}
 }
 
+fn create_file_with_checksum(name: &str, size: u64, checksum: &str) -> File {
+	let mut file = create_file(name, size, 1, 1);
+	file.checksum = Checksum {
+		sha512: checksum.to_string(),
+	};
+	file
+}
+
 #[test]
 fn diff_same() {
 	let mut self_files = vec![create_file("a", 1, 1, 1)];
@@ -34,10 +44,8 @@ fn diff_same() {
 	let mut self_dirty = false;
 	let mut other_dirty = false;
 	let diffs = diff(
-		&mut self_files,
-		&mut self_dirty,
-		&mut other_files,
-		&mut other_dirty,
+		DiffSide::new(&mut self_files, &mut self_dirty),
+		DiffSide::new(&mut other_files, &mut other_dirty),
 		|_, _| {},
 		false,
 		false,
@@ -54,10 +62,8 @@ fn diff_added() {
 	let mut self_dirty = false;
 	let mut other_dirty = false;
 	let diffs = diff(
-		&mut self_files,
-		&mut self_dirty,
-		&mut other_files,
-		&mut other_dirty,
+		DiffSide::new(&mut self_files, &mut self_dirty),
+		DiffSide::new(&mut other_files, &mut other_dirty),
 		|_, _| {},
 		false,
 		false,
@@ -74,10 +80,8 @@ fn diff_removed() {
 	let mut self_dirty = false;
 	let mut other_dirty = false;
 	let diffs = diff(
-		&mut self_files,
-		&mut self_dirty,
-		&mut other_files,
-		&mut other_dirty,
+		DiffSide::new(&mut self_files, &mut self_dirty),
+		DiffSide::new(&mut other_files, &mut other_dirty),
 		|_, _| {},
 		false,
 		false,
@@ -95,10 +99,8 @@ fn diff_changed() {
 	let mut self_dirty = false;
 	let mut other_dirty = false;
 	let diffs = diff(
-		&mut self_files,
-		&mut self_dirty,
-		&mut other_files,
-		&mut other_dirty,
+		DiffSide::new(&mut self_files, &mut self_dirty),
+		DiffSide::new(&mut other_files, &mut other_dirty),
 		|_, _| {},
 		false,
 		false,
@@ -121,10 +123,8 @@ fn diff_moved() {
 	let mut self_dirty = false;
 	let mut other_dirty = false;
 	let diffs = diff(
-		&mut self_files,
-		&mut self_dirty,
-		&mut other_files,
-		&mut other_dirty,
+		DiffSide::new(&mut self_files, &mut self_dirty),
+		DiffSide::new(&mut other_files, &mut other_dirty),
 		|_, _| {},
 		false,
 		false,
@@ -133,3 +133,177 @@ fn diff_moved() {
 	.unwrap();
 	assert_eq!(diffs, vec![Diff::Moved("a".to_string(), "b".to_string())]);
 }
+
+#[test]
+fn diff_moved_dir() {
+	let mut self_files = vec![
+		create_file_with_checksum("Archive/2023/a", 1, "a"),
+		create_file_with_checksum("Archive/2023/b", 1, "b"),
+		create_file_with_checksum("Projects/2024/c", 1, "c"),
+	];
+	let mut other_files = vec![
+		create_file_with_checksum("Projects/2023/a", 1, "a"),
+		create_file_with_checksum("Projects/2023/b", 1, "b"),
+		create_file_with_checksum("Projects/2024/c", 1, "c"),
+	];
+	let mut self_dirty = false;
+	let mut other_dirty = false;
+	let diffs = diff(
+		DiffSide::new(&mut self_files, &mut self_dirty),
+		DiffSide::new(&mut other_files, &mut other_dirty),
+		|_, _| {},
+		false,
+		false,
+		false,
+	)
+	.unwrap();
+	assert_eq!(diffs, vec![Diff::Moved("Archive/2023".to_string(), "Projects/2023".to_string())]);
+}
+
+#[test]
+fn diff_moved_dir_with_changes() {
+	let mut self_files = vec![
+		create_file_with_checksum("new/a", 1, "a"),
+		create_file_with_checksum("new/b", 1, "b"),
+		create_file_with_checksum("new/c", 1, "c"),
+		create_file_with_checksum("new/d", 1, "d2"),
+		create_file_with_checksum("new/e", 1, "e"),
+	];
+	let mut other_files = vec![
+		create_file_with_checksum("old/a", 1, "a"),
+		create_file_with_checksum("old/b", 1, "b"),
+		create_file_with_checksum("old/c", 1, "c"),
+		create_file_with_checksum("old/d", 1, "d1"),
+	];
+	let mut self_dirty = false;
+	let mut other_dirty = false;
+	let mut diffs = diff(
+		DiffSide::new(&mut self_files, &mut self_dirty),
+		DiffSide::new(&mut other_files, &mut other_dirty),
+		|_, _| {},
+		false,
+		false,
+		false,
+	)
+	.unwrap();
+	diffs.sort();
+	assert_eq!(
+		diffs,
+		vec![
+			Diff::Added("new/e".to_string()),
+			Diff::Changed("new/d".to_string()),
+			Diff::Moved("new".to_string(), "old".to_string()),
+		]
+	);
+}
+
+#[test]
+fn diff_moved_ambiguous_copied() {
+	let mut self_files = vec![
+		create_file_with_checksum("docs/2024/report.txt", 1, "c"),
+		create_file_with_checksum("notes.txt", 1, "c"),
+		create_file_with_checksum("other/copy.txt", 1, "c"),
+	];
+	let mut other_files = vec![
+		create_file_with_checksum("docs/report.txt", 1, "c"),
+		create_file_with_checksum("old/notes.txt", 1, "c"),
+	];
+	let mut self_dirty = false;
+	let mut other_dirty = false;
+	let mut diffs = diff(
+		DiffSide::new(&mut self_files, &mut self_dirty),
+		DiffSide::new(&mut other_files, &mut other_dirty),
+		|_, _| {},
+		false,
+		false,
+		false,
+	)
+	.unwrap();
+	diffs.sort();
+	assert_eq!(
+		diffs,
+		vec![
+			Diff::Moved("docs/2024/report.txt".to_string(), "docs/report.txt".to_string()),
+			Diff::Moved("notes.txt".to_string(), "old/notes.txt".to_string()),
+			Diff::Copied("old/notes.txt".to_string(), "other/copy.txt".to_string()),
+		]
+	);
+}
+
+#[test]
+fn diff_moved_ambiguous_deduplicated() {
+	let mut self_files = vec![
+		create_file_with_checksum("a/photo.jpg", 1, "c"),
+		create_file_with_checksum("b/photo.jpeg", 1, "c"),
+	];
+	let mut other_files = vec![
+		create_file_with_checksum("a/photo.jpg", 1, "c"),
+		create_file_with_checksum("b/photo (1).jpg", 1, "c"),
+		create_file_with_checksum("b/photo.jpg", 1, "c"),
+	];
+	let mut self_dirty = false;
+	let mut other_dirty = false;
+	let mut diffs = diff(
+		DiffSide::new(&mut self_files, &mut self_dirty),
+		DiffSide::new(&mut other_files, &mut other_dirty),
+		|_, _| {},
+		false,
+		false,
+		false,
+	)
+	.unwrap();
+	diffs.sort();
+	assert_eq!(
+		diffs,
+		vec![
+			Diff::Moved("b/photo.jpeg".to_string(), "b/photo.jpg".to_string()),
+			Diff::Deduplicated("b/photo (1).jpg".to_string(), "b/photo.jpeg".to_string()),
+		]
+	);
+}
+
+#[test]
+fn diff_copied() {
+	let mut self_files = vec![
+		create_file_with_checksum("a.txt", 1, "c"),
+		create_file_with_checksum("b.txt", 1, "c"),
+	];
+	let mut other_files = vec![create_file_with_checksum("a.txt", 1, "c")];
+	let mut self_dirty = false;
+	let mut other_dirty = false;
+	let diffs = diff(
+		DiffSide::new(&mut self_files, &mut self_dirty),
+		DiffSide::new(&mut other_files, &mut other_dirty),
+		|_, _| {},
+		false,
+		false,
+		false,
+	)
+	.unwrap();
+	assert_eq!(diffs, vec![Diff::Copied("a.txt".to_string(), "b.txt".to_string())]);
+}
+
+#[test]
+fn diff_moved_many_copies() {
+	// Too many copies to score every pair, so they are paired in path order.
+	let mut self_files: Vec<_> =
+		(0..150).map(|i| create_file_with_checksum(&format!("new/{i:03}.txt"), 1, "x")).collect();
+	let mut other_files: Vec<_> =
+		(0..150).map(|i| create_file_with_checksum(&format!("old/{i:03}.bak"), 1, "x")).collect();
+	let mut self_dirty = false;
+	let mut other_dirty = false;
+	let mut diffs = diff(
+		DiffSide::new(&mut self_files, &mut self_dirty),
+		DiffSide::new(&mut other_files, &mut other_dirty),
+		|_, _| {},
+		false,
+		false,
+		false,
+	)
+	.unwrap();
+	diffs.sort();
+	let expected: Vec<_> = (0..150)
+		.map(|i| Diff::Moved(format!("new/{i:03}.txt"), format!("old/{i:03}.bak")))
+		.collect();
+	assert_eq!(diffs, expected);
+}
@@ -1,7 +1,9 @@
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::time::SystemTime;
 
 use super::Allowlist;
+use crate::index::model::parent_str;
 use crate::index::model::Checksum;
 use crate::index::model::Dir;
 use crate::index::store::SliceIndex;
@@ -24,6 +26,27 @@ fn dir_stats(index: &SubIndex) -> DirStats {
 	}
 }
 
+// Returns the path of an entry inside the dir relative to the dir.
+fn relative_path<'a>(dir: &str, path: &'a str) -> &'a str {
+	&path[(dir.len() + 1)..]
+}
+
+// Files as their relative path, size and checksum, followed by the relative paths of subdirs, so
+// dirs with equal layouts have equal keys.
+type LayoutKey<'a> = (Vec<(&'a str, u64, &'a Checksum)>, Vec<&'a str>);
+
+fn layout_key<'a>(dir: &Dir, sub_index: &SubIndex<'a>) -> LayoutKey<'a> {
+	let dir_path = dir.meta.path();
+	let files = sub_index
+		.files
+		.iter()
+		.map(|file| (relative_path(dir_path, file.meta.path()), file.size, &file.checksum))
+		.collect();
+	let dirs =
+		sub_index.dirs.iter().map(|child| relative_path(dir_path, child.meta.path())).collect();
+	(files, dirs)
+}
+
 pub fn potential_dir_matches(
 	index: &SubIndex<'_>,
 	allowlist: &Allowlist,
@@ -49,62 +72,65 @@ pub fn potential_dir_matches(
 		if path_list.len() < 2 {
 			continue;
 		}
-		let mut name_by_count = HashMap::<Vec<String>, usize>::new();
+		let mut name_by_count = HashMap::<Vec<&str>, usize>::new();
 		let mut created_by_count = HashMap::<Vec<SystemTime>, usize>::new();
 		let mut modified_by_count = HashMap::<Vec<SystemTime>, usize>::new();
 		let mut name_list = vec![Vec::new(); path_list.len()];
 		let mut created_list = vec![Vec::new(); path_list.len()];
 		let mut modified_list = vec![Vec::new(); path_list.len()];
-		for dir_index in &path_list {
+		for (position, dir_index) in path_list.iter().enumerate() {
 			let sub_index = index.sub_index(*dir_index);
-			let file_list = sub_index.files();
+			let dir_path = index.dirs()[*dir_index].meta.path();
+			let file_list = sub_index.files;
 			if match_name {
-				name_list[*dir_index] =
-					file_list.iter().map(|entry| entry.meta.path().to_string()).collect();
-				name_list[*dir_index].sort();
+				name_list[position] = file_list
+					.iter()
+					.map(|entry| relative_path(dir_path, entry.meta.path()))
+					.collect();
+				name_list[position].sort_unstable();
 				name_by_count
-					.entry(name_list[*dir_index].clone())
+					.entry(name_list[position].clone())
 					.and_modify(|count| *count += 1)
 					.or_insert(1);
 			}
 			if match_created {
-				created_list[*dir_index] =
+				created_list[position] =
 					file_list.iter().map(|entry| entry.meta.created_time).collect();
-				created_list[*dir_index].sort();
+				created_list[position].sort();
 				created_by_count
-					.entry(created_list[*dir_index].clone())
+					.entry(created_list[position].clone())
 					.and_modify(|count| *count += 1)
 					.or_insert(1);
 			}
 			if match_modified {
-				modified_list[*dir_index] =
-					file_list.iter().map(|entry| entry.meta.created_time).collect();
-				modified_list[*dir_index].sort();
+				modified_list[position] =
+					file_list.iter().map(|entry| entry.meta.modified_time).collect();
+				modified_list[position].sort();
 				modified_by_count
-					.entry(modified_list[*dir_index].clone())
+					.entry(modified_list[position].clone())
 					.and_modify(|count| *count += 1)
 					.or_insert(1);
 			}
 		}
 
-		for dir_index in &path_list {
+		for (position, dir_index) in path_list.iter().enumerate() {
 			let dir = &index.dirs()[*dir_index];
 			if match_name {
-				if let Some(count) = name_by_count.get(&name_list[*dir_index]) {
+				if let Some(count) = name_by_count.get(&name_list[position]) {
 					if *count < 2 {
 						continue;
 					}
 				}
 			}
 			if match_created {
-				if let Some(count) = created_by_count.get(&created_list[*dir_index]) {
+				if let Some(count) = created_by_count.get(&created_list[position]) {
 					if *count < 2 {
 						continue;
 					}
 				}
 			}
 			if match_modified {
-				if let Some(count) = modified_by_count.get(&modified_list[*dir_index]) {
+				if let Some(count) = modified_by_count.get(&modified_list[position]) {
 					if *count < 2 {
 						continue;
 					}
@@ -126,34 +152,101 @@ pub fn potential_dir_matches(
 	})
 }
 
-pub fn duplicate_dirs<'a>(index: &SubIndex<'a>, allowlist: &Allowlist) -> Vec<Vec<&'a Dir>> {
-	let mut dirs_by_checksums = HashMap::<(DirStats, Vec<Checksum>), Vec<&'a Dir>>::new();
-	let dirs = index.dirs;
-	for (dir_index, dir) in dirs.iter().enumerate() {
+/// Groups dirs whose files have the same contents. If strict, files must also have the same
+/// relative paths, and dirs the same subdirs.
+///
+/// Strict groups are found from the stored dir checksums when every candidate dir has one, and
+/// from their layouts otherwise. Those checksums cover names, so they cannot group dirs by contents
+/// alone, which instead compares the sorted file checksums of the dirs with the same stats.
+pub fn duplicate_dirs<'a>(
+	index: &SubIndex<'a>,
+	allowlist: &Allowlist,
+	strict: bool,
+) -> Vec<Vec<&'a Dir>> {
+	let mut dirs_by_stats = HashMap::<DirStats, Vec<(&'a Dir, SubIndex<'a>)>>::new();
+	for (dir_index, dir) in index.dirs.iter().enumerate() {
 		if !allowlist.is_allowed(&dir.meta.path) {
 			continue;
 		}
-
 		let sub_index = index.sub_index(dir_index);
 		let stats = dir_stats(&sub_index);
 		if stats.file_size == 0 {
 			continue;
 		}
-
-		let mut file_checksums: Vec<_> =
-			sub_index.files().iter().map(|entry| entry.checksum.clone()).collect();
-		file_checksums.sort();
-
-		dirs_by_checksums.entry((stats, file_checksums)).or_default().push(dir);
+		dirs_by_stats.entry(stats).or_default().push((dir, sub_index));
 	}
+	let hashed = dirs_by_stats.values().flatten().all(|(dir, _)| !dir.checksum.is_empty());
 
 	let mut matches = Vec::new();
-	for (_, mut path_list) in dirs_by_checksums {
-		if path_list.len() > 1 {
-			path_list.sort();
-			matches.push(path_list);
+	for (_, dir_list) in dirs_by_stats {
+		if dir_list.len() < 2 {
+			continue;
+		}
+		let mut dirs_by_key = HashMap::<(Vec<Checksum>, LayoutKey<'a>), Vec<&'a Dir>>::new();
+		for (dir, sub_index) in dir_list {
+			let key = if !strict {
+				let mut file_checksums: Vec<_> =
+					sub_index.files().iter().map(|entry| entry.checksum.clone()).collect();
+				file_checksums.sort();
+				(file_checksums, LayoutKey::default())
+			} else if hashed {
+				(vec![dir.checksum.clone()], LayoutKey::default())
+			} else {
+				(Vec::new(), layout_key(dir, &sub_index))
+			};
+			dirs_by_key.entry(key).or_default().push(dir);
+		}
+		for (_, mut path_list) in dirs_by_key {
+			if path_list.len() > 1 {
+				path_list.sort();
+				matches.push(path_list);
+			}
 		}
 	}
 	matches.sort();
 	matches
 }
+
+// Returns the groups of the duplicate dirs containing the path, each with the path relative to
+// that dir.
+fn ancestor_groups<'a>(
+	group_by_path: &HashMap<&str, usize>,
+	path: &'a str,
+) -> HashSet<(usize, &'a str)> {
+	let mut ancestors = HashSet::new();
+	let mut current = parent_str(path);
+	while let Some(dir) = current {
+		if let Some(group) = group_by_path.get(dir) {
+			ancestors.insert((*group, relative_path(dir, path)));
+		}
+		current = parent_str(dir);
+	}
+	ancestors
+}
+
+/// Removes the groups of duplicate dirs which are implied by the group of their ancestors, where
+/// every dir is at the same relative path inside a dir of that single group.
+pub fn outermost_duplicate_dirs(groups: Vec<Vec<&Dir>>) -> Vec<Vec<&Dir>> {
+	let group_by_path: HashMap<_, _> = groups
+		.iter()
+		.enumerate()
+		.flat_map(|(group_index, group)| {
+			group.iter().map(move |dir| (dir.meta.path(), group_index))
+		})
+		.collect();
+	let nested: Vec<_> = groups
+		.iter()
+		.map(|group| {
+			let mut ancestors =
+				group.iter().map(|dir| ancestor_groups(&group_by_path, dir.meta.path()));
+			let Some(mut common) = ancestors.next() else {
+				return false;
+			};
+			for other in ancestors {
+				common.retain(|ancestor| other.contains(ancestor));
+			}
+			!common.is_empty()
+		})
+		.collect();
+	groups.into_iter().zip(nested).filter(|(_, nested)| !nested).map(|(group, _)| group).collect()
+}
@@ -0,0 +1,232 @@
+use super::duplicate_dirs;
+use super::outermost_duplicate_dirs;
+use super::potential_dir_matches;
+use super::test_fixtures::create_dir;
+use super::test_fixtures::create_file;
+use crate::index::calculator::Allowlist;
+use crate::index::model::Checksum;
+use crate::index::model::Dir;
+use crate::index::SubIndex;
+
+fn paths(groups: &[Vec<&Dir>]) -> Vec<Vec<String>> {
+	groups
+		.iter()
+		.map(|group| group.iter().map(|dir| dir.meta.path().to_string()).collect())
+		.collect()
+}
+
+#[test]
+fn outermost_skips_nested_groups() {
+	let dirs = [
+		create_dir("a"),
+		create_dir("a/x"),
+		create_dir("b"),
+		create_dir("b/x"),
+	];
+	let groups = vec![
+		vec![
+			&dirs[0],
+			&dirs[2],
+		],
+		vec![
+			&dirs[1],
+			&dirs[3],
+		],
+	];
+	assert_eq!(
+		paths(&outermost_duplicate_dirs(groups)),
+		vec![
+			vec![
+				"a".to_string(),
+				"b".to_string()
+			]
+		]
+	);
+}
+
+#[test]
+fn outermost_keeps_partially_nested_groups() {
+	let dirs = [
+		create_dir("a"),
+		create_dir("a-b"),
+		create_dir("a/x"),
+		create_dir("b"),
+		create_dir("b/x"),
+	];
+	let groups = vec![
+		vec![
+			&dirs[0],
+			&dirs[3],
+		],
+		vec![
+			&dirs[1],
+			&dirs[2],
+			&dirs[4],
+		],
+	];
+	assert_eq!(
+		paths(&outermost_duplicate_dirs(groups)),
+		vec![
+			vec![
+				"a".to_string(),
+				"b".to_string()
+			],
+			vec![
+				"a-b".to_string(),
+				"a/x".to_string(),
+				"b/x".to_string()
+			],
+		]
+	);
+}
+
+#[test]
+fn outermost_keeps_groups_across_ancestor_groups() {
+	let dirs = [
+		create_dir("a"),
+		create_dir("a/x"),
+		create_dir("a/z"),
+		create_dir("b"),
+		create_dir("b/z"),
+		create_dir("d"),
+		create_dir("d/x"),
+		create_dir("e"),
+	];
+	// `a/x` and `d/x` are inside dirs of different groups, so they are not implied by either.
+	let groups = vec![
+		vec![
+			&dirs[0],
+			&dirs[3],
+		],
+		vec![
+			&dirs[5],
+			&dirs[7],
+		],
+		vec![
+			&dirs[1],
+			&dirs[6],
+		],
+		vec![
+			&dirs[2],
+			&dirs[4],
+		],
+	];
+	assert_eq!(
+		paths(&outermost_duplicate_dirs(groups)),
+		vec![
+			vec![
+				"a".to_string(),
+				"b".to_string()
+			],
+			vec![
+				"d".to_string(),
+				"e".to_string()
+			],
+			vec![
+				"a/x".to_string(),
+				"d/x".to_string()
+			],
+		]
+	);
+}
+
+#[test]
+fn strict_requires_same_layout() {
+	let files = vec![
+		create_file("a/1", 1, 0, "1"),
+		create_file("a/2", 1, 0, "2"),
+		create_file("b/1", 1, 0, "1"),
+		create_file("b/2", 1, 0, "2"),
+		create_file("c/1", 1, 0, "2"),
+		create_file("c/2", 1, 0, "1"),
+	];
+	let dirs = vec![
+		create_dir("a"),
+		create_dir("b"),
+		create_dir("c"),
+	];
+	let index = SubIndex {
+		files: &files,
+		dirs: &dirs,
+	};
+	let allowlist = Allowlist::allow_all();
+	assert_eq!(
+		paths(&duplicate_dirs(&index, &allowlist, false)),
+		vec![
+			vec![
+				"a".to_string(),
+				"b".to_string(),
+				"c".to_string()
+			]
+		]
+	);
+	assert_eq!(
+		paths(&duplicate_dirs(&index, &allowlist, true)),
+		vec![
+			vec![
+				"a".to_string(),
+				"b".to_string()
+			]
+		]
+	);
+}
+
+#[test]
+fn strict_groups_partly_hashed_dirs() {
+	let files = vec![
+		create_file("a/1", 1, 0, "1"),
+		create_file("b/1", 1, 0, "1"),
+	];
+	// Only `a` has its checksum stored, so both are compared by layout.
+	let dirs = vec![
+		Dir {
+			checksum: Checksum {
+				sha512: "a".to_string(),
+			},
+			..create_dir("a")
+		},
+		create_dir("b"),
+	];
+	let index = SubIndex {
+		files: &files,
+		dirs: &dirs,
+	};
+	assert_eq!(
+		paths(&duplicate_dirs(&index, &Allowlist::allow_all(), true)),
+		vec![
+			vec![
+				"a".to_string(),
+				"b".to_string()
+			]
+		]
+	);
+}
+
+#[test]
+fn potential_matches_compare_relative_names() {
+	let files = vec![
+		create_file("a/1", 1, 0, ""),
+		create_file("b/1", 1, 0, ""),
+		create_file("c/2", 1, 0, ""),
+	];
+	let dirs = vec![
+		create_dir("a"),
+		create_dir("b"),
+		create_dir("c"),
+	];
+	let index = SubIndex {
+		files: &files,
+		dirs: &dirs,
+	};
+	let allowlist = Allowlist::allow_all();
+	let mut matches: Vec<_> =
+		potential_dir_matches(&index, &allowlist, true, false, false).collect();
+	matches.sort_unstable();
+	assert_eq!(
+		matches,
+		vec![
+			0,
+			1
+		]
+	);
+}
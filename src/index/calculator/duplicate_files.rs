@@ -1,7 +1,11 @@
+use std::cmp::Reverse;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::hash::Hash;
 use std::time::SystemTime;
 
+use clap::ValueEnum;
+
 use super::Allowlist;
 use crate::index::model::Checksum;
 use crate::index::model::File;
@@ -53,13 +57,14 @@ impl FileAttributeCounter<SystemTime> {
 	}
 }
 
-pub fn potential_file_matches<'a>(
-	files: &'a [File],
+// Returns the positions of the files which may have a duplicate among the others.
+pub(super) fn potential_matches(
+	files: &[&File],
 	allowlist: &Allowlist,
 	match_name: bool,
 	match_created: bool,
 	match_modified: bool,
-) -> impl Iterator<Item = usize> + use<'a> {
+) -> Vec<usize> {
 	let mut file_index_by_size = HashMap::<u64, Vec<usize>>::new();
 	for (file_index, file) in files.iter().enumerate() {
 		if !allowlist.is_allowed(&file.meta.path) {
@@ -68,13 +73,15 @@ pub fn potential_file_matches<'a>(
 		file_index_by_size.entry(file.size).or_default().push(file_index);
 	}
 
-	file_index_by_size.into_values().filter(|path_list| path_list.len() > 1).flat_map(
-		move |path_list| {
+	file_index_by_size
+		.into_values()
+		.filter(|path_list| path_list.len() > 1)
+		.flat_map(|path_list| {
 			let mut name_counter = FileAttributeCounter::with_name_matcher();
 			let mut created_counter = FileAttributeCounter::with_created_matcher();
 			let mut modified_counter = FileAttributeCounter::with_modified_matcher();
 			for file_index in &path_list {
-				let file = &files[*file_index];
+				let file = files[*file_index];
 				if match_name {
 					name_counter.record(file);
 				}
@@ -87,7 +94,7 @@ pub fn potential_file_matches<'a>(
 			}
 
 			path_list.into_iter().filter(move |file_index| {
-				let file = &files[*file_index];
+				let file = files[*file_index];
 				if match_name && !name_counter.has_matches(file) {
 					return false;
 				}
@@ -99,28 +106,143 @@ pub fn potential_file_matches<'a>(
 				}
 				true
 			})
-		},
-	)
+		})
+		.collect()
+}
+
+pub fn potential_file_matches(
+	files: &[File],
+	allowlist: &Allowlist,
+	match_name: bool,
+	match_created: bool,
+	match_modified: bool,
+) -> impl Iterator<Item = usize> {
+	let files: Vec<_> = files.iter().collect();
+	potential_matches(&files, allowlist, match_name, match_created, match_modified).into_iter()
 }
 
-pub fn duplicates<'a>(files: &'a [File], allowlist: &Allowlist) -> Vec<Vec<&'a File>> {
-	let mut path_by_checksum = HashMap::<(Checksum, u64), Vec<&File>>::new();
-	for file in files {
-		if !file.checksum.is_empty() {
-			if !allowlist.is_allowed(&file.meta.path) {
+/// Like `potential_file_matches`, but matching files across several lists, such as the files of
+/// different indexes. Returns the position of each list and of the file in it.
+pub fn potential_file_matches_across(
+	file_lists: &[&[File]],
+	allowlist: &Allowlist,
+	match_name: bool,
+	match_created: bool,
+	match_modified: bool,
+) -> Vec<(usize, usize)> {
+	let positions: Vec<_> = file_lists
+		.iter()
+		.enumerate()
+		.flat_map(|(list_index, files)| (0..files.len()).map(move |i| (list_index, i)))
+		.collect();
+	let files: Vec<_> = file_lists.iter().flat_map(|files| files.iter()).collect();
+	potential_matches(&files, allowlist, match_name, match_created, match_modified)
+		.into_iter()
+		.map(|i| positions[i])
+		.collect()
+}
+
+/// Order of groups of duplicate files.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateSort {
+	/// Most reclaimable bytes first.
+	Wasted,
+	/// Largest files first.
+	Size,
+	/// Most copies first.
+	Count,
+	/// Sorted by path.
+	Path,
+}
+
+/// Which groups of duplicate files are returned, and in which order.
+pub struct DuplicateOptions {
+	pub sort: DuplicateSort,
+	/// Skips groups of files smaller than this size.
+	pub min_size: u64,
+	/// Skips groups which would free less than this size by keeping a single copy.
+	pub min_wasted: u64,
+	/// Skips groups whose files are all in the same list, when matching across lists.
+	pub across: bool,
+}
+
+impl Default for DuplicateOptions {
+	fn default() -> Self {
+		Self {
+			sort: DuplicateSort::Path,
+			min_size: 0,
+			min_wasted: 0,
+			across: false,
+		}
+	}
+}
+
+/// Returns the size freed by keeping a single copy of a group of duplicates of the given size.
+pub fn wasted_size(size: u128, copies: usize) -> u128 {
+	size * (copies.saturating_sub(1) as u128)
+}
+
+/// Groups files with the same checksum, each sorted by path. Groups are filtered and sorted by the
+/// options, with ties sorted by path.
+pub fn duplicates<'a>(
+	files: &'a [File],
+	allowlist: &Allowlist,
+	options: &DuplicateOptions,
+) -> Vec<Vec<&'a File>> {
+	duplicates_across(&[files], allowlist, options)
+		.into_iter()
+		.map(|group| group.into_iter().map(|(_, file)| file).collect())
+		.collect()
+}
+
+/// Like `duplicates`, but grouping files across several lists. Each file comes with the position of
+/// its list, and groups are sorted by list then path. A path found in several lists is only kept in
+/// the first one, so overlapping lists do not report a file as its own duplicate.
+pub fn duplicates_across<'a>(
+	file_lists: &[&'a [File]],
+	allowlist: &Allowlist,
+	options: &DuplicateOptions,
+) -> Vec<Vec<(usize, &'a File)>> {
+	let mut path_by_checksum = HashMap::<(Checksum, u64), Vec<(usize, &File)>>::new();
+	for (list_index, files) in file_lists.iter().enumerate() {
+		for file in *files {
+			if file.checksum.is_empty() || !allowlist.is_allowed(&file.meta.path) {
 				continue;
 			}
-			path_by_checksum.entry((file.checksum.clone(), file.size)).or_default().push(file);
+			path_by_checksum
+				.entry((file.checksum.clone(), file.size))
+				.or_default()
+				.push((list_index, file));
 		}
 	}
 
 	let mut matches = Vec::new();
-	for (_, mut path_list) in path_by_checksum {
-		if path_list.len() > 1 {
-			path_list.sort();
-			matches.push(path_list);
+	for ((_, size), mut path_list) in path_by_checksum {
+		// Lists may overlap, so a path found in several of them is kept only in the first one.
+		path_list.sort();
+		let mut paths = HashSet::new();
+		path_list.retain(|(_, file)| paths.insert(file.meta.path()));
+		if path_list.len() < 2 || size < options.min_size {
+			continue;
+		}
+		if wasted_size(u128::from(size), path_list.len()) < u128::from(options.min_wasted) {
+			continue;
 		}
+		if options.across && path_list.iter().all(|(list_index, _)| *list_index == path_list[0].0) {
+			continue;
+		}
+		matches.push(path_list);
 	}
 	matches.sort();
+	match options.sort {
+		DuplicateSort::Wasted => {
+			matches.sort_by_key(|group| {
+				Reverse(wasted_size(u128::from(group[0].1.size), group.len()))
+			});
+		}
+		DuplicateSort::Size => matches.sort_by_key(|group| Reverse(group[0].1.size)),
+		DuplicateSort::Count => matches.sort_by_key(|group| Reverse(group.len())),
+		DuplicateSort::Path => {}
+	}
 	matches
 }
@@ -1,7 +1,12 @@
 use std::time::Duration;
 use std::time::SystemTime;
 
+use super::duplicates;
+use super::duplicates_across;
 use super::potential_file_matches;
+use super::potential_file_matches_across;
+use super::DuplicateOptions;
+use super::DuplicateSort;
 use crate::index::calculator::Allowlist;
 use crate::index::model::Checksum;
 use crate::index::model::File;
@@ -18,6 +23,7 @@ fn create_file(name: &str, size: u64, created: u64, modified: u64) -> File {
 		},
 		size,
 		checksum: Checksum::new(),
+		linked: None,
 	}
 }
 
@@ -110,3 +116,246 @@ fn potentially_matching_modified_different_size() {
 	let matches: Vec<_> = potential_file_matches(&files, &allowlist, false, false, true).collect();
 	assert!(matches.is_empty());
 }
+
+fn create_checksummed_file(name: &str, size: u64, checksum: &str) -> File {
+	let mut file = create_file(name, size, 1, 1);
+	file.checksum = Checksum {
+		sha512: checksum.to_string(),
+	};
+	file
+}
+
+fn duplicate_paths(files: &[File], options: &DuplicateOptions) -> Vec<Vec<String>> {
+	duplicates(files, &Allowlist::allow_all(), options)
+		.iter()
+		.map(|group| group.iter().map(|file| file.meta.path.clone()).collect())
+		.collect()
+}
+
+fn duplicate_test_files() -> Vec<File> {
+	vec![
+		// Three copies of 10 bytes, wasting 20.
+		create_checksummed_file("a1", 10, "a"),
+		create_checksummed_file("a2", 10, "a"),
+		create_checksummed_file("a3", 10, "a"),
+		// Two copies of 15 bytes, wasting 15.
+		create_checksummed_file("b2", 15, "b"),
+		create_checksummed_file("b1", 15, "b"),
+		// Two copies of 30 bytes, wasting 30.
+		create_checksummed_file("c1", 30, "c"),
+		create_checksummed_file("c2", 30, "c"),
+		create_checksummed_file("d", 40, "d"),
+	]
+}
+
+#[test]
+fn duplicates_sorted() {
+	let files = duplicate_test_files();
+	let first_paths = |sort| {
+		let options = DuplicateOptions {
+			sort,
+			..DuplicateOptions::default()
+		};
+		duplicate_paths(&files, &options).iter().map(|group| group[0].clone()).collect::<Vec<_>>()
+	};
+	assert_eq!(
+		first_paths(DuplicateSort::Path),
+		vec![
+			"a1",
+			"b1",
+			"c1"
+		]
+	);
+	assert_eq!(
+		first_paths(DuplicateSort::Wasted),
+		vec![
+			"c1",
+			"a1",
+			"b1"
+		]
+	);
+	assert_eq!(
+		first_paths(DuplicateSort::Size),
+		vec![
+			"c1",
+			"b1",
+			"a1"
+		]
+	);
+	assert_eq!(
+		first_paths(DuplicateSort::Count),
+		vec![
+			"a1",
+			"b1",
+			"c1"
+		]
+	);
+}
+
+#[test]
+fn duplicates_filtered_by_size() {
+	let files = duplicate_test_files();
+	let options = DuplicateOptions {
+		min_size: 15,
+		..DuplicateOptions::default()
+	};
+	assert_eq!(
+		duplicate_paths(&files, &options),
+		vec![
+			vec![
+				"b1",
+				"b2"
+			],
+			vec![
+				"c1",
+				"c2"
+			]
+		]
+	);
+	let options = DuplicateOptions {
+		min_wasted: 20,
+		..DuplicateOptions::default()
+	};
+	assert_eq!(
+		duplicate_paths(&files, &options),
+		vec![
+			vec![
+				"a1",
+				"a2",
+				"a3"
+			],
+			vec![
+				"c1",
+				"c2"
+			]
+		]
+	);
+}
+
+#[test]
+fn potentially_matching_across_lists() {
+	let first = vec![
+		create_file("a", 1, 1, 1),
+		create_file("b", 2, 1, 1),
+	];
+	let second = vec![
+		create_file("c", 3, 1, 1),
+		create_file("d", 1, 1, 1),
+	];
+	let allowlist = Allowlist::allow_all();
+	let mut matches = potential_file_matches_across(
+		&[
+			&first,
+			&second,
+		],
+		&allowlist,
+		false,
+		false,
+		false,
+	);
+	matches.sort_unstable();
+	assert_eq!(
+		matches,
+		vec![
+			(0, 0),
+			(1, 1)
+		]
+	);
+}
+
+#[test]
+fn duplicates_across_lists() {
+	let first = vec![
+		create_checksummed_file("a1", 10, "a"),
+		create_checksummed_file("a2", 10, "a"),
+		create_checksummed_file("b1", 10, "b"),
+	];
+	let second = vec![
+		create_checksummed_file("b2", 10, "b"),
+		create_checksummed_file("a3", 10, "a"),
+	];
+	let allowlist = Allowlist::allow_all();
+	let groups = |across| {
+		let options = DuplicateOptions {
+			across,
+			..DuplicateOptions::default()
+		};
+		duplicates_across(
+			&[
+				&first,
+				&second,
+			],
+			&allowlist,
+			&options,
+		)
+		.iter()
+		.map(|group| group.iter().map(|(list, file)| (*list, file.meta.path.as_str())).collect())
+		.collect::<Vec<Vec<_>>>()
+	};
+	assert_eq!(
+		groups(false),
+		vec![
+			vec![
+				(0, "a1"),
+				(0, "a2"),
+				(1, "a3")
+			],
+			vec![
+				(0, "b1"),
+				(1, "b2")
+			]
+		]
+	);
+
+	assert_eq!(groups(true), groups(false));
+
+	// Only the copies of `a` in the first list are duplicated.
+	let options = DuplicateOptions {
+		across: true,
+		..DuplicateOptions::default()
+	};
+	assert!(duplicates_across(
+		&[
+			&first,
+			&[]
+		],
+		&allowlist,
+		&options
+	)
+	.is_empty());
+}
+
+#[test]
+fn duplicates_across_overlapping_lists() {
+	let first = vec![
+		create_checksummed_file("a1", 10, "a"),
+		create_checksummed_file("b1", 10, "b"),
+		create_checksummed_file("z", 10, "z"),
+	];
+	let second = vec![
+		create_checksummed_file("a1", 10, "a"),
+		create_checksummed_file("a2", 10, "a"),
+		create_checksummed_file("z", 10, "z"),
+	];
+	let allowlist = Allowlist::allow_all();
+	let groups: Vec<Vec<_>> = duplicates_across(
+		&[
+			&first,
+			&second,
+		],
+		&allowlist,
+		&DuplicateOptions::default(),
+	)
+	.iter()
+	.map(|group| group.iter().map(|(list, file)| (*list, file.meta.path.as_str())).collect())
+	.collect();
+	assert_eq!(
+		groups,
+		vec![
+			vec![
+				(0, "a1"),
+				(1, "a2")
+			]
+		]
+	);
+}
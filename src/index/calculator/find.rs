@@ -0,0 +1,121 @@
+use std::time::SystemTime;
+
+use clap::ValueEnum;
+use regex::Regex;
+
+use crate::index::model::Checksum;
+use crate::index::model::Dir;
+use crate::index::model::File;
+use crate::index::model::Metadata;
+use crate::index::SubIndex;
+
+/// Kind of an indexed entry.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+	File,
+	Dir,
+}
+
+/// An indexed entry matching a query.
+pub enum FoundEntry<'a> {
+	File(&'a File),
+	Dir(&'a Dir),
+}
+
+impl FoundEntry<'_> {
+	pub fn meta(&self) -> &Metadata {
+		match self {
+			Self::File(file) => &file.meta,
+			Self::Dir(dir) => &dir.meta,
+		}
+	}
+}
+
+/// Predicates that entries must all match. Lists match when any of their values do, or when they
+/// are empty.
+#[derive(Default)]
+pub struct FindQuery {
+	pub kind: Option<EntryKind>,
+	/// Regexes matching the whole name, such as parsed from globs.
+	pub names: Vec<Regex>,
+	pub paths: Vec<Regex>,
+	/// Extensions without the dot, matched without case.
+	pub extensions: Vec<String>,
+	/// Size range of files, including both ends. Dirs never match a size.
+	pub min_size: Option<u64>,
+	pub max_size: Option<u64>,
+	pub modified_after: Option<SystemTime>,
+	pub modified_before: Option<SystemTime>,
+	pub created_after: Option<SystemTime>,
+	pub created_before: Option<SystemTime>,
+	/// Start of the hexadecimal SHA-512, matched without case.
+	pub checksum_prefix: Option<String>,
+	pub has_checksum: Option<bool>,
+	/// Whether the entry is hidden, either by its attributes or by a name starting with a dot.
+	pub hidden: Option<bool>,
+}
+
+fn in_range<T: PartialOrd + Copy>(value: T, min: Option<T>, max: Option<T>) -> bool {
+	min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+}
+
+impl FindQuery {
+	fn matches_meta(&self, meta: &Metadata) -> bool {
+		let name = meta.name();
+		if !self.names.is_empty() && !self.names.iter().any(|regex| regex.is_match(name)) {
+			return false;
+		}
+		if !self.paths.is_empty() && !self.paths.iter().any(|regex| regex.is_match(meta.path())) {
+			return false;
+		}
+		if !self.extensions.is_empty() {
+			let Some((_, extension)) = name.rsplit_once('.') else {
+				return false;
+			};
+			if !self.extensions.iter().any(|other| other.eq_ignore_ascii_case(extension)) {
+				return false;
+			}
+		}
+		if self.hidden.is_some_and(|hidden| hidden != (meta.hidden || name.starts_with('.'))) {
+			return false;
+		}
+		in_range(meta.modified_time, self.modified_after, self.modified_before)
+			&& in_range(meta.created_time, self.created_after, self.created_before)
+	}
+
+	fn matches_checksum(&self, checksum: &Checksum) -> bool {
+		if self.has_checksum.is_some_and(|has_checksum| has_checksum == checksum.is_empty()) {
+			return false;
+		}
+		self.checksum_prefix.as_ref().is_none_or(|prefix| {
+			checksum
+				.sha512
+				.get(..prefix.len())
+				.is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+		})
+	}
+
+	fn matches_file(&self, file: &File) -> bool {
+		self.kind != Some(EntryKind::Dir)
+			&& in_range(file.size, self.min_size, self.max_size)
+			&& self.matches_checksum(&file.checksum)
+			&& self.matches_meta(&file.meta)
+	}
+
+	fn matches_dir(&self, dir: &Dir) -> bool {
+		self.kind != Some(EntryKind::File)
+			&& self.min_size.is_none()
+			&& self.max_size.is_none()
+			&& self.matches_checksum(&dir.checksum)
+			&& self.matches_meta(&dir.meta)
+	}
+}
+
+/// Returns the entries of the index matching the query, sorted by path.
+pub fn find<'a>(index: &SubIndex<'a>, query: &FindQuery) -> Vec<FoundEntry<'a>> {
+	let files = index.files.iter().filter(|file| query.matches_file(file)).map(FoundEntry::File);
+	let dirs = index.dirs.iter().filter(|dir| query.matches_dir(dir)).map(FoundEntry::Dir);
+	let mut found: Vec<_> = files.chain(dirs).collect();
+	found.sort_by(|lhs, rhs| lhs.meta().path().cmp(rhs.meta().path()));
+	found
+}
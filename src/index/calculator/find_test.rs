@@ -0,0 +1,150 @@
+use std::time::Duration;
+use std::time::SystemTime;
+
+use super::find;
+use super::test_fixtures::create_dir;
+use super::test_fixtures::create_file;
+use super::test_fixtures::create_meta;
+use super::EntryKind;
+use super::FindQuery;
+use super::FoundEntry;
+use crate::index::model::Checksum;
+use crate::index::model::Dir;
+use crate::index::model::Metadata;
+use crate::index::SubIndex;
+use crate::util::glob::parse_glob;
+
+fn find_paths(query: &FindQuery) -> Vec<String> {
+	let files = vec![
+		create_file("a/.hidden", 1, 10, ""),
+		create_file("a/IMG_1.JPG", 100, 20, "abc123"),
+		create_file("a/b/IMG_2.jpg", 200, 30, "def456"),
+		create_file("a/b/notes.txt", 5, 40, ""),
+	];
+	let dirs = vec![
+		create_dir("a"),
+		Dir {
+			meta: Metadata {
+				hidden: true,
+				..create_meta("a/b", 0)
+			},
+			checksum: Checksum::new(),
+		},
+	];
+	let index = SubIndex {
+		files: &files,
+		dirs: &dirs,
+	};
+	find(&index, query)
+		.iter()
+		.map(|entry| {
+			let kind = match entry {
+				FoundEntry::File(_) => "",
+				FoundEntry::Dir(_) => "/",
+			};
+			format!("{}{kind}", entry.meta().path())
+		})
+		.collect()
+}
+
+#[test]
+fn find_by_name_and_extension() {
+	let query = FindQuery {
+		names: vec![parse_glob("IMG_*").unwrap()],
+		..FindQuery::default()
+	};
+	assert_eq!(
+		find_paths(&query),
+		vec![
+			"a/IMG_1.JPG",
+			"a/b/IMG_2.jpg"
+		]
+	);
+
+	let query = FindQuery {
+		extensions: vec![
+			"jpg".to_string(),
+			"txt".to_string(),
+		],
+		..FindQuery::default()
+	};
+	assert_eq!(
+		find_paths(&query),
+		vec![
+			"a/IMG_1.JPG",
+			"a/b/IMG_2.jpg",
+			"a/b/notes.txt"
+		]
+	);
+
+	let query = FindQuery {
+		hidden: Some(true),
+		..FindQuery::default()
+	};
+	// Entries are hidden by their attributes too.
+	assert_eq!(
+		find_paths(&query),
+		vec![
+			"a/.hidden",
+			"a/b/"
+		]
+	);
+}
+
+#[test]
+fn find_by_ranges() {
+	let query = FindQuery {
+		min_size: Some(5),
+		max_size: Some(100),
+		..FindQuery::default()
+	};
+	assert_eq!(
+		find_paths(&query),
+		vec![
+			"a/IMG_1.JPG",
+			"a/b/notes.txt"
+		]
+	);
+
+	let query = FindQuery {
+		modified_after: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(15)),
+		modified_before: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(30)),
+		..FindQuery::default()
+	};
+	assert_eq!(
+		find_paths(&query),
+		vec![
+			"a/IMG_1.JPG",
+			"a/b/IMG_2.jpg"
+		]
+	);
+}
+
+#[test]
+fn find_by_checksum_and_kind() {
+	let query = FindQuery {
+		checksum_prefix: Some("ABC".to_string()),
+		..FindQuery::default()
+	};
+	assert_eq!(find_paths(&query), vec!["a/IMG_1.JPG"]);
+
+	let query = FindQuery {
+		has_checksum: Some(false),
+		kind: Some(EntryKind::File),
+		..FindQuery::default()
+	};
+	assert_eq!(
+		find_paths(&query),
+		vec![
+			"a/.hidden",
+			"a/b/notes.txt"
+		]
+	);
+
+	let query = FindQuery {
+		paths: vec![regex::Regex::new("^a/b").unwrap()],
+		kind: Some(EntryKind::Dir),
+		..FindQuery::default()
+	};
+	assert_eq!(find_paths(&query), vec!["a/b/"]);
+}
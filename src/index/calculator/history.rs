@@ -0,0 +1,165 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::diff::FileStep;
+use super::diff::FileWalk;
+use super::diff::UnmatchedFiles;
+use super::Diff;
+use crate::index::model::Dir;
+use crate::index::model::File;
+
+/// Changes which turn a later state of an index back into an earlier snapshot of it.
+#[derive(Serialize, Deserialize, Default)]
+pub struct SnapshotDelta {
+	/// Files of the snapshot which were removed or changed afterwards.
+	#[serde(
+		default,
+		skip_serializing_if = "Vec::is_empty"
+	)]
+	pub files: Vec<File>,
+	/// Dirs of the snapshot which were removed or changed afterwards.
+	#[serde(
+		default,
+		skip_serializing_if = "Vec::is_empty"
+	)]
+	pub dirs: Vec<Dir>,
+	/// Paths of the files which were added afterwards.
+	#[serde(
+		default,
+		skip_serializing_if = "Vec::is_empty"
+	)]
+	pub added_files: Vec<String>,
+	/// Paths of the dirs which were added afterwards.
+	#[serde(
+		default,
+		skip_serializing_if = "Vec::is_empty"
+	)]
+	pub added_dirs: Vec<String>,
+}
+
+impl SnapshotDelta {
+	pub fn is_empty(&self) -> bool {
+		self.files.is_empty()
+			&& self.dirs.is_empty()
+			&& self.added_files.is_empty()
+			&& self.added_dirs.is_empty()
+	}
+
+	/// Turns the sorted entries of the later state back into the snapshot, keeping them sorted.
+	pub fn apply(&self, files: &mut Vec<File>, dirs: &mut Vec<Dir>) {
+		apply_entries(files, &self.files, &self.added_files, |file| file.meta.path());
+		apply_entries(dirs, &self.dirs, &self.added_dirs, |dir| dir.meta.path());
+	}
+}
+
+// Replaces the added and changed entries with the ones of the snapshot.
+fn apply_entries<T: Clone>(
+	entries: &mut Vec<T>,
+	snapshot_entries: &[T],
+	added_paths: &[String],
+	path_fn: impl Fn(&T) -> &str,
+) {
+	let replaced: HashSet<&str> = added_paths
+		.iter()
+		.map(String::as_str)
+		.chain(snapshot_entries.iter().map(&path_fn))
+		.collect();
+	entries.retain(|entry| !replaced.contains(path_fn(entry)));
+	entries.extend_from_slice(snapshot_entries);
+	entries.sort_by(|lhs, rhs| path_fn(lhs).cmp(path_fn(rhs)));
+}
+
+// Walks two lists of entries sorted by path, collecting the entries of the first which are missing
+// or different in the second, and the paths only found in the second.
+fn entry_delta<T: Clone + PartialEq>(
+	entries: &[T],
+	later_entries: &[T],
+	path_fn: impl Fn(&T) -> &str,
+) -> (Vec<T>, Vec<String>) {
+	let mut changed = Vec::new();
+	let mut added = Vec::new();
+	let mut index = 0;
+	let mut later_index = 0;
+	loop {
+		let ordering = match (entries.get(index), later_entries.get(later_index)) {
+			(Some(entry), Some(later_entry)) => path_fn(entry).cmp(path_fn(later_entry)),
+			(Some(_), None) => Ordering::Less,
+			(None, Some(_)) => Ordering::Greater,
+			(None, None) => break,
+		};
+		match ordering {
+			Ordering::Less => {
+				changed.push(entries[index].clone());
+				index += 1;
+			}
+			Ordering::Greater => {
+				added.push(path_fn(&later_entries[later_index]).to_string());
+				later_index += 1;
+			}
+			Ordering::Equal => {
+				if entries[index] != later_entries[later_index] {
+					changed.push(entries[index].clone());
+				}
+				index += 1;
+				later_index += 1;
+			}
+		}
+	}
+	(changed, added)
+}
+
+/// Calculates the delta which turns the later entries back into the earlier ones. Both must be
+/// sorted by path, like in an index.
+pub fn snapshot_delta(
+	files: &[File],
+	dirs: &[Dir],
+	later_files: &[File],
+	later_dirs: &[Dir],
+) -> SnapshotDelta {
+	let (files, added_files) = entry_delta(files, later_files, |file| file.meta.path());
+	let (dirs, added_dirs) = entry_delta(dirs, later_dirs, |dir| dir.meta.path());
+	SnapshotDelta {
+		files,
+		dirs,
+		added_files,
+		added_dirs,
+	}
+}
+
+/// Finds the differences between the files of two snapshots from their metadata alone, since past
+/// contents cannot be read. Files are changed when their size, modified time or known checksums
+/// differ, and are moved or copied when their checksums match. Both lists must be sorted by path.
+pub fn snapshot_diff(self_files: &[File], other_files: &[File]) -> Vec<Diff> {
+	let mut diff_list = Vec::new();
+	let mut unmatched = UnmatchedFiles::default();
+	let mut walk = FileWalk::default();
+	while let Some(step) = walk.next(self_files, other_files) {
+		match step {
+			FileStep::SelfOnly(file_index) => {
+				unmatched.add_self(self_files, file_index, &mut diff_list);
+			}
+			FileStep::OtherOnly(file_index) => {
+				unmatched.add_other(other_files, file_index, &mut diff_list);
+			}
+			FileStep::Both(file_index_self, file_index_other) => {
+				let file_self = &self_files[file_index_self];
+				let file_other = &other_files[file_index_other];
+				let checksums_differ = !file_self.checksum.is_empty()
+					&& !file_other.checksum.is_empty()
+					&& file_self.checksum != file_other.checksum;
+				if file_self.size != file_other.size
+					|| file_self.meta.modified_time() != file_other.meta.modified_time()
+					|| checksums_differ
+				{
+					diff_list.push(Diff::Changed(file_self.meta.path().to_string()));
+				}
+			}
+		}
+	}
+	unmatched.pair(self_files, other_files, &mut diff_list);
+	diff_list.sort();
+	diff_list
+}
@@ -0,0 +1,78 @@
+use super::snapshot_delta;
+use super::snapshot_diff;
+use super::test_fixtures::create_dir;
+use super::test_fixtures::create_file;
+use crate::index::model::File;
+use crate::index::Diff;
+
+fn paths(files: &[File]) -> Vec<&str> {
+	files.iter().map(|file| file.meta.path()).collect()
+}
+
+#[test]
+fn delta_restores_snapshot() {
+	let files = vec![
+		create_file("r/a/changed", 1, 1, ""),
+		create_file("r/a/removed", 2, 1, ""),
+		create_file("r/same", 3, 1, "same"),
+	];
+	let dirs = vec![
+		create_dir("r"),
+		create_dir("r/a"),
+	];
+	let later_files = vec![
+		create_file("r/a/changed", 5, 2, ""),
+		create_file("r/b/added", 4, 1, ""),
+		create_file("r/same", 3, 1, "same"),
+	];
+	let later_dirs = vec![
+		create_dir("r"),
+		create_dir("r/a"),
+		create_dir("r/b"),
+	];
+
+	let delta = snapshot_delta(&files, &dirs, &later_files, &later_dirs);
+	assert_eq!(
+		paths(&delta.files),
+		[
+			"r/a/changed",
+			"r/a/removed"
+		]
+	);
+	assert!(delta.dirs.is_empty());
+	assert_eq!(delta.added_files, ["r/b/added"]);
+	assert_eq!(delta.added_dirs, ["r/b"]);
+
+	let mut restored_files = later_files.clone();
+	let mut restored_dirs = later_dirs.clone();
+	delta.apply(&mut restored_files, &mut restored_dirs);
+	assert!(restored_files == files);
+	assert!(restored_dirs == dirs);
+
+	assert!(snapshot_delta(&files, &dirs, &files, &dirs).is_empty());
+}
+
+#[test]
+fn diff_snapshots() {
+	let self_files = vec![
+		create_file("added", 1, 1, ""),
+		create_file("changed", 1, 2, ""),
+		create_file("new/moved", 7, 1, "moved"),
+		create_file("rehashed", 1, 1, "rehashed"),
+	];
+	let other_files = vec![
+		create_file("changed", 1, 1, ""),
+		create_file("old/moved", 7, 1, "moved"),
+		create_file("rehashed", 1, 1, ""),
+		create_file("removed", 1, 1, ""),
+	];
+	assert_eq!(
+		snapshot_diff(&self_files, &other_files),
+		vec![
+			Diff::Added("added".to_string()),
+			Diff::Removed("removed".to_string()),
+			Diff::Changed("changed".to_string()),
+			Diff::Moved("new/moved".to_string(), "old/moved".to_string()),
+		]
+	);
+}
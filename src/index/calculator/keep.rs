@@ -0,0 +1,91 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use regex::Regex;
+
+use crate::index::model::normalized_path;
+use crate::index::model::Metadata;
+
+/// A rule which prefers one duplicate over another when choosing which one to keep.
+#[derive(Clone, Debug)]
+pub enum KeepRule {
+	/// Prefers the oldest modified time, written as `oldest`.
+	Oldest,
+	/// Prefers the newest modified time, written as `newest`.
+	Newest,
+	/// Prefers the shortest path, written as `shortest`.
+	Shortest,
+	/// Prefers the longest path, written as `longest`.
+	Longest,
+	/// Prefers paths inside the given directory, written as `under:<dir>`. Relative directories
+	/// are resolved against the current directory, as indexed paths are absolute.
+	Under(String),
+	/// Prefers paths matching the regular expression, written as `match:<regex>`.
+	Match(Regex),
+	/// Prefers paths not matching the regular expression, written as `not-match:<regex>`.
+	NotMatch(Regex),
+}
+
+impl FromStr for KeepRule {
+	type Err = String;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		let (name, argument) = match value.split_once(':') {
+			Some((name, argument)) => (name, Some(argument)),
+			None => (value, None),
+		};
+		let regex = |argument: &str| Regex::new(argument).map_err(|e| e.to_string());
+		match (name, argument) {
+			("oldest", None) => Ok(Self::Oldest),
+			("newest", None) => Ok(Self::Newest),
+			("shortest", None) => Ok(Self::Shortest),
+			("longest", None) => Ok(Self::Longest),
+			("under", Some(dir)) => {
+				let dir =
+					std::path::absolute(dir).map_err(|e| format!("Invalid dir `{dir}`: {e}"))?;
+				Ok(Self::Under(normalized_path(dir)))
+			}
+			("match", Some(argument)) => Ok(Self::Match(regex(argument)?)),
+			("not-match", Some(argument)) => Ok(Self::NotMatch(regex(argument)?)),
+			_ => {
+				Err(format!(
+					"Unknown rule `{value}`, expected one of oldest, newest, shortest, longest, \
+					 under:<dir>, match:<regex> or not-match:<regex>"
+				))
+			}
+		}
+	}
+}
+
+impl KeepRule {
+	// Orders the preferred entry first.
+	fn compare(&self, lhs: &Metadata, rhs: &Metadata) -> Ordering {
+		// Sorts `true` first.
+		let prefer = |lhs: bool, rhs: bool| rhs.cmp(&lhs);
+		match self {
+			Self::Oldest => lhs.modified_time.cmp(&rhs.modified_time),
+			Self::Newest => rhs.modified_time.cmp(&lhs.modified_time),
+			Self::Shortest => lhs.path.len().cmp(&rhs.path.len()),
+			Self::Longest => rhs.path.len().cmp(&lhs.path.len()),
+			Self::Under(dir) => {
+				let is_under = |meta: &Metadata| meta.is_child_of(dir);
+				prefer(is_under(lhs), is_under(rhs))
+			}
+			Self::Match(regex) => prefer(regex.is_match(&lhs.path), regex.is_match(&rhs.path)),
+			Self::NotMatch(regex) => prefer(!regex.is_match(&lhs.path), !regex.is_match(&rhs.path)),
+		}
+	}
+}
+
+/// Sorts a group of duplicates so the one to keep comes first, followed by the redundant ones.
+/// Rules are applied in order, with later rules breaking ties of earlier ones. Remaining ties keep
+/// their order in the group.
+pub fn sort_by_keep_rules<T>(group: &mut [T], rules: &[KeepRule], meta: impl Fn(&T) -> &Metadata) {
+	group.sort_by(|lhs, rhs| {
+		rules
+			.iter()
+			.map(|rule| rule.compare(meta(lhs), meta(rhs)))
+			.find(|ordering| ordering.is_ne())
+			.unwrap_or(Ordering::Equal)
+	});
+}
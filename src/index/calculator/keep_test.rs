@@ -0,0 +1,172 @@
+use super::sort_by_keep_rules;
+use super::test_fixtures::create_meta;
+use super::KeepRule;
+use crate::index::model::normalized_path;
+use crate::index::model::Metadata;
+
+fn sorted_paths(group: &[Metadata], rules: &[&str]) -> Vec<String> {
+	let rules: Vec<KeepRule> = rules.iter().map(|rule| rule.parse().unwrap()).collect();
+	let mut group: Vec<_> = group.iter().collect();
+	sort_by_keep_rules(&mut group, &rules, |meta| *meta);
+	group.iter().map(|meta| meta.path.clone()).collect()
+}
+
+#[test]
+fn keep_without_rules() {
+	let group = vec![
+		create_meta("b/a.txt", 2),
+		create_meta("a.txt", 1),
+	];
+	assert_eq!(
+		sorted_paths(&group, &[]),
+		[
+			"b/a.txt",
+			"a.txt"
+		]
+	);
+}
+
+#[test]
+fn keep_oldest_and_newest() {
+	let group = vec![
+		create_meta("a.txt", 2),
+		create_meta("b.txt", 1),
+		create_meta("c.txt", 3),
+	];
+	assert_eq!(
+		sorted_paths(&group, &["oldest"]),
+		[
+			"b.txt",
+			"a.txt",
+			"c.txt"
+		]
+	);
+	assert_eq!(
+		sorted_paths(&group, &["newest"]),
+		[
+			"c.txt",
+			"a.txt",
+			"b.txt"
+		]
+	);
+}
+
+#[test]
+fn keep_shortest_and_longest() {
+	let group = vec![
+		create_meta("a/b.txt", 1),
+		create_meta("a.txt", 1),
+		create_meta("a/b/c.txt", 1),
+	];
+	assert_eq!(
+		sorted_paths(&group, &["shortest"]),
+		[
+			"a.txt",
+			"a/b.txt",
+			"a/b/c.txt"
+		]
+	);
+	assert_eq!(
+		sorted_paths(&group, &["longest"]),
+		[
+			"a/b/c.txt",
+			"a/b.txt",
+			"a.txt"
+		]
+	);
+}
+
+#[test]
+fn keep_under_dir() {
+	let group = vec![
+		create_meta("/backup/a.txt", 1),
+		create_meta("/photos2/a.txt", 1),
+		create_meta("/photos/a.txt", 1),
+	];
+	assert_eq!(
+		sorted_paths(&group, &["under:/photos/"]),
+		[
+			"/photos/a.txt",
+			"/backup/a.txt",
+			"/photos2/a.txt",
+		]
+	);
+}
+
+#[test]
+fn keep_under_relative_dir() {
+	let current_dir = normalized_path(std::env::current_dir().unwrap());
+	let group = vec![
+		create_meta(&format!("{current_dir}/backup/a.txt"), 1),
+		create_meta(&format!("{current_dir}/photos/a.txt"), 1),
+	];
+	assert_eq!(sorted_paths(&group, &["under:photos"])[0], format!("{current_dir}/photos/a.txt"));
+	assert!("under:".parse::<KeepRule>().is_err());
+}
+
+#[test]
+fn keep_match_and_not_match() {
+	let group = vec![
+		create_meta("a (copy).txt", 1),
+		create_meta("a.txt", 1),
+	];
+	assert_eq!(
+		sorted_paths(&group, &["match:^a\\.txt$"]),
+		[
+			"a.txt",
+			"a (copy).txt"
+		]
+	);
+	assert_eq!(
+		sorted_paths(&group, &["not-match:\\(copy\\)"]),
+		[
+			"a.txt",
+			"a (copy).txt"
+		]
+	);
+}
+
+#[test]
+fn keep_rules_break_ties_in_order() {
+	let group = vec![
+		create_meta("old/a.txt", 1),
+		create_meta("new/longer.txt", 2),
+		create_meta("new/a.txt", 2),
+	];
+	assert_eq!(
+		sorted_paths(
+			&group,
+			&[
+				"newest",
+				"shortest"
+			]
+		),
+		[
+			"new/a.txt",
+			"new/longer.txt",
+			"old/a.txt",
+		]
+	);
+	assert_eq!(
+		sorted_paths(
+			&group,
+			&[
+				"shortest",
+				"newest"
+			]
+		),
+		[
+			"new/a.txt",
+			"old/a.txt",
+			"new/longer.txt",
+		]
+	);
+}
+
+#[test]
+fn keep_rule_parse_errors() {
+	assert!("largest".parse::<KeepRule>().is_err());
+	assert!("under".parse::<KeepRule>().is_err());
+	assert!("oldest:a".parse::<KeepRule>().is_err());
+	assert!("match:(".parse::<KeepRule>().is_err());
+}
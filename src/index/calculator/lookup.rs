@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use super::duplicate_files::potential_matches;
+use super::Allowlist;
+use crate::index::model::Checksum;
+use crate::index::model::File;
+
+/// Whether a file is already in an index.
+pub enum Lookup<'a> {
+	/// Files in the index with the same contents.
+	Present(Vec<&'a File>),
+	/// Files in the index with the same name but other contents.
+	Different(Vec<&'a File>),
+	/// No file in the index has the same contents or name.
+	New,
+}
+
+/// Returns the positions of the allowed files and of the index files which need checksums to be
+/// looked up, which are the ones whose size exists on the other side.
+pub fn potential_lookup_matches(
+	files: &[File],
+	index_files: &[File],
+	allowlist: &Allowlist,
+) -> (Vec<usize>, Vec<usize>) {
+	let allowed: Vec<_> = files
+		.iter()
+		.enumerate()
+		.filter(|(_, file)| allowlist.is_allowed(&file.meta.path))
+		.map(|(file_index, _)| file_index)
+		.collect();
+	let candidates: Vec<_> =
+		allowed.iter().map(|file_index| &files[*file_index]).chain(index_files).collect();
+	let (mut file_matches, mut index_matches): (Vec<_>, Vec<_>) =
+		potential_matches(&candidates, &Allowlist::allow_all(), false, false, false)
+			.into_iter()
+			.partition(|candidate| *candidate < allowed.len());
+
+	// Sizes shared only by files on the same side need no checksums.
+	let size = |candidate: &usize| candidates[*candidate].size;
+	let file_sizes: HashSet<_> = file_matches.iter().map(size).collect();
+	let index_sizes: HashSet<_> = index_matches.iter().map(size).collect();
+	file_matches.retain(|candidate| index_sizes.contains(&size(candidate)));
+	index_matches.retain(|candidate| file_sizes.contains(&size(candidate)));
+
+	let mut file_matches: Vec<_> =
+		file_matches.into_iter().map(|candidate| allowed[candidate]).collect();
+	let mut index_matches: Vec<_> =
+		index_matches.into_iter().map(|candidate| candidate - allowed.len()).collect();
+	file_matches.sort_unstable();
+	index_matches.sort_unstable();
+	(file_matches, index_matches)
+}
+
+/// Looks up each allowed file in the index files, first by contents then by name. Files must have
+/// checksums calculated to be found by contents.
+pub fn lookup<'a>(
+	files: &'a [File],
+	index_files: &'a [File],
+	allowlist: &Allowlist,
+) -> Vec<(&'a File, Lookup<'a>)> {
+	let mut files_by_checksum = HashMap::<(&Checksum, u64), Vec<&File>>::new();
+	let mut files_by_name = HashMap::<&str, Vec<&File>>::new();
+	for file in index_files {
+		if !file.checksum.is_empty() {
+			files_by_checksum.entry((&file.checksum, file.size)).or_default().push(file);
+		}
+		files_by_name.entry(file.meta.name()).or_default().push(file);
+	}
+
+	files
+		.iter()
+		.filter(|file| allowlist.is_allowed(&file.meta.path))
+		.map(|file| {
+			let present = if file.checksum.is_empty() {
+				None
+			} else {
+				files_by_checksum.get(&(&file.checksum, file.size))
+			};
+			let lookup = if let Some(present) = present {
+				Lookup::Present(present.clone())
+			} else if let Some(different) = files_by_name.get(file.meta.name()) {
+				Lookup::Different(different.clone())
+			} else {
+				Lookup::New
+			};
+			(file, lookup)
+		})
+		.collect()
+}
@@ -0,0 +1,63 @@
+use super::lookup;
+use super::potential_lookup_matches;
+use super::test_fixtures::create_file;
+use super::Lookup;
+use crate::index::calculator::Allowlist;
+
+#[test]
+fn potential_matches_by_size() {
+	let files = vec![
+		create_file("card/a", 1, 0, ""),
+		create_file("card/b", 2, 0, ""),
+		create_file("card/c", 2, 0, ""),
+	];
+	let index_files = vec![
+		create_file("archive/a", 1, 0, ""),
+		create_file("archive/d", 3, 0, ""),
+	];
+	let (file_matches, index_matches) =
+		potential_lookup_matches(&files, &index_files, &Allowlist::allow_all());
+	assert_eq!(file_matches, vec![0]);
+	assert_eq!(index_matches, vec![0]);
+}
+
+#[test]
+fn lookup_by_contents_then_name() {
+	let files = vec![
+		create_file("card/a", 1, 0, "a"),
+		create_file("card/b", 2, 0, "b"),
+		create_file("card/c", 3, 0, ""),
+	];
+	let index_files = vec![
+		create_file("archive/x/a", 1, 0, "a"),
+		create_file("archive/y/a", 1, 0, "a"),
+		create_file("archive/b", 2, 0, "other"),
+	];
+	let results: Vec<_> = lookup(&files, &index_files, &Allowlist::allow_all())
+		.into_iter()
+		.map(|(file, lookup)| {
+			let (kind, paths) = match lookup {
+				Lookup::Present(matches) => ("present", matches),
+				Lookup::Different(matches) => ("different", matches),
+				Lookup::New => ("new", Vec::new()),
+			};
+			let paths: Vec<_> = paths.iter().map(|file| file.meta.path()).collect();
+			(file.meta.path(), kind, paths)
+		})
+		.collect();
+	assert_eq!(
+		results,
+		vec![
+			(
+				"card/a",
+				"present",
+				vec![
+					"archive/x/a",
+					"archive/y/a"
+				]
+			),
+			("card/b", "different", vec!["archive/b"]),
+			("card/c", "new", vec![]),
+		]
+	);
+}
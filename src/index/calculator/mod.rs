@@ -1,13 +1,57 @@
 mod allowlist;
+mod breakdown;
+#[cfg(test)]
+mod breakdown_test;
+mod compare;
+#[cfg(test)]
+mod compare_test;
+mod contained;
+#[cfg(test)]
+mod contained_test;
 mod diff;
+mod diff_copies;
+mod diff_dirs;
 #[cfg(test)]
 mod diff_test;
 mod duplicate_dirs;
+#[cfg(test)]
+mod duplicate_dirs_test;
 mod duplicate_files;
 #[cfg(test)]
 mod duplicate_files_test;
+mod find;
+#[cfg(test)]
+mod find_test;
+mod history;
+#[cfg(test)]
+mod history_test;
+mod keep;
+#[cfg(test)]
+mod keep_test;
+mod lookup;
+#[cfg(test)]
+mod lookup_test;
+mod similar_dirs;
+#[cfg(test)]
+mod similar_dirs_test;
+#[cfg(test)]
+mod test_fixtures;
+mod tree;
+#[cfg(test)]
+mod tree_test;
 
 pub use allowlist::*;
+pub use breakdown::*;
+pub use compare::*;
+pub use contained::*;
 pub use diff::*;
+pub use diff_copies::*;
+pub use diff_dirs::*;
 pub use duplicate_dirs::*;
 pub use duplicate_files::*;
+pub use find::*;
+pub use history::*;
+pub use keep::*;
+pub use lookup::*;
+pub use similar_dirs::*;
+pub use tree::*;
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use super::contained::ancestor_dirs;
+use super::Allowlist;
+use crate::index::model::parent_str;
+use crate::index::model::Checksum;
+use crate::index::model::Dir;
+use crate::index::model::File;
+use crate::index::store::SortedSliceIndexOpts;
+use crate::index::SubIndex;
+
+/// A pair of dirs which share most of their contents.
+pub struct SimilarDirs<'a> {
+	pub dir: &'a Dir,
+	pub other: &'a Dir,
+	/// Size of the distinct contents found in both dirs.
+	pub shared_size: u128,
+	/// Size of the distinct contents found in either dir.
+	pub total_size: u128,
+	/// Files in the dir whose contents are not in the other dir.
+	pub only_in_dir: Vec<&'a File>,
+	/// Files in the other dir whose contents are not in the dir.
+	pub only_in_other: Vec<&'a File>,
+}
+
+// Returns the checksums of the files in the dir, and the size of its distinct contents. Files
+// without a checksum have no duplicate, so each one counts as distinct.
+fn dir_contents<'a>(sub_index: &SubIndex<'a>) -> (HashSet<&'a Checksum>, u128) {
+	let mut checksums = HashSet::new();
+	let mut size = 0;
+	for file in sub_index.files {
+		if file.checksum.is_empty() || checksums.insert(&file.checksum) {
+			size += u128::from(file.size);
+		}
+	}
+	(checksums, size)
+}
+
+// Returns the files whose contents are not in the given checksums.
+fn missing_files<'a>(sub_index: &SubIndex<'a>, checksums: &HashSet<&Checksum>) -> Vec<&'a File> {
+	sub_index
+		.files
+		.iter()
+		.filter(|file| file.checksum.is_empty() || !checksums.contains(&file.checksum))
+		.collect()
+}
+
+/// Finds pairs of dirs whose shared contents make up at least the given percentage of their
+/// combined contents, weighted by size. A pair is skipped if one dir is inside the other, or if it
+/// is inside another similar pair. Files must have checksums calculated to be matched.
+pub fn similar_dirs<'a>(
+	index: &SubIndex<'a>,
+	allowlist: &Allowlist,
+	threshold: u8,
+) -> Vec<SimilarDirs<'a>> {
+	let dirs = index.dirs;
+	let mut contents = HashMap::new();
+	let mut ancestors_by_checksum = HashMap::<&Checksum, (u64, BTreeSet<usize>)>::new();
+	for file in index.files {
+		if file.size == 0 || file.checksum.is_empty() {
+			continue;
+		}
+		let (_, ancestors) =
+			ancestors_by_checksum.entry(&file.checksum).or_insert((file.size, BTreeSet::new()));
+		for dir_index in ancestor_dirs(index, file.meta.path()) {
+			if !allowlist.is_allowed(&dirs[dir_index].meta.path) {
+				continue;
+			}
+			ancestors.insert(dir_index);
+		}
+	}
+
+	// Sums the shared size of every pair of dirs with a common file, which is usually far fewer
+	// than every pair of dirs.
+	let mut shared_by_pair = BTreeMap::<(usize, usize), u128>::new();
+	for (size, ancestors) in ancestors_by_checksum.values() {
+		let ancestors: Vec<_> = ancestors.iter().copied().collect();
+		for (position, dir_index) in ancestors.iter().enumerate() {
+			let dir = &dirs[*dir_index].meta;
+			for other_index in &ancestors[(position + 1)..] {
+				if dirs[*other_index].meta.is_child_of(dir.path()) {
+					continue;
+				}
+				*shared_by_pair.entry((*dir_index, *other_index)).or_default() += u128::from(*size);
+			}
+		}
+	}
+
+	let mut similar_pairs = HashSet::new();
+	let mut similar = Vec::new();
+	for ((dir_index, other_index), shared_size) in shared_by_pair {
+		for index_of in [
+			dir_index,
+			other_index,
+		] {
+			contents.entry(index_of).or_insert_with(|| dir_contents(&index.sub_index(index_of)));
+		}
+		let (dir_checksums, dir_size) = &contents[&dir_index];
+		let (other_checksums, other_size) = &contents[&other_index];
+		let total_size = dir_size + other_size - shared_size;
+		if shared_size * 100 < u128::from(threshold) * total_size {
+			continue;
+		}
+		similar_pairs.insert((dir_index, other_index));
+
+		// Pairs inside a similar pair are mostly the same as it, so only the outermost is kept.
+		let dir_parent =
+			parent_str(dirs[dir_index].meta.path()).and_then(|parent| index.dir_index(parent));
+		let other_parent =
+			parent_str(dirs[other_index].meta.path()).and_then(|parent| index.dir_index(parent));
+		let outer_pairs = [
+			(dir_parent, Some(other_index)),
+			(Some(dir_index), other_parent),
+			(dir_parent, other_parent),
+		];
+		let is_nested = outer_pairs.into_iter().any(|pair| {
+			let (Some(lhs), Some(rhs)) = pair else {
+				return false;
+			};
+			similar_pairs.contains(&(lhs.min(rhs), lhs.max(rhs)))
+		});
+		if is_nested {
+			continue;
+		}
+		similar.push(SimilarDirs {
+			dir: &dirs[dir_index],
+			other: &dirs[other_index],
+			shared_size,
+			total_size,
+			only_in_dir: missing_files(&index.sub_index(dir_index), other_checksums),
+			only_in_other: missing_files(&index.sub_index(other_index), dir_checksums),
+		});
+	}
+	similar
+}
@@ -0,0 +1,79 @@
+use super::similar_dirs;
+use super::test_fixtures::create_dir;
+use super::test_fixtures::create_file;
+use crate::index::calculator::Allowlist;
+use crate::index::model::Dir;
+use crate::index::model::File;
+use crate::index::SubIndex;
+
+// Similar dir paths along with their sizes and differing files.
+type SimilarPaths = (String, String, u128, u128, Vec<String>, Vec<String>);
+
+fn similar(files: &[File], dirs: &[Dir], threshold: u8) -> Vec<SimilarPaths> {
+	let index = SubIndex {
+		files,
+		dirs,
+	};
+	let paths = |files: &[&File]| files.iter().map(|file| file.meta.path().to_string()).collect();
+	similar_dirs(&index, &Allowlist::allow_all(), threshold)
+		.into_iter()
+		.map(|similar| {
+			(
+				similar.dir.meta.path().to_string(),
+				similar.other.meta.path().to_string(),
+				similar.shared_size,
+				similar.total_size,
+				paths(&similar.only_in_dir),
+				paths(&similar.only_in_other),
+			)
+		})
+		.collect()
+}
+
+#[test]
+fn similar_weighted_by_size() {
+	let files = vec![
+		create_file("a/big", 90, 0, "big"),
+		create_file("a/small", 5, 0, "small"),
+		create_file("b/big", 90, 0, "big"),
+		create_file("b/other", 5, 0, ""),
+	];
+	let dirs = vec![
+		create_dir("a"),
+		create_dir("b"),
+	];
+	assert_eq!(
+		similar(&files, &dirs, 90),
+		vec![
+			(
+				"a".to_string(),
+				"b".to_string(),
+				90,
+				100,
+				vec!["a/small".to_string()],
+				vec!["b/other".to_string()],
+			)
+		]
+	);
+	assert!(similar(&files, &dirs, 91).is_empty());
+}
+
+#[test]
+fn similar_skips_nested_pairs() {
+	let files = vec![
+		create_file("a/x/1", 10, 0, "1"),
+		create_file("a/x/2", 10, 0, "2"),
+		create_file("b/x/1", 10, 0, "1"),
+		create_file("b/x/2", 10, 0, "2"),
+	];
+	let dirs = vec![
+		create_dir("a"),
+		create_dir("a/x"),
+		create_dir("b"),
+		create_dir("b/x"),
+	];
+	let similar = similar(&files, &dirs, 50);
+	let pairs: Vec<_> =
+		similar.iter().map(|(dir, other, ..)| (dir.as_str(), other.as_str())).collect();
+	assert_eq!(pairs, vec![("a", "b")]);
+}
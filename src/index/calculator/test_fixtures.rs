@@ -0,0 +1,37 @@
+use std::time::Duration;
+use std::time::SystemTime;
+
+use crate::index::model::Checksum;
+use crate::index::model::Dir;
+use crate::index::model::File;
+use crate::index::model::Metadata;
+
+// Creates metadata modified the given number of seconds after the Unix epoch.
+pub fn create_meta(path: &str, modified: u64) -> Metadata {
+	Metadata {
+		path: path.to_string(),
+		created_time: SystemTime::UNIX_EPOCH,
+		modified_time: SystemTime::UNIX_EPOCH + Duration::from_secs(modified),
+		hidden: false,
+	}
+}
+
+// Creates a file with the given checksum, which is left uncalculated if empty.
+pub fn create_file(path: &str, size: u64, modified: u64, checksum: &str) -> File {
+	File {
+		meta: create_meta(path, modified),
+		size,
+		checksum: Checksum {
+			sha512: checksum.to_string(),
+		},
+		linked: None,
+	}
+}
+
+// Creates a dir without a checksum.
+pub fn create_dir(path: &str) -> Dir {
+	Dir {
+		meta: create_meta(path, 0),
+		checksum: Checksum::new(),
+	}
+}
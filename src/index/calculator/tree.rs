@@ -0,0 +1,92 @@
+use std::cmp::Reverse;
+
+use clap::ValueEnum;
+
+use crate::index::model::parent_str;
+use crate::index::model::Dir;
+use crate::index::model::File;
+use crate::index::store::SortedSliceIndexOpts;
+use crate::index::Index;
+use crate::index::SubIndex;
+
+/// Order of sibling dirs in a tree.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeSort {
+	/// Largest dirs first.
+	Size,
+	/// Sorted by name.
+	Name,
+}
+
+/// Recursive totals of a dir.
+pub struct DirUsage<'a> {
+	pub dir: &'a Dir,
+	/// Position of the parent dir in the usages, or `None` for the top dirs of the index.
+	pub parent: Option<usize>,
+	/// Depth below the top dirs of the index, which are at 0.
+	pub depth: usize,
+	pub size: u128,
+	pub file_count: usize,
+	pub dir_count: usize,
+}
+
+/// Returns the recursive totals of every dir, in the same order as the dirs of the index.
+pub fn dir_usages<'a>(index: &SubIndex<'a>) -> Vec<DirUsage<'a>> {
+	let mut usages: Vec<DirUsage<'a>> = Vec::with_capacity(index.dirs.len());
+	for (dir_index, dir) in index.dirs.iter().enumerate() {
+		let sub_index = index.sub_index(dir_index);
+		// Parents are sorted before their children, so they already have a usage.
+		let parent = parent_str(dir.meta.path()).and_then(|parent| index.dir_index(parent));
+		usages.push(DirUsage {
+			dir,
+			parent,
+			depth: parent.map_or(0, |parent| usages[parent].depth + 1),
+			size: sub_index.file_size(),
+			file_count: sub_index.file_count(),
+			dir_count: sub_index.dir_count(),
+		});
+	}
+	usages
+}
+
+/// Returns the positions of the usages in depth-first order, with siblings sorted, skipping the
+/// dirs deeper than the maximum depth.
+pub fn tree_order(usages: &[DirUsage<'_>], sort: TreeSort, max_depth: Option<usize>) -> Vec<usize> {
+	let mut top = Vec::new();
+	let mut children = vec![Vec::new(); usages.len()];
+	for (usage_index, usage) in usages.iter().enumerate() {
+		if max_depth.is_some_and(|max_depth| usage.depth > max_depth) {
+			continue;
+		}
+		match usage.parent {
+			Some(parent) => children[parent].push(usage_index),
+			None => top.push(usage_index),
+		}
+	}
+	let sort_siblings = |siblings: &mut Vec<usize>| {
+		match sort {
+			TreeSort::Size => siblings.sort_by_key(|sibling| Reverse(usages[*sibling].size)),
+			TreeSort::Name => siblings.sort_by_key(|sibling| usages[*sibling].dir.meta.name()),
+		}
+	};
+
+	let mut order = Vec::new();
+	sort_siblings(&mut top);
+	// Siblings are pushed in reverse so the first one is visited first.
+	let mut stack: Vec<_> = top.into_iter().rev().collect();
+	while let Some(usage_index) = stack.pop() {
+		order.push(usage_index);
+		let mut siblings = std::mem::take(&mut children[usage_index]);
+		sort_siblings(&mut siblings);
+		stack.extend(siblings.into_iter().rev());
+	}
+	order
+}
+
+/// Returns the largest files, from the largest one, with ties sorted by path.
+pub fn largest_files<'a>(index: &SubIndex<'a>, count: usize) -> Vec<&'a File> {
+	let mut files: Vec<_> = index.files.iter().collect();
+	files.sort_by_key(|file| Reverse(file.size));
+	files.truncate(count);
+	files
+}
@@ -0,0 +1,107 @@
+use super::dir_usages;
+use super::largest_files;
+use super::test_fixtures::create_dir;
+use super::test_fixtures::create_file;
+use super::tree_order;
+use super::TreeSort;
+use crate::index::model::Dir;
+use crate::index::model::File;
+use crate::index::SubIndex;
+
+fn test_files() -> Vec<File> {
+	vec![
+		create_file("r/a-b/x", 1, 0, ""),
+		create_file("r/a/b/y", 10, 0, ""),
+		create_file("r/a/z", 5, 0, ""),
+		create_file("r/c/w", 20, 0, ""),
+	]
+}
+
+fn test_dirs() -> Vec<Dir> {
+	vec![
+		create_dir("r"),
+		create_dir("r/a"),
+		create_dir("r/a-b"),
+		create_dir("r/a/b"),
+		create_dir("r/c"),
+	]
+}
+
+#[test]
+fn usages_are_recursive() {
+	let (files, dirs) = (test_files(), test_dirs());
+	let index = SubIndex {
+		files: &files,
+		dirs: &dirs,
+	};
+	let usages: Vec<_> = dir_usages(&index)
+		.iter()
+		.map(|usage| {
+			(usage.dir.meta.path(), usage.depth, usage.size, usage.file_count, usage.dir_count)
+		})
+		.collect();
+	assert_eq!(
+		usages,
+		vec![
+			("r", 0, 36, 4, 4),
+			("r/a", 1, 15, 2, 1),
+			("r/a-b", 1, 1, 1, 0),
+			("r/a/b", 2, 10, 1, 0),
+			("r/c", 1, 20, 1, 0),
+		]
+	);
+}
+
+#[test]
+fn tree_is_depth_first() {
+	let (files, dirs) = (test_files(), test_dirs());
+	let index = SubIndex {
+		files: &files,
+		dirs: &dirs,
+	};
+	let usages = dir_usages(&index);
+	let paths = |sort, max_depth| {
+		tree_order(&usages, sort, max_depth)
+			.into_iter()
+			.map(|usage_index| usages[usage_index].dir.meta.path())
+			.collect::<Vec<_>>()
+	};
+	assert_eq!(
+		paths(TreeSort::Size, None),
+		vec![
+			"r",
+			"r/c",
+			"r/a",
+			"r/a/b",
+			"r/a-b"
+		]
+	);
+	assert_eq!(
+		paths(TreeSort::Name, None),
+		vec![
+			"r",
+			"r/a",
+			"r/a/b",
+			"r/a-b",
+			"r/c"
+		]
+	);
+	assert_eq!(
+		paths(TreeSort::Name, Some(1)),
+		vec![
+			"r",
+			"r/a",
+			"r/a-b",
+			"r/c"
+		]
+	);
+
+	let largest: Vec<_> = largest_files(&index, 2).iter().map(|file| file.meta.path()).collect();
+	assert_eq!(
+		largest,
+		vec![
+			"r/c/w",
+			"r/a/b/y"
+		]
+	);
+}
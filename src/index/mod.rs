@@ -6,11 +6,43 @@ mod store;
 // Size of buffer to compare files, optimized for an 8 KiB average file-size.
 // Dinneen, Jesse & Nguyen, Ba. (2021). How Big Are Peoples' Computer Files? File Size Distributions
 // Among User-managed Collections.
-const BUF_SIZE: usize = 1024 * 8;
+pub(crate) const BUF_SIZE: usize = 1024 * 8;
 
+#[cfg(test)]
+pub use builder::PathIndexBuilder;
+pub use calculator::age_buckets;
+pub use calculator::content_type_buckets;
+pub use calculator::dir_growth;
+pub use calculator::dir_usages;
+pub use calculator::extension_buckets;
+pub use calculator::largest_files;
+pub use calculator::new_extensions;
+pub use calculator::outermost_duplicate_dirs;
+pub use calculator::potential_file_matches_across;
+pub use calculator::potential_lookup_matches;
+pub use calculator::size_buckets;
+pub use calculator::snapshot_diff;
+pub use calculator::sniff_content_type;
+pub use calculator::sort_by_keep_rules;
+pub use calculator::total_growth;
+pub use calculator::tree_order;
+pub use calculator::wasted_size;
 pub use calculator::Allowlist;
+pub use calculator::Bucket;
 pub use calculator::Diff;
+pub use calculator::DirGrowth;
+pub use calculator::DuplicateOptions;
+pub use calculator::DuplicateSort;
+pub use calculator::EntryKind;
+pub use calculator::FindQuery;
+pub use calculator::FoundEntry;
+pub use calculator::KeepRule;
+pub use calculator::Lookup;
+pub use calculator::TreeSort;
+pub use calculator::SNIFF_LEN;
 pub use store::ChecksumCalculator;
+pub use store::History;
 pub use store::Index;
+pub use store::IndexUpdate;
 pub use store::RootIndex;
 pub use store::SubIndex;
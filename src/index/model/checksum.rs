@@ -8,6 +8,8 @@ use serde::Serialize;
 use sha2::Digest;
 use sha2::Sha512;
 
+use super::resolve_lossy_path;
+
 pub trait FileReader {
 	fn read(&self, path: impl AsRef<Path>, buf: &mut Vec<u8>) -> io::Result<()>;
 }
@@ -16,7 +18,16 @@ pub struct NativeFileReader;
 
 impl FileReader for NativeFileReader {
 	fn read(&self, path: impl AsRef<Path>, buf: &mut Vec<u8>) -> io::Result<()> {
-		let mut file = File::open(path)?;
+		let path = path.as_ref();
+		let mut file = match File::open(path) {
+			Err(e) if e.kind() == io::ErrorKind::NotFound => {
+				match path.to_str().and_then(resolve_lossy_path) {
+					Some(resolved) if resolved != path => File::open(resolved)?,
+					_ => return Err(e),
+				}
+			}
+			file => file?,
+		};
 		file.read_to_end(buf)?;
 		Ok(())
 	}
@@ -34,6 +45,14 @@ fn sha512_checksum(
 	Ok(format!("{:x}", hasher.finalize()))
 }
 
+// Adds the kind and name of a child to a dir checksum. Names are prefixed with their length so they
+// cannot run into the next field.
+fn update_child(hasher: &mut Sha512, kind: &[u8], name: &str) {
+	hasher.update(kind);
+	hasher.update((name.len() as u64).to_le_bytes());
+	hasher.update(name);
+}
+
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Hash, Clone, Default, PartialOrd, Ord)]
 pub struct Checksum {
 	pub sha512: String,
@@ -59,4 +78,32 @@ impl Checksum {
 		self.sha512 = sha512_checksum(reader, path, buf)?;
 		Ok(())
 	}
+
+	/// Calculates the checksum of a dir from the name, size and checksum of each file directly
+	/// inside it, and the name and checksum of each subdir, in order. Dirs with the same checksum
+	/// have the same layout and contents. Returns an empty checksum if any child has none.
+	pub fn from_children<'a>(
+		files: impl IntoIterator<Item = (&'a str, u64, &'a Checksum)>,
+		dirs: impl IntoIterator<Item = (&'a str, &'a Checksum)>,
+	) -> Self {
+		let mut hasher = Sha512::new();
+		for (name, size, checksum) in files {
+			if checksum.is_empty() {
+				return Self::new();
+			}
+			update_child(&mut hasher, b"f", name);
+			hasher.update(size.to_le_bytes());
+			hasher.update(&checksum.sha512);
+		}
+		for (name, checksum) in dirs {
+			if checksum.is_empty() {
+				return Self::new();
+			}
+			update_child(&mut hasher, b"d", name);
+			hasher.update(&checksum.sha512);
+		}
+		Self {
+			sha512: format!("{:x}", hasher.finalize()),
+		}
+	}
 }
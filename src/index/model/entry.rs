@@ -13,6 +13,12 @@ pub struct File {
 	pub meta: Metadata,
 	pub size: u64,
 	pub checksum: Checksum,
+	/// Path of the file whose contents this file shares through a link, after deduplicating.
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none"
+	)]
+	pub linked: Option<String>,
 }
 
 impl File {
@@ -22,6 +28,7 @@ impl File {
 			meta: Metadata::from_metadata(path.as_ref(), &metadata),
 			size: metadata.len(),
 			checksum: Checksum::new(),
+			linked: None,
 		})
 	}
 }
@@ -29,12 +36,22 @@ impl File {
 #[derive(Serialize, Deserialize, PartialEq, PartialOrd, Eq, Ord, Hash, Clone)]
 pub struct Dir {
 	pub meta: Metadata,
+	/// Checksum of the names and contents of everything inside the dir, calculated from the
+	/// checksums of its children. Empty until every file inside has a checksum, and cleared when
+	/// anything inside changes. Only strict duplicate dir detection uses it, since moved
+	/// dirs are matched even when some of their files changed.
+	#[serde(
+		default,
+		skip_serializing_if = "Checksum::is_empty"
+	)]
+	pub checksum: Checksum,
 }
 
 impl Dir {
 	pub fn from_path(path: impl AsRef<Path>) -> io::Result<Self> {
 		Ok(Self {
 			meta: Metadata::from_path(path)?,
+			checksum: Checksum::new(),
 		})
 	}
 }
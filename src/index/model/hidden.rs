@@ -10,6 +10,6 @@ pub fn is_hidden_windows_metadata(metadata: &Metadata) -> bool {
 }
 
 #[cfg(not(target_os = "windows"))]
-pub fn is_hidden_windows_metadata(metadata: &Metadata) -> bool {
+pub fn is_hidden_windows_metadata(_metadata: &Metadata) -> bool {
 	false
 }
@@ -1,6 +1,7 @@
 use std::fs::{self};
 use std::io::{self};
 use std::path::Path;
+use std::path::PathBuf;
 use std::time::SystemTime;
 
 use serde::Deserialize;
@@ -22,6 +23,39 @@ pub fn normalized_path(path: impl AsRef<Path>) -> String {
 	normalized_path_str(&path.as_ref().to_string_lossy())
 }
 
+/// Returns the path an indexed path was read from. Paths are stored lossily, so names which are not
+/// valid Unicode are looked up in their parent dir. Returns `None` if a name is missing or
+/// ambiguous.
+pub fn resolve_lossy_path(path: &str) -> Option<PathBuf> {
+	if !path.contains(char::REPLACEMENT_CHARACTER) {
+		return Some(PathBuf::from(path));
+	}
+	let mut resolved = PathBuf::new();
+	for component in Path::new(path).components() {
+		let name = component.as_os_str().to_string_lossy();
+		if !name.contains(char::REPLACEMENT_CHARACTER) {
+			resolved.push(component);
+			continue;
+		}
+		let parent = if resolved.as_os_str().is_empty() {
+			Path::new(".")
+		} else {
+			resolved.as_path()
+		};
+		let mut matches = fs::read_dir(parent)
+			.ok()?
+			.filter_map(Result::ok)
+			.map(|entry| entry.file_name())
+			.filter(|file_name| file_name.to_string_lossy() == name);
+		let file_name = matches.next()?;
+		if matches.next().is_some() {
+			return None;
+		}
+		resolved.push(file_name);
+	}
+	Some(resolved)
+}
+
 pub fn parent_str(path: &str) -> Option<&str> {
 	if let Some(index) = path.rfind('/') {
 		return Some(&path[..index]);
@@ -29,6 +63,13 @@ pub fn parent_str(path: &str) -> Option<&str> {
 	None
 }
 
+pub fn name_str(path: &str) -> &str {
+	if let Some(index) = path.rfind('/') {
+		return &path[(index + 1)..];
+	}
+	path
+}
+
 #[derive(Serialize, Deserialize, Eq, PartialEq, Hash, Clone, PartialOrd, Ord)]
 pub struct Metadata {
 	pub path: String,
@@ -54,10 +95,7 @@ impl Metadata {
 	}
 
 	pub fn is_child_of(&self, dir: &str) -> bool {
-		if self.path.len() < dir.len() {
-			return false;
-		}
-		self.path.starts_with(dir) && self.path.as_bytes()[dir.len()] == b'/'
+		self.path.starts_with(dir) && self.path.as_bytes().get(dir.len()) == Some(&b'/')
 	}
 
 	pub fn path(&self) -> &str {
@@ -65,10 +103,7 @@ impl Metadata {
 	}
 
 	pub fn name(&self) -> &str {
-		if let Some(index) = self.path.rfind('/') {
-			return &self.path[(index + 1)..];
-		}
-		&self.path
+		name_str(&self.path)
 	}
 
 	pub fn parent(&self) -> Option<&str> {
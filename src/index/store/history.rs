@@ -0,0 +1,38 @@
+use std::time::SystemTime;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::index::calculator::SnapshotDelta;
+
+/// A past state of an index, stored as the changes which restore it from the state after it.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+	/// When this state was first indexed.
+	pub time: SystemTime,
+	pub delta: SnapshotDelta,
+}
+
+/// Past snapshots of an index, oldest first. Snapshots are numbered from 0, and the current state
+/// of the index comes last.
+#[derive(Serialize, Deserialize)]
+pub struct History {
+	/// When the current state was first indexed.
+	pub time: SystemTime,
+	pub snapshots: Vec<Snapshot>,
+}
+
+impl History {
+	/// Returns the number of snapshots, including the current state.
+	pub fn len(&self) -> usize {
+		self.snapshots.len() + 1
+	}
+
+	/// Returns when the given snapshot was first indexed.
+	pub fn time(&self, snapshot: usize) -> Option<SystemTime> {
+		if snapshot == self.snapshots.len() {
+			return Some(self.time);
+		}
+		self.snapshots.get(snapshot).map(|snapshot| snapshot.time)
+	}
+}
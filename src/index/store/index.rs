@@ -74,8 +74,13 @@ where
 
 	fn dir_children_indices(&self, dir_index: usize) -> (usize, usize) {
 		let dir = &self.dirs()[dir_index];
-		// Don't include itself in the sub-index.
-		let dir_start = dir_index + 1;
+		// Siblings such as `a-b` sort between `a` and `a/b`, so children are found from the first
+		// path starting with `a/`. This also leaves out the dir itself.
+		let mut path_normal = dir.meta.path().to_owned();
+		path_normal.push('/');
+		let after = &self.dirs()[dir_index + 1..];
+		let dir_start =
+			dir_index + 1 + after.partition_point(|entry| entry.meta.path() < path_normal.as_str());
 		let mut dir_end = dir_start;
 		for entry in &self.dirs()[dir_start..] {
 			if !entry.meta.is_child_of(dir.meta.path()) {
@@ -1,13 +1,21 @@
+mod history;
 mod index;
 mod root_index;
 #[cfg(test)]
 mod root_index_remove_test;
+#[cfg(test)]
+mod root_index_test;
 mod sub_index;
 mod sub_index_mut;
 #[cfg(test)]
 mod sub_index_test;
+mod update;
+#[cfg(test)]
+mod update_test;
 
+pub use history::*;
 pub use index::*;
 pub use root_index::*;
 pub use sub_index::*;
 pub use sub_index_mut::*;
+pub use update::*;
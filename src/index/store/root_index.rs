@@ -5,16 +5,35 @@ use std::collections::VecDeque;
 use std::fs::{self};
 use std::io::{self};
 use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
 
 use serde::Deserialize;
 use serde::Serialize;
 
+use super::history::History;
+use super::history::Snapshot;
 use super::sub_index::SubIndex;
+use crate::index::calculator::contained_dirs;
 use crate::index::calculator::diff;
 use crate::index::calculator::duplicate_dirs;
 use crate::index::calculator::duplicates;
+use crate::index::calculator::duplicates_across;
+use crate::index::calculator::find;
+use crate::index::calculator::lookup;
+use crate::index::calculator::similar_dirs;
+use crate::index::calculator::snapshot_delta;
+use crate::index::calculator::ContainedDir;
 use crate::index::calculator::Diff;
+use crate::index::calculator::DiffSide;
+use crate::index::calculator::DuplicateOptions;
+use crate::index::calculator::FindQuery;
+use crate::index::calculator::FoundEntry;
+use crate::index::calculator::Lookup;
+use crate::index::calculator::SimilarDirs;
 use crate::index::model::normalized_path;
+use crate::index::model::parent_str;
+use crate::index::model::Checksum;
 use crate::index::model::Dir;
 use crate::index::model::File;
 use crate::index::model::Metadata;
@@ -26,6 +45,11 @@ use crate::index::store::SubIndexMut;
 use crate::index::Allowlist;
 use crate::index::BUF_SIZE;
 
+// Returns the path relative to the given dir, which is empty for the root of a Unix filesystem.
+fn relative_path(path: &str, dir: &str) -> String {
+	path.strip_prefix(dir).unwrap_or(path).trim_start_matches('/').to_string()
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct RootIndex {
 	// TODO: Make this private.
@@ -33,9 +57,21 @@ pub struct RootIndex {
 	// TODO: Make this private.
 	pub dirs: Vec<Dir>,
 
+	// Past snapshots of the index, kept only once history is enabled.
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none"
+	)]
+	history: Option<History>,
+
 	#[serde(skip_serializing)]
 	#[serde(skip_deserializing)]
 	pub(super) dirty: bool,
+
+	// Directory that entry paths are relative to, which is empty for absolute paths.
+	#[serde(skip_serializing)]
+	#[serde(skip_deserializing)]
+	root: PathBuf,
 }
 
 impl RootIndex {
@@ -43,10 +79,19 @@ impl RootIndex {
 		Self {
 			files: Vec::new(),
 			dirs: Vec::new(),
+			history: None,
 			dirty: false,
+			root: PathBuf::new(),
 		}
 	}
 
+	// Creates an empty index whose entry paths are relative to the given directory.
+	pub fn with_root(root: impl AsRef<Path>) -> Self {
+		let mut index = Self::new();
+		index.root = PathBuf::from(normalized_path(root));
+		index
+	}
+
 	pub fn with_files(files: Vec<File>) -> Self {
 		let mut dir_path_set = HashSet::new();
 		for file in &files {
@@ -64,6 +109,7 @@ impl RootIndex {
 						modified_time: std::time::SystemTime::UNIX_EPOCH,
 						hidden: false,
 					},
+					checksum: Checksum::new(),
 				}
 			})
 			.collect();
@@ -99,6 +145,7 @@ impl RootIndex {
 		path: impl AsRef<std::path::Path>,
 		mut notifier: impl FnMut(&str),
 	) -> io::Result<()> {
+		let normalized = normalized_path(path.as_ref());
 		if path.as_ref().is_dir() {
 			self.dirty = true;
 			let removed = self.remove_dir(path.as_ref());
@@ -130,6 +177,7 @@ impl RootIndex {
 			return Err(io::Error::from(io::ErrorKind::Unsupported));
 		}
 		self.normalize();
+		self.invalidate_dirs(&normalized);
 		Ok(())
 	}
 
@@ -166,7 +214,7 @@ impl RootIndex {
 				} else {
 					let file = File::from_path(path)?;
 					if file.meta.hidden {
-						println!("Skipping hidden file: {}", file.meta.path());
+						eprintln!("Skipping hidden file: {}", file.meta.path());
 						continue;
 					}
 					let entry = self.add_file(file);
@@ -207,10 +255,116 @@ impl RootIndex {
 		None
 	}
 
+	// Removes the file or directory in the given path, along with its contents.
+	pub fn remove(&mut self, path: impl AsRef<std::path::Path>) {
+		if self.remove_file(path.as_ref()).is_some() || self.remove_dir(path.as_ref()).is_some() {
+			self.invalidate_dirs(&normalized_path(path));
+			self.dirty = true;
+		}
+	}
+
+	// Clears the checksums of the dirs containing the given path, since their contents changed.
+	pub(super) fn invalidate_dirs(&mut self, path: &str) {
+		let mut current = parent_str(path);
+		while let Some(dir) = current {
+			if let Some(dir_index) = self.dir_index(dir) {
+				self.dirs[dir_index].checksum = Checksum::new();
+			}
+			current = parent_str(dir);
+		}
+	}
+
+	// Calculates the checksum of every dir whose files all have checksums, from the innermost
+	// dirs outward. Dirs which still have a checksum are unchanged since it was calculated.
+	pub fn calculate_dir_checksums(&mut self) {
+		let mut files_by_parent = HashMap::<&str, Vec<&File>>::new();
+		for file in &self.files {
+			if let Some(parent) = parent_str(file.meta.path()) {
+				files_by_parent.entry(parent).or_default().push(file);
+			}
+		}
+		let mut dirs_by_parent = HashMap::<&str, Vec<usize>>::new();
+		for (dir_index, dir) in self.dirs.iter().enumerate() {
+			if let Some(parent) = parent_str(dir.meta.path()) {
+				dirs_by_parent.entry(parent).or_default().push(dir_index);
+			}
+		}
+
+		// Subdirs are sorted after their parent, so they are calculated first in reverse.
+		let mut checksums: Vec<_> = self.dirs.iter().map(|dir| dir.checksum.clone()).collect();
+		for dir_index in (0..self.dirs.len()).rev() {
+			if !checksums[dir_index].is_empty() {
+				continue;
+			}
+			let path = self.dirs[dir_index].meta.path();
+			let files = files_by_parent
+				.get(path)
+				.into_iter()
+				.flatten()
+				.map(|file| (file.meta.name(), file.size, &file.checksum));
+			let dirs = dirs_by_parent
+				.get(path)
+				.into_iter()
+				.flatten()
+				.map(|child| (self.dirs[*child].meta.name(), &checksums[*child]));
+			checksums[dir_index] = Checksum::from_children(files, dirs);
+		}
+
+		for (dir, checksum) in self.dirs.iter_mut().zip(checksums) {
+			if dir.checksum != checksum {
+				dir.checksum = checksum;
+				self.dirty = true;
+			}
+		}
+	}
+
+	// Records that the file in the given path shares its contents with another file through a
+	// link. Returns whether the file was found.
+	pub fn set_linked(
+		&mut self,
+		path: impl AsRef<std::path::Path>,
+		linked: Option<String>,
+	) -> bool {
+		let Some(index) = self.file_index(&normalized_path(path)) else {
+			return false;
+		};
+		self.files[index].linked = linked;
+		self.dirty = true;
+		true
+	}
+
+	// Returns the outermost indexed directory which contains the given path.
+	pub fn top_dir<'a>(&self, path: &'a str) -> Option<&'a str> {
+		let mut top_dir = None;
+		let mut current = parent_str(path);
+		while let Some(dir) = current {
+			if self.dir_index(dir).is_some() {
+				top_dir = Some(dir);
+			} else if top_dir.is_some() {
+				break;
+			}
+			current = parent_str(dir);
+		}
+		top_dir
+	}
+
+	// Returns the file in the given path.
+	pub fn file(&self, path: impl AsRef<std::path::Path>) -> Option<&File> {
+		let index = self.file_index(&normalized_path(path))?;
+		Some(&self.files[index])
+	}
+
 	pub fn calculate_all(&mut self) -> io::Result<()> {
 		let mut buf = Vec::with_capacity(BUF_SIZE);
 		for metadata in &mut self.files {
-			metadata.checksum.calculate(&NativeFileReader, metadata.meta.path(), &mut buf)?;
+			metadata.checksum.calculate(
+				&NativeFileReader,
+				self.root.join(metadata.meta.path()),
+				&mut buf,
+			)?;
+		}
+		for dir in &mut self.dirs {
+			dir.checksum = Checksum::new();
 		}
 		self.dirty = true;
 		Ok(())
@@ -257,8 +411,32 @@ impl RootIndex {
 		}
 	}
 
-	pub fn duplicates(&self, allowlist: &Allowlist) -> Vec<Vec<&File>> {
-		duplicates(&self.files, allowlist)
+	pub fn duplicates(&self, allowlist: &Allowlist, options: &DuplicateOptions) -> Vec<Vec<&File>> {
+		duplicates(&self.files, allowlist, options)
+	}
+
+	/// Finds duplicate files across several indexes. Each file comes with the position of its
+	/// index.
+	pub fn duplicates_across<'a>(
+		indexes: &'a [RootIndex],
+		allowlist: &Allowlist,
+		options: &DuplicateOptions,
+	) -> Vec<Vec<(usize, &'a File)>> {
+		let file_lists: Vec<_> = indexes.iter().map(|index| index.files.as_slice()).collect();
+		duplicates_across(&file_lists, allowlist, options)
+	}
+
+	/// Looks up the allowed files of another index in this one.
+	pub fn lookup<'a>(
+		&'a self,
+		other: &'a RootIndex,
+		allowlist: &Allowlist,
+	) -> Vec<(&'a File, Lookup<'a>)> {
+		lookup(&other.files, &self.files, allowlist)
+	}
+
+	pub fn find(&self, query: &FindQuery) -> Vec<FoundEntry<'_>> {
+		find(&self.all(), query)
 	}
 
 	pub fn all(&self) -> SubIndex<'_> {
@@ -279,8 +457,55 @@ impl RootIndex {
 		})
 	}
 
-	pub fn duplicate_dirs(&self, allowlist: &Allowlist) -> Vec<Vec<&Dir>> {
-		duplicate_dirs(&self.all(), allowlist)
+	// Returns the entries inside of the given directory, with paths relative to it.
+	pub fn relative(&self, dir: impl AsRef<Path>) -> Option<RootIndex> {
+		let p = normalized_path(dir);
+		// The filesystem root normalizes to an empty path, which is the dir holding everything.
+		let sub_index = if p.is_empty() {
+			SubIndex {
+				files: &self.files,
+				dirs: &self.dirs[self.dirs.partition_point(|dir| dir.meta.path().is_empty())..],
+			}
+		} else {
+			self.sub_index(&p)?
+		};
+		let mut index = Self::with_root(&p);
+		index.files = sub_index
+			.files
+			.iter()
+			.map(|file| {
+				let mut file = file.clone();
+				file.meta.path = relative_path(file.meta.path(), &p);
+				file
+			})
+			.collect();
+		index.dirs = sub_index
+			.dirs
+			.iter()
+			.map(|dir| {
+				let mut dir = dir.clone();
+				dir.meta.path = relative_path(dir.meta.path(), &p);
+				dir
+			})
+			.collect();
+		Some(index)
+	}
+
+	// Returns the directory that entry paths are relative to, which is empty for absolute paths.
+	pub fn root(&self) -> &Path {
+		&self.root
+	}
+
+	pub fn duplicate_dirs(&self, allowlist: &Allowlist, strict: bool) -> Vec<Vec<&Dir>> {
+		duplicate_dirs(&self.all(), allowlist, strict)
+	}
+
+	pub fn contained_dirs(&self, allowlist: &Allowlist) -> Vec<ContainedDir<'_>> {
+		contained_dirs(&self.all(), allowlist)
+	}
+
+	pub fn similar_dirs(&self, allowlist: &Allowlist, threshold: u8) -> Vec<SimilarDirs<'_>> {
+		similar_dirs(&self.all(), allowlist, threshold)
 	}
 
 	pub fn diff(
@@ -292,10 +517,8 @@ impl RootIndex {
 		match_modified: bool,
 	) -> io::Result<Vec<Diff>> {
 		diff(
-			&mut self.files,
-			&mut self.dirty,
-			&mut other.files,
-			&mut other.dirty,
+			DiffSide::with_root(&mut self.files, &mut self.dirty, &self.root),
+			DiffSide::with_root(&mut other.files, &mut other.dirty, &other.root),
 			notifier,
 			match_name,
 			match_created,
@@ -303,18 +526,68 @@ impl RootIndex {
 		)
 	}
 
+	/// Starts keeping past snapshots, where the current state was first indexed at the given time.
+	pub fn enable_history(&mut self, time: SystemTime) {
+		if self.history.is_none() {
+			self.history = Some(History {
+				time,
+				snapshots: Vec::new(),
+			});
+			self.dirty = true;
+		}
+	}
+
+	pub fn history(&self) -> Option<&History> {
+		self.history.as_ref()
+	}
+
+	/// Records the given entries, from before the index was updated, as the snapshot before the
+	/// current state, which was indexed at the given time. Nothing is recorded without history or
+	/// without any change.
+	pub fn record_snapshot(&mut self, files: &[File], dirs: &[Dir], time: SystemTime) {
+		let Some(history) = &mut self.history else {
+			return;
+		};
+		let delta = snapshot_delta(files, dirs, &self.files, &self.dirs);
+		if delta.is_empty() {
+			return;
+		}
+		history.snapshots.push(Snapshot {
+			time: history.time,
+			delta,
+		});
+		history.time = time;
+		self.dirty = true;
+	}
+
+	/// Restores a snapshot from the history as a standalone index without history.
+	pub fn snapshot(&self, snapshot: usize) -> Option<RootIndex> {
+		let history = self.history.as_ref()?;
+		let deltas = history.snapshots.get(snapshot..)?;
+		let mut files = self.files.clone();
+		let mut dirs = self.dirs.clone();
+		for past in deltas.iter().rev() {
+			past.delta.apply(&mut files, &mut dirs);
+		}
+		let mut index = Self::with_root(&self.root);
+		index.files = files;
+		index.dirs = dirs;
+		debug_assert!(index.validate());
+		Some(index)
+	}
+
 	pub fn dirty(&self) -> bool {
 		self.dirty
 	}
 
-	fn validate(&self) -> bool {
+	pub(super) fn validate(&self) -> bool {
 		for i in 1..self.files.len() {
 			let file_before = &self.files[i - 1];
 			let file_after = &self.files[i];
 			let path_before = file_before.meta.path();
 			let path_after = file_after.meta.path();
 			if path_before.cmp(path_after) != Ordering::Less {
-				println!("File order is wrong: {path_before} vs {path_after}");
+				eprintln!("File order is wrong: {path_before} vs {path_after}");
 				return false;
 			}
 		}
@@ -324,7 +597,7 @@ impl RootIndex {
 			let path_before = dir_before.meta.path();
 			let path_after = dir_after.meta.path();
 			if path_before.cmp(path_after) != Ordering::Less {
-				println!("File order is wrong: {path_before} vs {path_after}");
+				eprintln!("File order is wrong: {path_before} vs {path_after}");
 				return false;
 			}
 		}
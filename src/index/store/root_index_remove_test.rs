@@ -11,7 +11,7 @@ fn new_test_index(file_slice: &[&'static str]) -> RootIndex {
 
 #[test]
 fn test_remove_file_empty() {
-	let mut index = new_test_index(&vec![]);
+	let mut index = new_test_index(&[]);
 	index.remove_file("a.txt");
 	index.remove_file("foo/a.txt");
 	assert_eq!(index.files.len(), 0);
@@ -20,7 +20,7 @@ fn test_remove_file_empty() {
 
 #[test]
 fn test_remove_file_single_relative() {
-	let mut index = new_test_index(&vec!["a.txt"]);
+	let mut index = new_test_index(&["a.txt"]);
 	index.remove_file("a.txt");
 	assert_eq!(index.files.len(), 0);
 	assert_eq!(index.dirs.len(), 1);
@@ -28,7 +28,7 @@ fn test_remove_file_single_relative() {
 
 #[test]
 fn test_remove_file_single_absolute() {
-	let mut index = new_test_index(&vec!["/a.txt"]);
+	let mut index = new_test_index(&["/a.txt"]);
 	index.remove_file("/a.txt");
 	assert_eq!(index.files.len(), 0);
 	assert_eq!(index.dirs.len(), 1);
@@ -36,7 +36,7 @@ fn test_remove_file_single_absolute() {
 
 #[test]
 fn test_remove_file_single_nested() {
-	let mut index = new_test_index(&vec!["foo/a.txt"]);
+	let mut index = new_test_index(&["foo/a.txt"]);
 	index.remove_file("foo/a.txt");
 	assert_eq!(index.files.len(), 0);
 	assert_eq!(index.dirs.len(), 1);
@@ -44,7 +44,7 @@ fn test_remove_file_single_nested() {
 
 #[test]
 fn test_remove_file_multiple() {
-	let mut index = new_test_index(&vec![
+	let mut index = new_test_index(&[
 		"a.txt",
 		"b.txt",
 		"c.txt",
@@ -58,7 +58,7 @@ fn test_remove_file_multiple() {
 
 #[test]
 fn test_remove_file_multiple_nested() {
-	let mut index = new_test_index(&vec![
+	let mut index = new_test_index(&[
 		"foo/a.txt",
 		"foo/b.txt",
 		"bar/c.txt",
@@ -72,7 +72,7 @@ fn test_remove_file_multiple_nested() {
 
 #[test]
 fn test_remove_file_nonexistent() {
-	let mut index = new_test_index(&vec![
+	let mut index = new_test_index(&[
 		"a.txt",
 		"foo/b.txt",
 	]);
@@ -86,7 +86,7 @@ fn test_remove_file_nonexistent() {
 
 #[test]
 fn test_remove_file_first() {
-	let mut index = new_test_index(&vec![
+	let mut index = new_test_index(&[
 		"a.txt",
 		"b.txt",
 		"c.txt",
@@ -100,7 +100,7 @@ fn test_remove_file_first() {
 
 #[test]
 fn test_remove_file_last() {
-	let mut index = new_test_index(&vec![
+	let mut index = new_test_index(&[
 		"a.txt",
 		"b.txt",
 		"c.txt",
@@ -114,7 +114,7 @@ fn test_remove_file_last() {
 
 #[test]
 fn test_remove_dir_empty() {
-	let mut index = new_test_index(&vec![]);
+	let mut index = new_test_index(&[]);
 	index.remove_dir("foo");
 	assert_eq!(index.files.len(), 0);
 	assert_eq!(index.dirs.len(), 0);
@@ -122,7 +122,7 @@ fn test_remove_dir_empty() {
 
 #[test]
 fn test_remove_dir_single() {
-	let mut index = new_test_index(&vec!["foo/a.txt"]);
+	let mut index = new_test_index(&["foo/a.txt"]);
 	index.remove_dir("foo");
 	assert_eq!(index.files.len(), 0);
 	assert_eq!(index.dirs.len(), 0);
@@ -130,7 +130,7 @@ fn test_remove_dir_single() {
 
 #[test]
 fn test_remove_dir_multiple() {
-	let mut index = new_test_index(&vec![
+	let mut index = new_test_index(&[
 		"foo/a.txt",
 		"foo/b.txt",
 		"bar/c.txt",
@@ -143,7 +143,7 @@ fn test_remove_dir_multiple() {
 
 #[test]
 fn test_remove_dir_nonexistent() {
-	let mut index = new_test_index(&vec![
+	let mut index = new_test_index(&[
 		"foo/a.txt",
 		"bar/b.txt",
 	]);
@@ -154,7 +154,7 @@ fn test_remove_dir_nonexistent() {
 
 #[test]
 fn test_remove_dir_root() {
-	let mut index = new_test_index(&vec![
+	let mut index = new_test_index(&[
 		"a.txt",
 		"b.txt",
 		"foo/c.txt",
@@ -166,7 +166,7 @@ fn test_remove_dir_root() {
 
 #[test]
 fn test_remove_dir_nested() {
-	let mut index = new_test_index(&vec![
+	let mut index = new_test_index(&[
 		"foo/bar/a.txt",
 		"foo/b.txt",
 		"c.txt",
@@ -180,7 +180,7 @@ fn test_remove_dir_nested() {
 
 #[test]
 fn test_remove_dir_nested_children() {
-	let mut index = new_test_index(&vec![
+	let mut index = new_test_index(&[
 		"foo/bar/baz/a.txt",
 		"foo/bar/b.txt",
 		"c.txt",
@@ -0,0 +1,211 @@
+use std::time::Duration;
+use std::time::SystemTime;
+
+use super::IndexUpdate;
+use super::RootIndex;
+use crate::index::builder::PathIndexBuilder;
+use crate::index::model::Checksum;
+
+fn new_test_index(file_slice: &[&'static str]) -> RootIndex {
+	let mut builder = PathIndexBuilder::new();
+	for file in file_slice {
+		builder.add_file(file);
+	}
+	builder.build()
+}
+
+fn file_paths(index: &RootIndex) -> Vec<&str> {
+	index.files.iter().map(|file| file.meta.path()).collect()
+}
+
+#[test]
+fn test_rename_file() {
+	let mut index = new_test_index(&[
+		"foo/a.txt",
+		"foo/b.txt",
+	]);
+	index.update([IndexUpdate::Rename("foo/b.txt".into(), "foo/0.txt".into())]).unwrap();
+	assert!(index.dirty());
+	assert_eq!(
+		file_paths(&index),
+		[
+			"foo/0.txt",
+			"foo/a.txt"
+		]
+	);
+}
+
+#[test]
+fn test_rename_dir() {
+	let mut index = new_test_index(&[
+		"foo/a.txt",
+		"foo/bar/b.txt",
+		"foobar/c.txt",
+	]);
+	index.update([IndexUpdate::Rename("foo".into(), "qux".into())]).unwrap();
+	assert_eq!(
+		file_paths(&index),
+		[
+			"foobar/c.txt",
+			"qux/a.txt",
+			"qux/bar/b.txt"
+		]
+	);
+	assert!(index.dirs.iter().any(|dir| dir.meta.path() == "qux/bar"));
+	assert!(!index.dirs.iter().any(|dir| dir.meta.path() == "foo"));
+}
+
+#[test]
+fn test_remove_dir_with_contents() {
+	let mut index = new_test_index(&[
+		"foo/a.txt",
+		"foobar/c.txt",
+	]);
+	index.remove("foo");
+	assert!(index.dirty());
+	assert_eq!(file_paths(&index), ["foobar/c.txt"]);
+}
+
+#[test]
+fn test_top_dir() {
+	let index = new_test_index(&[
+		"/root/foo/a.txt",
+		"/root/foo/bar/b.txt",
+	]);
+	assert_eq!(index.top_dir("/root/foo/bar/b.txt"), Some("/root"));
+	assert_eq!(index.top_dir("/other/c.txt"), None);
+}
+
+// Returns an index whose files have their names as checksums.
+fn new_checksummed_index(file_slice: &[&'static str]) -> RootIndex {
+	let mut index = new_test_index(file_slice);
+	for file in &mut index.files {
+		file.checksum = Checksum {
+			sha512: file.meta.name().to_string(),
+		};
+	}
+	index.calculate_dir_checksums();
+	index
+}
+
+fn dir_checksum<'a>(index: &'a RootIndex, path: &str) -> &'a Checksum {
+	&index.dirs.iter().find(|dir| dir.meta.path() == path).unwrap().checksum
+}
+
+#[test]
+fn test_dir_checksums_match_layout() {
+	let index = new_checksummed_index(&[
+		"a/x.txt",
+		"a/sub/y.txt",
+		"b/x.txt",
+		"b/sub/y.txt",
+		"c/x.txt",
+		"c/other/y.txt",
+	]);
+	assert!(!dir_checksum(&index, "a").is_empty());
+	assert_eq!(dir_checksum(&index, "a"), dir_checksum(&index, "b"));
+	assert_eq!(dir_checksum(&index, "a/sub"), dir_checksum(&index, "c/other"));
+	assert_ne!(dir_checksum(&index, "a"), dir_checksum(&index, "c"));
+}
+
+#[test]
+fn test_dir_checksums_need_file_checksums() {
+	let mut index = new_test_index(&[
+		"a/x.txt",
+		"a/sub/y.txt",
+	]);
+	// Only `a/x.txt` gets a checksum, since files are sorted with `a/sub/y.txt` first.
+	index.files[1].checksum = Checksum {
+		sha512: "x".to_string(),
+	};
+	index.calculate_dir_checksums();
+	assert!(dir_checksum(&index, "a/sub").is_empty());
+	assert!(dir_checksum(&index, "a").is_empty());
+}
+
+#[test]
+fn test_dir_checksums_invalidated() {
+	let mut index = new_checksummed_index(&[
+		"a/x.txt",
+		"a/sub/y.txt",
+		"b/z.txt",
+	]);
+	index.update([IndexUpdate::Remove("a/sub/y.txt".into())]).unwrap();
+	assert!(dir_checksum(&index, "a/sub").is_empty());
+	assert!(dir_checksum(&index, "a").is_empty());
+	assert!(!dir_checksum(&index, "b").is_empty());
+
+	index.calculate_dir_checksums();
+	let sub = dir_checksum(&index, "a/sub").clone();
+	index.update([IndexUpdate::Rename("a/sub".into(), "b/sub".into())]).unwrap();
+	assert!(dir_checksum(&index, "a").is_empty());
+	assert!(dir_checksum(&index, "b").is_empty());
+	assert_eq!(dir_checksum(&index, "b/sub"), &sub);
+}
+
+#[test]
+fn test_history_snapshots() {
+	let time = |seconds| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+	let mut index = new_test_index(&[
+		"foo/a.txt",
+		"foo/b.txt",
+	]);
+	let unchanged = (index.files.clone(), index.dirs.clone());
+	index.record_snapshot(&unchanged.0, &unchanged.1, time(1));
+	assert!(index.history().is_none());
+
+	index.enable_history(time(1));
+	let first = (index.files.clone(), index.dirs.clone());
+	index.update([IndexUpdate::Rename("foo/b.txt".into(), "bar/b.txt".into())]).unwrap();
+	index.record_snapshot(&first.0, &first.1, time(2));
+	let second = (index.files.clone(), index.dirs.clone());
+	index.remove("foo/a.txt");
+	index.record_snapshot(&second.0, &second.1, time(3));
+	let third = (index.files.clone(), index.dirs.clone());
+	index.record_snapshot(&third.0, &third.1, time(4));
+
+	let history = index.history().unwrap();
+	assert_eq!(history.len(), 3);
+	assert_eq!(history.time(0), Some(time(1)));
+	assert_eq!(history.time(2), Some(time(3)));
+	assert_eq!(
+		file_paths(&index.snapshot(0).unwrap()),
+		[
+			"foo/a.txt",
+			"foo/b.txt"
+		]
+	);
+	assert_eq!(
+		file_paths(&index.snapshot(1).unwrap()),
+		[
+			"bar/b.txt",
+			"foo/a.txt"
+		]
+	);
+	assert_eq!(file_paths(&index.snapshot(2).unwrap()), ["bar/b.txt"]);
+	assert!(index.snapshot(3).is_none());
+}
+
+#[test]
+fn test_relative_to_filesystem_root() {
+	let index = new_test_index(&[
+		"/a.txt",
+		"/foo/b.txt",
+	]);
+	let relative = index.relative("/").unwrap();
+	assert_eq!(
+		file_paths(&relative),
+		[
+			"a.txt",
+			"foo/b.txt"
+		]
+	);
+	let dirs: Vec<_> = relative.dirs.iter().map(|dir| dir.meta.path()).collect();
+	assert_eq!(dirs, ["foo"]);
+	let relative = index.relative("/foo").unwrap();
+	assert_eq!(file_paths(&relative), ["b.txt"]);
+
+	let index = new_test_index(&["C:\\foo\\b.txt"]);
+	let relative = index.relative("C:\\").unwrap();
+	assert_eq!(file_paths(&relative), ["foo/b.txt"]);
+}
@@ -10,9 +10,9 @@ pub struct SubIndex<'a> {
 	pub(crate) dirs: &'a [Dir],
 }
 
-impl SubIndex<'_> {
+impl<'a> SubIndex<'a> {
 	// Returns the sub-index of the given directory index.
-	pub fn sub_index(&self, dir_index: usize) -> SubIndex<'_> {
+	pub fn sub_index(&self, dir_index: usize) -> SubIndex<'a> {
 		debug_assert!(dir_index < self.dirs.len());
 
 		let dir = &self.dirs[dir_index];
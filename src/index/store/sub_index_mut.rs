@@ -97,6 +97,18 @@ impl<'a> ChecksumCalculator<'a> {
 		}
 	}
 
+	/// Calculates the checksums of the files at the given positions in the index.
+	pub fn with_files(
+		index: &'a mut SubIndexMut<'a>,
+		file_indexes: impl IntoIterator<Item = usize>,
+	) -> Self {
+		Self {
+			queue: file_indexes.into_iter().collect(),
+			index,
+			buf: Vec::with_capacity(BUF_SIZE),
+		}
+	}
+
 	pub fn index_mut(&mut self) -> &mut SubIndexMut<'a> {
 		self.index
 	}
@@ -109,3 +109,19 @@ fn test_sub_index_file_same_name_as_dir() {
 	assert_eq!(sub_index.dir_count(), 1);
 	assert_eq!(sub_index.file_count(), 4);
 }
+
+#[test]
+fn test_sub_index_interleaved_sibling() {
+	let mut builder = PathIndexBuilder::new();
+	builder.add_dir("foo");
+	builder.add_dir("foo-bar");
+	builder.add_dir("foo/bar");
+	builder.add_file("foo-bar/f.txt");
+	builder.add_file("foo/bar/g.txt");
+	let store = builder.build();
+	let index = store.all();
+	let dir_index = index.dir_index("foo").unwrap();
+	let sub_index = index.sub_index(dir_index);
+	assert_eq!(sub_index.dir_count(), 1);
+	assert_eq!(sub_index.file_count(), 1);
+}
@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::ops::Bound;
+use std::path::PathBuf;
+
+use crate::index::model::normalized_path;
+use crate::index::model::Checksum;
+use crate::index::model::Dir;
+use crate::index::model::File;
+use crate::index::RootIndex;
+
+/// A change made to the filesystem, to be reflected in an index with `RootIndex::update`.
+pub enum IndexUpdate {
+	/// The file or empty dir at the path was created or replaced. The checksum of a new file is
+	/// set if known.
+	Add(PathBuf, Option<Checksum>),
+	/// The file or dir at the path was removed, along with its contents.
+	Remove(PathBuf),
+	/// The file or dir at the first path was renamed to the second, along with its contents.
+	Rename(PathBuf, PathBuf),
+}
+
+// Removes the entry at the path and the entries inside of it, returning them by path.
+fn take_entries<T>(entries: &mut BTreeMap<String, T>, path: &str) -> Vec<(String, T)> {
+	let mut keys = Vec::new();
+	if entries.contains_key(path) {
+		keys.push(path.to_string());
+	}
+	// Entries inside of `path` sort between `path/` and `path0`, since `0` follows `/`.
+	let start = format!("{path}/");
+	let end = format!("{path}0");
+	keys.extend(
+		entries
+			.range::<str, _>((Bound::Included(start.as_str()), Bound::Excluded(end.as_str())))
+			.map(|(key, _)| key.clone()),
+	);
+	keys.into_iter()
+		.map(|key| {
+			let entry = entries.remove(&key).unwrap();
+			(key, entry)
+		})
+		.collect()
+}
+
+// Returns the path after renaming `from` to `to`, for a path which is `from` or inside of it.
+fn renamed_path(path: &str, from: &str, to: &str) -> String {
+	format!("{to}{}", &path[from.len()..])
+}
+
+// Applies a single update to the entries, returning the paths whose parent dirs changed.
+fn apply_update(
+	files: &mut BTreeMap<String, File>,
+	dirs: &mut BTreeMap<String, Dir>,
+	update: IndexUpdate,
+) -> io::Result<Vec<String>> {
+	match update {
+		IndexUpdate::Add(path, checksum) => {
+			if path.is_dir() {
+				let dir = Dir::from_path(&path)?;
+				let key = dir.meta.path().to_string();
+				dirs.insert(key.clone(), dir);
+				return Ok(vec![key]);
+			}
+			let mut file = File::from_path(&path)?;
+			if let Some(checksum) = checksum {
+				file.checksum = checksum;
+			} else if let Some(previous) = files.get(file.meta.path()) {
+				if previous.meta == file.meta {
+					file.checksum = previous.checksum.clone();
+				}
+			}
+			let key = file.meta.path().to_string();
+			files.insert(key.clone(), file);
+			Ok(vec![key])
+		}
+		IndexUpdate::Remove(path) => {
+			let path = normalized_path(path);
+			take_entries(files, &path);
+			take_entries(dirs, &path);
+			Ok(vec![path])
+		}
+		IndexUpdate::Rename(from, to) => {
+			let from = normalized_path(from);
+			let to = normalized_path(to);
+			for (key, mut file) in take_entries(files, &from) {
+				file.meta.path = renamed_path(&key, &from, &to);
+				files.insert(file.meta.path.clone(), file);
+			}
+			for (key, mut dir) in take_entries(dirs, &from) {
+				dir.meta.path = renamed_path(&key, &from, &to);
+				dirs.insert(dir.meta.path.clone(), dir);
+			}
+			Ok(vec![
+				from,
+				to,
+			])
+		}
+	}
+}
+
+impl RootIndex {
+	/// Reflects the changes in the index in order. Entries are only sorted once, unlike calling
+	/// `add`, `remove` or `rename` for each change. If reading a path fails, the changes before it
+	/// are still reflected.
+	pub fn update(&mut self, updates: impl IntoIterator<Item = IndexUpdate>) -> io::Result<()> {
+		let mut files: BTreeMap<_, _> =
+			self.files.drain(..).map(|file| (file.meta.path.clone(), file)).collect();
+		let mut dirs: BTreeMap<_, _> =
+			self.dirs.drain(..).map(|dir| (dir.meta.path.clone(), dir)).collect();
+
+		let mut changed_paths = Vec::new();
+		let mut result = Ok(());
+		for update in updates {
+			match apply_update(&mut files, &mut dirs, update) {
+				Ok(paths) => changed_paths.extend(paths),
+				Err(e) => {
+					result = Err(e);
+					break;
+				}
+			}
+		}
+
+		self.files = files.into_values().collect();
+		self.dirs = dirs.into_values().collect();
+		for path in &changed_paths {
+			self.invalidate_dirs(path);
+		}
+		self.dirty = true;
+		debug_assert!(self.validate());
+		result
+	}
+}
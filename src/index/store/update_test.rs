@@ -0,0 +1,85 @@
+use std::fs;
+
+use super::IndexUpdate;
+use crate::index::builder::PathIndexBuilder;
+use crate::index::model::Checksum;
+use crate::index::RootIndex;
+
+fn new_test_index(file_slice: &[&'static str]) -> RootIndex {
+	let mut builder = PathIndexBuilder::new();
+	for file in file_slice {
+		builder.add_file(file);
+	}
+	builder.build()
+}
+
+fn file_paths(index: &RootIndex) -> Vec<&str> {
+	index.files.iter().map(|file| file.meta.path()).collect()
+}
+
+fn dir_paths(index: &RootIndex) -> Vec<&str> {
+	index.dirs.iter().map(|dir| dir.meta.path()).collect()
+}
+
+#[test]
+fn test_update_remove_and_rename() {
+	let mut index = new_test_index(&[
+		"r/a/x.txt",
+		"r/a/sub/y.txt",
+		"r/a-b/z.txt",
+		"r/c.txt",
+	]);
+	index
+		.update([
+			IndexUpdate::Rename("r/a".into(), "r/d".into()),
+			IndexUpdate::Remove("r/d/sub".into()),
+			IndexUpdate::Rename("r/c.txt".into(), "r/d/c.txt".into()),
+		])
+		.unwrap();
+	assert!(index.dirty());
+	assert_eq!(
+		file_paths(&index),
+		[
+			"r/a-b/z.txt",
+			"r/d/c.txt",
+			"r/d/x.txt"
+		]
+	);
+	assert_eq!(
+		dir_paths(&index),
+		[
+			"r",
+			"r/a-b",
+			"r/d"
+		]
+	);
+}
+
+#[test]
+fn test_update_add_from_filesystem() {
+	let root = tempfile::tempdir().unwrap();
+	let dir = root.path().join("new");
+	fs::create_dir(&dir).unwrap();
+	fs::write(dir.join("a.txt"), "a").unwrap();
+
+	let mut index = RootIndex::new();
+	let checksum = Checksum {
+		sha512: "known".to_string(),
+	};
+	index
+		.update([
+			IndexUpdate::Add(dir.clone(), None),
+			IndexUpdate::Add(dir.join("a.txt"), Some(checksum.clone())),
+		])
+		.unwrap();
+	assert_eq!(index.dirs.len(), 1);
+	assert_eq!(index.file(dir.join("a.txt")).unwrap().checksum, checksum);
+
+	// Changes before a failing one are still reflected.
+	let result = index.update([
+		IndexUpdate::Remove(dir.join("a.txt")),
+		IndexUpdate::Add(dir.join("missing.txt"), None),
+	]);
+	assert!(result.is_err());
+	assert!(index.files.is_empty());
+}
@@ -2,10 +2,14 @@
 
 mod command;
 mod index;
+mod sync;
 mod util;
 
 use std::env;
 use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+use std::time::SystemTime;
 
 use anyhow::Context;
 use anyhow::Result;
@@ -13,7 +17,20 @@ use clap::Args;
 use clap::Parser;
 use clap::Subcommand;
 use index::Allowlist;
+use index::DuplicateOptions;
+use index::DuplicateSort;
+use index::EntryKind;
+use index::FindQuery;
+use index::KeepRule;
+use index::TreeSort;
 use regex::Regex;
+use sync::DedupeMode;
+use util::display::parse_size;
+use util::glob::parse_glob;
+use util::output::OutputFormat;
+use util::script::ScriptFormat;
+use util::timer::parse_duration;
+use util::timer::parse_time;
 
 /// Utility to compare folder contents.
 #[derive(Parser, Debug)]
@@ -21,6 +38,15 @@ use regex::Regex;
 struct Cli {
 	#[command(subcommand)]
 	command: Command,
+
+	/// Format of the results. Progress is always written to stderr.
+	#[clap(
+		long,
+		global = true,
+		value_enum,
+		default_value_t
+	)]
+	output: OutputFormat,
 }
 
 /// Doc comment
@@ -30,12 +56,37 @@ enum Command {
 	Index(IndexSubcommand),
 	/// Show folder statistics.
 	Stats(StatsSubcommand),
-	/// Find differences in two folders.
+	/// Shows the recursive size and counts of each folder in an index, like `du`.
+	#[command(alias = "du")]
+	Tree(TreeSubcommand),
+	/// Lists, compares and restores the snapshots of an index file indexed with `--history`.
+	History(HistorySubcommand),
+	/// Find differences in two folders. Exits with code 1 if any differences are found.
 	Diff(DiffSubcommand),
 	/// Finds duplicates in a folder.
 	Duplicates(Duplicates),
 	/// Finds folders whose contents are duplicated elsewhere.
 	Redundant(Redundant),
+	/// Finds indexed entries matching all the given predicates, without reading the disk.
+	Find(FindSubcommand),
+	/// Checks which files of a path are already in an index, such as before importing them.
+	Lookup(LookupSubcommand),
+	/// Finds folders whose file contents all exist under another folder.
+	Contained(ContainedSubcommand),
+	/// Finds pairs of folders which share most of their contents, and what differs between them.
+	SimilarDirs(SimilarDirsSubcommand),
+	/// Plans the operations which make a destination folder match a source folder.
+	Plan(PlanSubcommand),
+	/// Applies a plan, journaling each operation so an interrupted run can be rolled back.
+	Apply(ApplySubcommand),
+	/// Makes a destination folder identical to a source folder, renaming moved entries.
+	Mirror(MirrorSubcommand),
+	/// Replaces duplicate files with links to a single copy, or deletes them.
+	Dedupe(DedupeSubcommand),
+	/// Moves quarantined duplicates back to their original paths.
+	Restore(RestoreSubcommand),
+	/// Deletes quarantined duplicates.
+	Purge(PurgeSubcommand),
 }
 
 #[derive(Args, Debug)]
@@ -50,6 +101,61 @@ struct IndexSubcommand {
 	/// Whether to calculate the SHA-512 of the source files.
 	#[clap(long)]
 	sha_512: bool,
+
+	/// Keeps the previous state of the index as a snapshot whenever it changes. Once enabled, the
+	/// index file keeps its history on every later run.
+	#[clap(long)]
+	history: bool,
+}
+
+#[derive(Args, Debug)]
+struct HistorySubcommand {
+	#[command(subcommand)]
+	command: HistoryCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum HistoryCommand {
+	/// Lists the snapshots, oldest first, with what changed since the previous one.
+	List(HistoryListSubcommand),
+	/// Shows the differences between two snapshots. Exits with code 1 if any are found.
+	Diff(HistoryDiffSubcommand),
+	/// Writes a snapshot as a standalone index file.
+	Restore(HistoryRestoreSubcommand),
+}
+
+#[derive(Args, Debug)]
+struct HistoryListSubcommand {
+	/// Path to the index file with a history.
+	#[clap(long)]
+	index_file: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct HistoryDiffSubcommand {
+	/// Path to the index file with a history.
+	#[clap(long)]
+	index_file: PathBuf,
+
+	/// Earlier snapshot to compare.
+	from: usize,
+
+	/// Later snapshot to compare, or the current state if not provided.
+	to: Option<usize>,
+}
+
+#[derive(Args, Debug)]
+struct HistoryRestoreSubcommand {
+	/// Path to the index file with a history.
+	#[clap(long)]
+	index_file: PathBuf,
+
+	/// Snapshot to restore.
+	snapshot: usize,
+
+	/// Path to write the restored index to.
+	#[clap(long)]
+	restore_file: PathBuf,
 }
 
 #[derive(Args, Debug)]
@@ -64,6 +170,70 @@ struct StatsSubcommand {
 	/// Source dir to find stats for, or all indexed directories if not provided.
 	#[clap(long)]
 	dir: Option<PathBuf>,
+
+	#[command(flatten)]
+	filter: Filter,
+
+	/// Also breaks files down by extension, size and age.
+	#[clap(long)]
+	breakdown: bool,
+
+	/// Also breaks files down by content type, read from the magic bytes at their start.
+	#[clap(
+		long,
+		requires = "breakdown"
+	)]
+	sniff: bool,
+
+	/// Path to a newer index file to compare against, showing the bytes and files added and
+	/// removed in each folder.
+	#[clap(
+		long,
+		value_name = "PATH",
+		requires = "index_file",
+		conflicts_with_all = ["src", "allow", "deny", "breakdown"]
+	)]
+	compare: Option<PathBuf>,
+
+	/// Number of growing and shrinking folders to show when comparing.
+	#[clap(
+		long,
+		value_name = "COUNT",
+		default_value_t = 10,
+		requires = "compare"
+	)]
+	top: usize,
+}
+
+#[derive(Args, Debug)]
+struct TreeSubcommand {
+	/// Path to the index file to show the folders of.
+	#[clap(long)]
+	index_file: PathBuf,
+
+	/// Source dir to show the folders inside of, or all indexed directories if not provided.
+	#[clap(long)]
+	dir: Option<PathBuf>,
+
+	/// Deepest level of folders to show, where the top folders are at 0.
+	#[clap(long)]
+	depth: Option<usize>,
+
+	/// Order of the folders inside each folder.
+	#[clap(
+		long,
+		value_enum,
+		default_value = "size"
+	)]
+	sort: TreeSort,
+
+	/// Shows this many of the largest folders and files instead of the tree.
+	#[clap(
+		long,
+		value_name = "COUNT",
+		conflicts_with = "depth"
+	)]
+	top: Option<usize>,
 }
 
 #[derive(Args, Debug)]
@@ -80,18 +250,211 @@ struct DiffSubcommand {
 }
 
 #[derive(Args, Debug)]
-struct Duplicates {
-	/// Path to the index file.
+struct FindSubcommand {
+	/// Path to the index file to search.
+	#[clap(long)]
+	index_file: PathBuf,
+
+	/// Only finds entries of this kind.
+	#[clap(
+		long = "type",
+		value_enum,
+		value_name = "TYPE"
+	)]
+	kind: Option<EntryKind>,
+
+	/// Glob matching the whole name, such as `IMG_*.jpg`. Multiple globs are considered an "OR".
+	#[clap(
+		long,
+		value_parser = parse_glob,
+		value_name = "GLOB"
+	)]
+	name: Vec<Regex>,
+
+	/// Regular expression matching the path. Multiple regular expressions are considered an "OR".
+	#[clap(
+		long,
+		value_name = "REGEX"
+	)]
+	path: Vec<Regex>,
+
+	/// Extension without the dot, matched without case. Multiple extensions are considered an
+	/// "OR".
+	#[clap(
+		long = "ext",
+		value_name = "EXT"
+	)]
+	extensions: Vec<String>,
+
+	/// Smallest size of the files to find, such as `10M`. Dirs have no size.
+	#[clap(
+		long,
+		value_parser = parse_size
+	)]
+	min_size: Option<u64>,
+
+	/// Largest size of the files to find, such as `1G`. Dirs have no size.
+	#[clap(
+		long,
+		value_parser = parse_size
+	)]
+	max_size: Option<u64>,
+
+	/// Finds entries modified at or after a UTC date such as `2024-05-31`, or a duration ago such
+	/// as `7d`.
+	#[clap(
+		long,
+		value_parser = parse_time,
+		value_name = "TIME"
+	)]
+	modified_after: Option<SystemTime>,
+
+	/// Finds entries modified at or before a UTC date or a duration ago.
+	#[clap(
+		long,
+		value_parser = parse_time,
+		value_name = "TIME"
+	)]
+	modified_before: Option<SystemTime>,
+
+	/// Finds entries created at or after a UTC date or a duration ago.
+	#[clap(
+		long,
+		value_parser = parse_time,
+		value_name = "TIME"
+	)]
+	created_after: Option<SystemTime>,
+
+	/// Finds entries created at or before a UTC date or a duration ago.
+	#[clap(
+		long,
+		value_parser = parse_time,
+		value_name = "TIME"
+	)]
+	created_before: Option<SystemTime>,
+
+	/// Start of the hexadecimal SHA-512 of the entries to find.
+	#[clap(
+		long,
+		value_name = "PREFIX"
+	)]
+	checksum: Option<String>,
+
+	/// Whether the entries to find have a checksum calculated.
+	#[clap(long)]
+	has_checksum: Option<bool>,
+
+	/// Whether the entries to find are hidden, by their attributes or a name starting with a dot.
+	#[clap(long)]
+	hidden: Option<bool>,
+}
+
+#[derive(Args, Debug)]
+struct LookupSubcommand {
+	/// Source path whose files are looked up.
+	src: PathBuf,
+
+	/// Path to the index file to look the files up in.
 	#[clap(long)]
 	index_file: PathBuf,
 
 	#[command(flatten)]
 	filter: Filter,
 
+	/// Exits with code 1 if any file is new, with neither its contents nor its name in the index.
+	#[clap(long)]
+	fail_on_new: bool,
+}
+
+#[derive(Args, Debug)]
+struct DuplicateDirs {
 	/// Finds duplicate dirs. If unset, finds duplicate files instead.
 	#[clap(long)]
 	dirs: bool,
 
+	/// Also lists duplicate dirs inside other duplicate dirs.
+	#[clap(
+		long,
+		requires = "dirs"
+	)]
+	all_levels: bool,
+
+	/// Only matches dirs whose files also have the same relative paths, and whose subdirs have
+	/// the same layout. If unset, dirs match when their files have the same contents.
+	#[clap(
+		long,
+		requires = "dirs"
+	)]
+	strict: bool,
+}
+
+#[derive(Args, Debug)]
+struct Duplicates {
+	/// Path to the index file. Repeat it to find files duplicated across several indexes, such as
+	/// one per backup drive.
+	#[clap(
+		long,
+		required = true
+	)]
+	index_file: Vec<PathBuf>,
+
+	/// Only lists duplicate files held by at least two of the indexes.
+	#[clap(
+		long,
+		conflicts_with = "dirs"
+	)]
+	across: bool,
+
+	#[command(flatten)]
+	filter: Filter,
+
+	#[command(flatten)]
+	dirs: DuplicateDirs,
+
+	/// Order of groups of duplicate files.
+	#[clap(
+		long,
+		value_enum,
+		default_value = "wasted",
+		conflicts_with = "dirs"
+	)]
+	sort: DuplicateSort,
+
+	/// Skips groups of files smaller than this size, such as `10M`.
+	#[clap(
+		long,
+		value_parser = parse_size,
+		default_value = "0",
+		conflicts_with = "dirs"
+	)]
+	min_size: u64,
+
+	/// Skips groups which would free less than this size by keeping a single copy.
+	#[clap(
+		long,
+		value_parser = parse_size,
+		default_value = "0",
+		conflicts_with = "dirs"
+	)]
+	min_wasted: u64,
+
+	#[command(flatten)]
+	keep: Keep,
+
+	/// Prints a script which deletes every duplicate file except the one to keep, instead of the
+	/// duplicates. Only available with a single index.
+	#[clap(
+		long,
+		value_enum,
+		value_name = "SHELL",
+		conflicts_with = "dirs"
+	)]
+	script: Option<ScriptFormat>,
+
+	/// Exits with code 1 if any duplicates are found.
+	#[clap(long)]
+	fail_on_found: bool,
+
 	#[command(flatten)]
 	matches: Matches,
 }
@@ -102,6 +465,191 @@ struct Redundant {
 	#[clap(long)]
 	index_file: PathBuf,
 
+	/// Prints a script which deletes the redundant folders, instead of the folders.
+	#[clap(
+		long,
+		value_enum,
+		value_name = "SHELL"
+	)]
+	script: Option<ScriptFormat>,
+
+	/// Exits with code 1 if any redundant folders are found.
+	#[clap(long)]
+	fail_on_found: bool,
+
+	#[command(flatten)]
+	filter: Filter,
+
+	#[command(flatten)]
+	matches: Matches,
+}
+
+#[derive(Args, Debug)]
+struct ContainedSubcommand {
+	/// Path to the index file.
+	#[clap(long)]
+	index_file: PathBuf,
+
+	/// Exits with code 1 if any contained folders are found.
+	#[clap(long)]
+	fail_on_found: bool,
+
+	#[command(flatten)]
+	filter: Filter,
+
+	#[command(flatten)]
+	matches: Matches,
+}
+
+#[derive(Args, Debug)]
+struct SimilarDirsSubcommand {
+	/// Path to the index file.
+	#[clap(long)]
+	index_file: PathBuf,
+
+	/// Minimum percentage of contents, weighted by size, that a pair of folders must share.
+	#[clap(long, default_value_t = 90, value_parser = clap::value_parser!(u8).range(0..=100))]
+	threshold: u8,
+
+	/// Exits with code 1 if any similar folders are found.
+	#[clap(long)]
+	fail_on_found: bool,
+
+	#[command(flatten)]
+	filter: Filter,
+
+	#[command(flatten)]
+	matches: Matches,
+}
+
+#[derive(Args, Debug)]
+struct PlanSubcommand {
+	/// Source path whose contents should be copied.
+	src: PathBuf,
+
+	/// Destination path to make identical to the source.
+	dst: PathBuf,
+
+	/// Path to store the plan.
+	#[clap(long)]
+	plan_file: PathBuf,
+
+	/// Path to an index file of the source. If not provided, the source is read instead.
+	#[clap(long)]
+	src_index_file: Option<PathBuf>,
+
+	/// Path to an index file of the destination. If not provided, the destination is read instead.
+	#[clap(long)]
+	dst_index_file: Option<PathBuf>,
+
+	#[command(flatten)]
+	matches: Matches,
+}
+
+#[derive(Args, Debug)]
+struct ApplySubcommand {
+	/// Path to the plan to apply. Not needed to roll back if `--journal-file` is given.
+	#[clap(long, required_unless_present_all = ["rollback", "journal_file"])]
+	plan_file: Option<PathBuf>,
+
+	/// Path to store the journal. Defaults to the plan file with a `.journal` extension.
+	#[clap(long)]
+	journal_file: Option<PathBuf>,
+
+	/// Path to an index file of the source, used to verify copied files.
+	#[clap(long)]
+	src_index_file: Option<PathBuf>,
+
+	/// Path to an index file of the destination, which is updated as operations are applied.
+	#[clap(long)]
+	dst_index_file: Option<PathBuf>,
+
+	/// Prints the operations without applying them.
+	#[clap(long)]
+	dry_run: bool,
+
+	/// Undoes the operations of an interrupted or failed run using its journal.
+	#[clap(
+		long,
+		conflicts_with = "dry_run"
+	)]
+	rollback: bool,
+}
+
+#[derive(Args, Debug)]
+struct MirrorSubcommand {
+	/// Source path whose contents should be copied.
+	src: PathBuf,
+
+	/// Destination path to make identical to the source.
+	dst: PathBuf,
+
+	/// Path to an index file of the source. If not provided, the source is read instead.
+	#[clap(long)]
+	src_index_file: Option<PathBuf>,
+
+	/// Path to an index file of the destination, which is updated as operations are applied. If
+	/// not provided, the destination is read instead.
+	#[clap(long)]
+	dst_index_file: Option<PathBuf>,
+
+	/// Path to store the journal. Defaults to the destination with a `.journal` extension.
+	#[clap(long)]
+	journal_file: Option<PathBuf>,
+
+	/// Deletes entries of the destination which are missing from the source.
+	#[clap(long)]
+	delete: bool,
+
+	/// Prints the operations without applying them.
+	#[clap(long)]
+	dry_run: bool,
+
+	#[command(flatten)]
+	matches: Matches,
+}
+
+#[derive(Args, Debug)]
+struct DedupeSubcommand {
+	/// Path to the index file, which is updated as duplicates are removed.
+	#[clap(long)]
+	index_file: PathBuf,
+
+	/// How duplicates are removed.
+	#[clap(
+		long,
+		value_enum
+	)]
+	mode: DedupeMode,
+
+	#[command(flatten)]
+	keep: Keep,
+
+	/// Path to store the journal. Defaults to the index file with a `.journal` extension.
+	#[clap(long)]
+	journal_file: Option<PathBuf>,
+
+	/// Directory to move duplicates to when quarantining them.
+	#[clap(
+		long,
+		required_if_eq(
+			"mode",
+			"quarantine"
+		)
+	)]
+	quarantine_dir: Option<PathBuf>,
+
+	/// Prints the duplicates that would be removed without changing anything.
+	#[clap(long)]
+	dry_run: bool,
+
+	/// Restores the duplicates removed by the run recorded in the journal.
+	#[clap(
+		long,
+		conflicts_with = "dry_run"
+	)]
+	undo: bool,
+
 	#[command(flatten)]
 	filter: Filter,
 
@@ -125,6 +673,42 @@ struct Matches {
 	modified: bool,
 }
 
+#[derive(Args, Debug)]
+struct RestoreSubcommand {
+	/// Directory the duplicates were quarantined in.
+	#[clap(long)]
+	quarantine_dir: PathBuf,
+
+	/// Path to an index file, which is updated with the restored files.
+	#[clap(long)]
+	index_file: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct PurgeSubcommand {
+	/// Directory the duplicates were quarantined in.
+	#[clap(long)]
+	quarantine_dir: PathBuf,
+
+	/// Only deletes files quarantined longer ago than this, such as `30d`. Supports the units s,
+	/// m, h, d and w.
+	#[clap(long, value_parser = parse_duration)]
+	older_than: Duration,
+}
+
+#[derive(Args, Debug)]
+struct Keep {
+	/// Rule for choosing which duplicate to keep: `oldest`, `newest`, `shortest`, `longest`,
+	/// `under:<dir>`, `match:<regex>` or `not-match:<regex>`. Later rules break ties of earlier
+	/// ones. Remaining ties keep the first path. Relative dirs are resolved against the current
+	/// dir.
+	#[clap(
+		long = "keep",
+		value_name = "RULE"
+	)]
+	rules: Vec<KeepRule>,
+}
+
 #[derive(Args, Debug)]
 struct Filter {
 	/// Regular expression for expressing the paths to keep. Multiple allowlists are considered an
@@ -138,20 +722,103 @@ struct Filter {
 	deny: Vec<Regex>,
 }
 
-fn main() -> Result<()> {
+// Exit code when differences or duplicates were found, similar to `diff`.
+const EXIT_FOUND: u8 = 1;
+// Exit code when the command failed.
+const EXIT_FAILURE: u8 = 2;
+
+fn main() -> ExitCode {
+	match run() {
+		Ok(false) => ExitCode::SUCCESS,
+		Ok(true) => ExitCode::from(EXIT_FOUND),
+		Err(e) => {
+			eprintln!("Error: {e:?}");
+			ExitCode::from(EXIT_FAILURE)
+		}
+	}
+}
+
+// Runs the command, returning whether it found something that should fail the run.
+#[allow(clippy::too_many_lines)]
+fn run() -> Result<bool> {
 	let cli = Cli::parse();
 	let path = env::current_dir().context("Unable to retrieve the current directory")?;
 	match cli.command {
 		Command::Index(subcommand) => {
-			command::index(&subcommand.src, &subcommand.index_file, subcommand.sha_512)
+			command::index(
+				&subcommand.src,
+				&subcommand.index_file,
+				subcommand.sha_512,
+				subcommand.history,
+			)?;
+			Ok(false)
+		}
+		Command::History(subcommand) => {
+			match subcommand.command {
+				HistoryCommand::List(subcommand) => {
+					command::history_list(&subcommand.index_file, cli.output)?;
+					Ok(false)
+				}
+				HistoryCommand::Diff(subcommand) => {
+					command::history_diff(
+						&subcommand.index_file,
+						subcommand.from,
+						subcommand.to,
+						cli.output,
+					)
+				}
+				HistoryCommand::Restore(subcommand) => {
+					command::history_restore(
+						&subcommand.index_file,
+						subcommand.snapshot,
+						&subcommand.restore_file,
+					)?;
+					Ok(false)
+				}
+			}
 		}
 		Command::Stats(subcommand) => {
+			if let (Some(index_file), Some(compare)) = (&subcommand.index_file, &subcommand.compare)
+			{
+				command::compare_stats(
+					index_file,
+					compare,
+					subcommand.dir.as_ref(),
+					subcommand.top,
+					cli.output,
+				)?;
+				return Ok(false);
+			}
 			let path = if subcommand.index_file.is_some() {
 				subcommand.src.as_ref()
 			} else {
 				subcommand.src.as_ref().or(Some(&path))
 			};
-			command::stats(path, subcommand.index_file.as_ref(), subcommand.dir.as_ref())
+			let allowlist = Allowlist {
+				allow: subcommand.filter.allow,
+				deny: subcommand.filter.deny,
+			};
+			command::stats(
+				path,
+				subcommand.index_file.as_ref(),
+				subcommand.dir.as_ref(),
+				&allowlist,
+				subcommand.breakdown,
+				subcommand.sniff,
+				cli.output,
+			)?;
+			Ok(false)
+		}
+		Command::Tree(subcommand) => {
+			command::tree(
+				&subcommand.index_file,
+				subcommand.dir.as_ref(),
+				subcommand.depth,
+				subcommand.sort,
+				subcommand.top,
+				cli.output,
+			)?;
+			Ok(false)
 		}
 		Command::Diff(subcommand) => {
 			command::diff(
@@ -160,6 +827,7 @@ fn main() -> Result<()> {
 				subcommand.matches.name,
 				subcommand.matches.created,
 				subcommand.matches.modified,
+				cli.output,
 			)
 		}
 		Command::Duplicates(subcommand) => {
@@ -167,27 +835,173 @@ fn main() -> Result<()> {
 				allow: subcommand.filter.allow,
 				deny: subcommand.filter.deny,
 			};
-			command::duplicates(
+			let options = DuplicateOptions {
+				sort: subcommand.sort,
+				min_size: subcommand.min_size,
+				min_wasted: subcommand.min_wasted,
+				across: subcommand.across,
+			};
+			let found = command::duplicates(
 				&subcommand.index_file,
-				subcommand.dirs,
+				subcommand.dirs.dirs,
+				subcommand.dirs.all_levels,
+				subcommand.dirs.strict,
 				&allowlist,
+				&options,
+				&subcommand.keep.rules,
 				subcommand.matches.name,
 				subcommand.matches.created,
 				subcommand.matches.modified,
-			)
+				subcommand.script,
+				cli.output,
+			)?;
+			Ok(found && subcommand.fail_on_found)
 		}
 		Command::Redundant(subcommand) => {
 			let allowlist = Allowlist {
 				allow: subcommand.filter.allow,
 				deny: subcommand.filter.deny,
 			};
-			command::redundant(
+			let found = command::redundant(
 				&subcommand.index_file,
 				&allowlist,
 				subcommand.matches.name,
 				subcommand.matches.created,
 				subcommand.matches.modified,
-			)
+				subcommand.script,
+				cli.output,
+			)?;
+			Ok(found && subcommand.fail_on_found)
+		}
+		Command::Find(subcommand) => {
+			let query = FindQuery {
+				kind: subcommand.kind,
+				names: subcommand.name,
+				paths: subcommand.path,
+				extensions: subcommand.extensions,
+				min_size: subcommand.min_size,
+				max_size: subcommand.max_size,
+				modified_after: subcommand.modified_after,
+				modified_before: subcommand.modified_before,
+				created_after: subcommand.created_after,
+				created_before: subcommand.created_before,
+				checksum_prefix: subcommand.checksum,
+				has_checksum: subcommand.has_checksum,
+				hidden: subcommand.hidden,
+			};
+			command::find(&subcommand.index_file, &query, cli.output)?;
+			Ok(false)
+		}
+		Command::Lookup(subcommand) => {
+			let allowlist = Allowlist {
+				allow: subcommand.filter.allow,
+				deny: subcommand.filter.deny,
+			};
+			let found =
+				command::lookup(&subcommand.src, &subcommand.index_file, &allowlist, cli.output)?;
+			Ok(found && subcommand.fail_on_new)
+		}
+		Command::Contained(subcommand) => {
+			let allowlist = Allowlist {
+				allow: subcommand.filter.allow,
+				deny: subcommand.filter.deny,
+			};
+			let found = command::contained(
+				&subcommand.index_file,
+				&allowlist,
+				subcommand.matches.name,
+				subcommand.matches.created,
+				subcommand.matches.modified,
+				cli.output,
+			)?;
+			Ok(found && subcommand.fail_on_found)
+		}
+		Command::SimilarDirs(subcommand) => {
+			let allowlist = Allowlist {
+				allow: subcommand.filter.allow,
+				deny: subcommand.filter.deny,
+			};
+			let found = command::similar_dirs(
+				&subcommand.index_file,
+				&allowlist,
+				subcommand.threshold,
+				subcommand.matches.name,
+				subcommand.matches.created,
+				subcommand.matches.modified,
+				cli.output,
+			)?;
+			Ok(found && subcommand.fail_on_found)
+		}
+		Command::Plan(subcommand) => {
+			command::plan(
+				&subcommand.src,
+				&subcommand.dst,
+				&subcommand.plan_file,
+				subcommand.src_index_file.as_ref(),
+				subcommand.dst_index_file.as_ref(),
+				subcommand.matches.name,
+				subcommand.matches.created,
+				subcommand.matches.modified,
+				cli.output,
+			)?;
+			Ok(false)
+		}
+		Command::Apply(subcommand) => {
+			command::apply(
+				subcommand.plan_file.as_ref(),
+				subcommand.journal_file.as_ref(),
+				subcommand.src_index_file.as_ref(),
+				subcommand.dst_index_file.as_ref(),
+				subcommand.dry_run,
+				subcommand.rollback,
+				cli.output,
+			)?;
+			Ok(false)
+		}
+		Command::Mirror(subcommand) => {
+			command::mirror(
+				&subcommand.src,
+				&subcommand.dst,
+				subcommand.journal_file.as_ref(),
+				subcommand.src_index_file.as_ref(),
+				subcommand.dst_index_file.as_ref(),
+				subcommand.delete,
+				subcommand.dry_run,
+				subcommand.matches.name,
+				subcommand.matches.created,
+				subcommand.matches.modified,
+				cli.output,
+			)?;
+			Ok(false)
+		}
+		Command::Dedupe(subcommand) => {
+			let allowlist = Allowlist {
+				allow: subcommand.filter.allow,
+				deny: subcommand.filter.deny,
+			};
+			command::dedupe(
+				&subcommand.index_file,
+				subcommand.mode,
+				subcommand.journal_file.as_ref(),
+				subcommand.quarantine_dir.as_ref(),
+				&allowlist,
+				&subcommand.keep.rules,
+				subcommand.dry_run,
+				subcommand.undo,
+				subcommand.matches.name,
+				subcommand.matches.created,
+				subcommand.matches.modified,
+				cli.output,
+			)?;
+			Ok(false)
+		}
+		Command::Restore(subcommand) => {
+			command::restore(&subcommand.quarantine_dir, subcommand.index_file.as_ref())?;
+			Ok(false)
+		}
+		Command::Purge(subcommand) => {
+			command::purge(&subcommand.quarantine_dir, subcommand.older_than)?;
+			Ok(false)
 		}
 	}
 }
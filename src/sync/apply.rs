@@ -0,0 +1,222 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::journal::backup_path;
+use super::journal::ignore_not_found;
+use super::journal::move_file;
+use super::journal::remove_backup;
+use super::Journal;
+use super::JournalEntry;
+use super::JournalRecord;
+use super::Operation;
+use super::Plan;
+use crate::index::model::Checksum;
+use crate::index::model::NativeFileReader;
+use crate::index::IndexUpdate;
+use crate::index::RootIndex;
+use crate::index::BUF_SIZE;
+
+// Returns the checksum of the copied file after checking that it matches the source. The checksum
+// of the source is taken from the index if it is known, otherwise it is calculated.
+fn verify(
+	src_path: &Path,
+	dst_path: &Path,
+	src_index: Option<&RootIndex>,
+	buf: &mut Vec<u8>,
+) -> io::Result<Checksum> {
+	let mut checksum = Checksum::new();
+	checksum.calculate(&NativeFileReader, dst_path, buf)?;
+	let indexed = src_index.and_then(|index| index.file(src_path));
+	let expected = match indexed {
+		Some(file) if !file.checksum.is_empty() => file.checksum.clone(),
+		_ => {
+			let mut expected = Checksum::new();
+			expected.calculate(&NativeFileReader, src_path, buf)?;
+			expected
+		}
+	};
+	if checksum != expected {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidData,
+			format!("Checksum mismatch after copying {}", src_path.display()),
+		));
+	}
+	Ok(checksum)
+}
+
+// Runs the operations of the plan, recording the changes to reflect in the destination index.
+fn run_operations(
+	plan: &Plan,
+	journal: &mut Journal,
+	backup: &Path,
+	src_index: Option<&RootIndex>,
+	updates: &mut Vec<IndexUpdate>,
+	mut notifier: impl FnMut(&Operation),
+) -> io::Result<()> {
+	let src = Path::new(&plan.src);
+	let dst = Path::new(&plan.dst);
+	let mut buf = Vec::with_capacity(BUF_SIZE);
+	for operation in &plan.operations {
+		notifier(operation);
+		let backup_file = match operation {
+			Operation::Overwrite(path) | Operation::Delete(path) => Some(backup.join(path)),
+			_ => None,
+		};
+		journal.write(&JournalEntry::Begin {
+			operation: operation.clone(),
+			backup: backup_file.as_ref().map(|path| path.to_string_lossy().to_string()),
+		})?;
+
+		let dst_path = dst.join(operation.path());
+		match operation {
+			Operation::Mkdir(_) => {
+				fs::create_dir(&dst_path)?;
+				updates.push(IndexUpdate::Add(dst_path, None));
+			}
+			Operation::Copy(path) | Operation::Overwrite(path) => {
+				if let Some(backup_file) = &backup_file {
+					move_file(&dst_path, backup_file)?;
+				}
+				let src_path = src.join(path);
+				fs::copy(&src_path, &dst_path)?;
+				let checksum = verify(&src_path, &dst_path, src_index, &mut buf)?;
+				updates.push(IndexUpdate::Add(dst_path, Some(checksum)));
+			}
+			Operation::Move(from, _) => {
+				fs::rename(dst.join(from), &dst_path)?;
+				updates.push(IndexUpdate::Rename(dst.join(from), dst_path));
+			}
+			Operation::Delete(_) => {
+				if let Some(backup_file) = &backup_file {
+					move_file(&dst_path, backup_file)?;
+				}
+				updates.push(IndexUpdate::Remove(dst_path));
+			}
+			Operation::Rmdir(_) => {
+				fs::remove_dir(&dst_path)?;
+				updates.push(IndexUpdate::Remove(dst_path));
+			}
+		}
+		journal.write(&JournalEntry::End)?;
+	}
+	journal.write(&JournalEntry::Completed)
+}
+
+/// Applies the plan, writing its progress to a new journal at the given path. Replaced and deleted
+/// files are kept in a backup directory next to the journal until every operation finishes.
+///
+/// Copied files are verified against their checksum in the source index if given. The destination
+/// index is updated with every operation that finished, even if a later one failed, so it matches
+/// the destination without a rescan.
+pub fn apply(
+	plan: &Plan,
+	journal_path: &Path,
+	src_index: Option<&RootIndex>,
+	dst_index: Option<&mut RootIndex>,
+	notifier: impl FnMut(&Operation),
+) -> io::Result<()> {
+	let backup = backup_path(journal_path)?;
+	let mut journal = Journal::create(journal_path)?;
+	journal.write(&JournalEntry::Started {
+		src: plan.src.clone(),
+		dst: plan.dst.clone(),
+		backup: backup.to_string_lossy().to_string(),
+	})?;
+	fs::create_dir_all(&plan.dst)?;
+
+	let mut updates = Vec::new();
+	let result = run_operations(plan, &mut journal, &backup, src_index, &mut updates, notifier);
+	if let Some(index) = dst_index {
+		index.update(updates)?;
+	}
+	result?;
+	remove_backup(&backup)
+}
+
+// Undoes the operations which began, in reverse order, recording the changes to reflect in the
+// destination index.
+fn undo_operations(
+	entries: &[JournalEntry],
+	dst: &Path,
+	updates: &mut Vec<IndexUpdate>,
+	mut notifier: impl FnMut(&Operation),
+) -> io::Result<()> {
+	let begun = entries.iter().filter_map(|entry| {
+		let JournalEntry::Begin {
+			operation,
+			backup,
+		} = entry
+		else {
+			return None;
+		};
+		Some((operation, backup.as_ref().map(Path::new)))
+	});
+	for (operation, backup_file) in begun.collect::<Vec<_>>().into_iter().rev() {
+		notifier(operation);
+		let dst_path = dst.join(operation.path());
+		match operation {
+			Operation::Mkdir(_) | Operation::Copy(_) => {
+				if dst_path.is_dir() {
+					ignore_not_found(fs::remove_dir(&dst_path))?;
+				} else {
+					ignore_not_found(fs::remove_file(&dst_path))?;
+				}
+				updates.push(IndexUpdate::Remove(dst_path));
+			}
+			Operation::Overwrite(_) | Operation::Delete(_) => {
+				let Some(backup_file) = backup_file.filter(|path| path.exists()) else {
+					continue;
+				};
+				ignore_not_found(fs::remove_file(&dst_path))?;
+				move_file(backup_file, &dst_path)?;
+				updates.push(IndexUpdate::Add(dst_path, None));
+			}
+			Operation::Move(from, _) => {
+				if !dst_path.exists() {
+					continue;
+				}
+				fs::rename(&dst_path, dst.join(from))?;
+				updates.push(IndexUpdate::Rename(dst_path, dst.join(from)));
+			}
+			Operation::Rmdir(_) => {
+				fs::create_dir_all(&dst_path)?;
+				updates.push(IndexUpdate::Add(dst_path, None));
+			}
+		}
+	}
+	Ok(())
+}
+
+/// Undoes the operations of an interrupted or failed run from its journal, in reverse order. The
+/// destination index is updated with every undone operation, even if a later one failed.
+pub fn rollback(
+	journal_path: &Path,
+	dst_index: Option<&mut RootIndex>,
+	notifier: impl FnMut(&Operation),
+) -> io::Result<()> {
+	let entries: Vec<JournalEntry> = Journal::read(journal_path)?;
+	let Some(JournalEntry::Started {
+		dst,
+		backup,
+		..
+	}) = entries.first()
+	else {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "Journal was never started"));
+	};
+	if entries.iter().any(JournalRecord::is_finished) {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidData,
+			"Journal has already completed or been rolled back",
+		));
+	}
+
+	let mut updates = Vec::new();
+	let result = undo_operations(&entries, Path::new(dst), &mut updates, notifier);
+	if let Some(index) = dst_index {
+		index.update(updates)?;
+	}
+	result?;
+	Journal::append(journal_path)?.write(&JournalEntry::RolledBack)?;
+	remove_backup(backup)
+}
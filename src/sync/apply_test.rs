@@ -0,0 +1,124 @@
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use super::apply;
+use super::rollback;
+use super::Journal;
+use super::JournalEntry;
+use super::Operation;
+use super::Plan;
+use crate::index::RootIndex;
+
+// Creates the source and destination trees of a test, returning the plan between them.
+fn create_trees(root: &Path, operations: Vec<Operation>) -> Plan {
+	let src = root.join("src");
+	let dst = root.join("dst");
+	fs::create_dir_all(src.join("new")).unwrap();
+	fs::write(src.join("new/a.txt"), "a").unwrap();
+	fs::write(src.join("b.txt"), "new b").unwrap();
+	fs::create_dir_all(dst.join("empty")).unwrap();
+	fs::write(dst.join("b.txt"), "old b").unwrap();
+	fs::write(dst.join("old.txt"), "moved").unwrap();
+	fs::write(dst.join("gone.txt"), "gone").unwrap();
+	Plan {
+		src: src.to_string_lossy().to_string(),
+		dst: dst.to_string_lossy().to_string(),
+		operations,
+	}
+}
+
+fn entry_paths(index: &RootIndex) -> (Vec<&str>, Vec<&str>) {
+	(
+		index.files.iter().map(|file| file.meta.path()).collect(),
+		index.dirs.iter().map(|dir| dir.meta.path()).collect(),
+	)
+}
+
+fn read(path: impl AsRef<Path>) -> String {
+	fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn test_apply_plan() {
+	let root = tempfile::tempdir().unwrap();
+	let plan = create_trees(
+		root.path(),
+		vec![
+			Operation::Mkdir("new".to_string()),
+			Operation::Move("old.txt".to_string(), "moved.txt".to_string()),
+			Operation::Delete("gone.txt".to_string()),
+			Operation::Overwrite("b.txt".to_string()),
+			Operation::Copy("new/a.txt".to_string()),
+			Operation::Rmdir("empty".to_string()),
+		],
+	);
+	let dst = Path::new(&plan.dst);
+	let mut dst_index = RootIndex::from_path(dst, |_| {}).unwrap();
+	let journal_path = root.path().join("journal.ron");
+
+	let mut applied = Vec::new();
+	apply(&plan, &journal_path, None, Some(&mut dst_index), |operation| {
+		applied.push(operation.clone());
+	})
+	.unwrap();
+	assert_eq!(applied, plan.operations);
+	assert_eq!(read(dst.join("new/a.txt")), "a");
+	assert_eq!(read(dst.join("b.txt")), "new b");
+	assert_eq!(read(dst.join("moved.txt")), "moved");
+	assert!(!dst.join("old.txt").exists());
+	assert!(!dst.join("gone.txt").exists());
+	assert!(!dst.join("empty").exists());
+
+	let entries: Vec<JournalEntry> = Journal::read(&journal_path).unwrap();
+	assert_eq!(entries.last(), Some(&JournalEntry::Completed));
+	assert!(!root.path().join("journal.ron.backup").exists());
+	assert!(rollback(&journal_path, None, |_| {}).is_err());
+
+	let rescanned = RootIndex::from_path(dst, |_| {}).unwrap();
+	assert_eq!(entry_paths(&dst_index), entry_paths(&rescanned));
+	assert!(!dst_index.file(dst.join("new/a.txt")).unwrap().checksum.is_empty());
+}
+
+#[test]
+fn test_rollback_interrupted_run() {
+	let root = tempfile::tempdir().unwrap();
+	let plan = create_trees(
+		root.path(),
+		vec![
+			Operation::Mkdir("new".to_string()),
+			Operation::Move("old.txt".to_string(), "moved.txt".to_string()),
+			Operation::Delete("gone.txt".to_string()),
+			Operation::Overwrite("b.txt".to_string()),
+			Operation::Copy("missing.txt".to_string()),
+		],
+	);
+	let dst = Path::new(&plan.dst);
+	let original = RootIndex::from_path(dst, |_| {}).unwrap();
+	let mut dst_index = RootIndex::from_path(dst, |_| {}).unwrap();
+	let journal_path = root.path().join("journal.ron");
+
+	assert!(apply(&plan, &journal_path, None, Some(&mut dst_index), |_| {}).is_err());
+	assert_eq!(read(dst.join("b.txt")), "new b");
+	assert!(!dst.join("gone.txt").exists());
+	// The index reflects the operations that finished before the failure.
+	assert!(dst_index.file(dst.join("moved.txt")).is_some());
+	assert!(dst_index.file(dst.join("gone.txt")).is_none());
+
+	// An interruption can leave a partially written entry at the end of the journal.
+	let mut journal = OpenOptions::new().append(true).open(&journal_path).unwrap();
+	write!(journal, "Begin(operation:Del").unwrap();
+
+	rollback(&journal_path, Some(&mut dst_index), |_| {}).unwrap();
+	assert_eq!(read(dst.join("b.txt")), "old b");
+	assert_eq!(read(dst.join("gone.txt")), "gone");
+	assert_eq!(read(dst.join("old.txt")), "moved");
+	assert!(!dst.join("moved.txt").exists());
+	assert!(!dst.join("new").exists());
+	assert!(!root.path().join("journal.ron.backup").exists());
+	assert_eq!(entry_paths(&dst_index), entry_paths(&original));
+
+	let entries: Vec<JournalEntry> = Journal::read(&journal_path).unwrap();
+	assert_eq!(entries.last(), Some(&JournalEntry::RolledBack));
+}
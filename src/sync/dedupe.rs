@@ -0,0 +1,286 @@
+use std::fs;
+use std::io;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::journal::backup_path;
+use super::journal::ignore_not_found;
+use super::journal::move_file;
+use super::journal::remove_backup;
+use super::Journal;
+use super::JournalRecord;
+use crate::index::model::Checksum;
+use crate::index::model::File;
+use crate::index::model::NativeFileReader;
+use crate::index::RootIndex;
+use crate::index::BUF_SIZE;
+
+/// How duplicates are removed.
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupeMode {
+	/// Replaces duplicates with hardlinks to the kept file.
+	Hardlink,
+	/// Replaces duplicates with copy-on-write clones of the kept file, where the filesystem
+	/// supports it.
+	Reflink,
+	/// Replaces duplicates with relative symlinks to the kept file.
+	Symlink,
+	/// Deletes duplicates.
+	Delete,
+	/// Moves duplicates to a quarantine directory, from which they can be restored or purged.
+	Quarantine,
+}
+
+/// A duplicate file to remove in favor of the kept file with the same contents.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Dedupe {
+	pub kept: String,
+	pub duplicate: String,
+}
+
+/// A record of progress while deduplicating, used to undo the run.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum DedupeEntry {
+	/// Deduplicating started. Duplicates are kept in the backup directory until it completes.
+	Started {
+		mode: DedupeMode,
+		backup: String,
+	},
+	/// The duplicate is about to be moved to the backup path and replaced.
+	Begin {
+		dedupe: Dedupe,
+		backup: String,
+	},
+	/// The last duplicate that began has been replaced.
+	End,
+	/// Every duplicate was replaced.
+	Completed,
+	/// The duplicates were restored.
+	RolledBack,
+}
+
+impl JournalRecord for DedupeEntry {
+	fn is_finished(&self) -> bool {
+		matches!(self, DedupeEntry::Completed | DedupeEntry::RolledBack)
+	}
+}
+
+/// Pairs every duplicate with the file kept from its group, which is the first one after sorting by
+/// the keep rules. Files already linked to the kept file are skipped.
+pub fn dedupes(groups: &[Vec<&File>]) -> Vec<Dedupe> {
+	let mut dedupes = Vec::new();
+	for group in groups {
+		let Some((kept, duplicates)) = group.split_first() else {
+			continue;
+		};
+		for duplicate in duplicates {
+			if duplicate.linked.as_deref() == Some(kept.meta.path()) {
+				continue;
+			}
+			dedupes.push(Dedupe {
+				kept: kept.meta.path().to_string(),
+				duplicate: duplicate.meta.path().to_string(),
+			});
+		}
+	}
+	dedupes
+}
+
+// Returns whether both paths are links to the same file.
+#[cfg(unix)]
+fn same_file(lhs: &Path, rhs: &Path) -> io::Result<bool> {
+	use std::os::unix::fs::MetadataExt;
+	let lhs = fs::metadata(lhs)?;
+	let rhs = fs::metadata(rhs)?;
+	Ok(lhs.dev() == rhs.dev() && lhs.ino() == rhs.ino())
+}
+
+#[cfg(not(unix))]
+fn same_file(_lhs: &Path, _rhs: &Path) -> io::Result<bool> {
+	Ok(false)
+}
+
+// Returns whether both files still have the contents the index expects. Files without a checksum
+// in the index are compared to each other.
+pub(super) fn verify(index: &RootIndex, dedupe: &Dedupe, buf: &mut Vec<u8>) -> io::Result<bool> {
+	let mut kept = Checksum::new();
+	kept.calculate(&NativeFileReader, &dedupe.kept, buf)?;
+	let mut duplicate = Checksum::new();
+	duplicate.calculate(&NativeFileReader, &dedupe.duplicate, buf)?;
+	let expected = index.file(&dedupe.kept).map(|file| &file.checksum);
+	Ok(kept == duplicate
+		&& expected.is_none_or(|expected| expected.is_empty() || *expected == kept))
+}
+
+// Returns the path to the target relative to the directory of the link.
+fn relative_target(target: &Path, link: &Path) -> PathBuf {
+	let target: Vec<_> = target.components().collect();
+	let dir: Vec<_> = link.parent().map(|dir| dir.components().collect()).unwrap_or_default();
+	let common = target.iter().zip(&dir).take_while(|(lhs, rhs)| lhs == rhs).count();
+	let mut path: PathBuf = dir[common..].iter().map(|_| Component::ParentDir).collect();
+	path.extend(&target[common..]);
+	path
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+	std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+	std::os::windows::fs::symlink_file(target, link)
+}
+
+#[cfg(target_os = "linux")]
+fn reflink(from: &Path, to: &Path) -> io::Result<()> {
+	use std::os::fd::AsRawFd;
+	let src = fs::File::open(from)?;
+	let dst = fs::OpenOptions::new().write(true).create_new(true).open(to)?;
+	// SAFETY: Both file descriptors stay open for the duration of the call.
+	if unsafe { libc::ioctl(dst.as_raw_fd(), libc::FICLONE, src.as_raw_fd()) } == -1 {
+		let e = io::Error::last_os_error();
+		drop(dst);
+		fs::remove_file(to)?;
+		return Err(e);
+	}
+	Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reflink(_from: &Path, _to: &Path) -> io::Result<()> {
+	Err(io::Error::new(io::ErrorKind::Unsupported, "Reflinks are only supported on Linux"))
+}
+
+fn replace(mode: DedupeMode, kept: &Path, duplicate: &Path) -> io::Result<()> {
+	match mode {
+		DedupeMode::Hardlink => fs::hard_link(kept, duplicate),
+		DedupeMode::Reflink => reflink(kept, duplicate),
+		DedupeMode::Symlink => symlink(&relative_target(kept, duplicate), duplicate),
+		DedupeMode::Delete => Ok(()),
+		DedupeMode::Quarantine => {
+			Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				"Duplicates are quarantined by `quarantine`",
+			))
+		}
+	}
+}
+
+/// Removes the duplicates, writing progress to a new journal at the given path. Duplicates are
+/// kept in a backup directory next to the journal until every one is replaced.
+///
+/// The contents of both files are verified right before acting, and duplicates which changed since
+/// they were indexed are skipped and returned. The index is updated after each duplicate, so its
+/// stats reflect the saved space.
+pub fn dedupe<'a>(
+	dedupes: &'a [Dedupe],
+	mode: DedupeMode,
+	journal_path: &Path,
+	index: &mut RootIndex,
+	mut notifier: impl FnMut(&Dedupe),
+) -> io::Result<Vec<&'a Dedupe>> {
+	let backup = backup_path(journal_path)?;
+	let mut journal = Journal::create(journal_path)?;
+	journal.write(&DedupeEntry::Started {
+		mode,
+		backup: backup.to_string_lossy().to_string(),
+	})?;
+
+	let mut skipped = Vec::new();
+	let mut buf = Vec::with_capacity(BUF_SIZE);
+	for (dedupe_index, dedupe) in dedupes.iter().enumerate() {
+		notifier(dedupe);
+		let kept = Path::new(&dedupe.kept);
+		let duplicate = Path::new(&dedupe.duplicate);
+		if mode == DedupeMode::Hardlink && same_file(kept, duplicate)? {
+			index.set_linked(duplicate, Some(dedupe.kept.clone()));
+			continue;
+		}
+		if !verify(index, dedupe, &mut buf)? {
+			skipped.push(dedupe);
+			continue;
+		}
+
+		let backup_file = backup.join(dedupe_index.to_string());
+		journal.write(&DedupeEntry::Begin {
+			dedupe: dedupe.clone(),
+			backup: backup_file.to_string_lossy().to_string(),
+		})?;
+		move_file(duplicate, &backup_file)?;
+		if let Err(e) = replace(mode, kept, duplicate) {
+			move_file(&backup_file, duplicate)?;
+			return Err(e);
+		}
+		if mode == DedupeMode::Delete {
+			index.remove(duplicate);
+		} else {
+			index.set_linked(duplicate, Some(dedupe.kept.clone()));
+		}
+		journal.write(&DedupeEntry::End)?;
+	}
+	journal.write(&DedupeEntry::Completed)?;
+	remove_backup(&backup)?;
+	Ok(skipped)
+}
+
+/// Restores the duplicates removed by a run from its journal, in reverse order. Duplicates are
+/// restored from the backup directory if the run was interrupted, otherwise they are copied from
+/// the kept file, which was verified to have the same contents.
+pub fn undo_dedupe(
+	journal_path: &Path,
+	index: &mut RootIndex,
+	mut notifier: impl FnMut(&Dedupe),
+) -> io::Result<()> {
+	let entries: Vec<DedupeEntry> = Journal::read(journal_path)?;
+	let Some(DedupeEntry::Started {
+		backup,
+		..
+	}) = entries.first()
+	else {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "Journal was never started"));
+	};
+	if entries.contains(&DedupeEntry::RolledBack) {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "Journal has already been undone"));
+	}
+
+	// Every duplicate that began, along with whether it was replaced.
+	let mut begun = Vec::new();
+	for entry in &entries {
+		match entry {
+			DedupeEntry::Begin {
+				dedupe,
+				backup,
+			} => begun.push((dedupe, Path::new(backup), false)),
+			DedupeEntry::End => {
+				if let Some((_, _, ended)) = begun.last_mut() {
+					*ended = true;
+				}
+			}
+			_ => {}
+		}
+	}
+	for (dedupe, backup_file, ended) in begun.into_iter().rev() {
+		notifier(dedupe);
+		let duplicate = Path::new(&dedupe.duplicate);
+		if backup_file.exists() {
+			ignore_not_found(fs::remove_file(duplicate))?;
+			move_file(backup_file, duplicate)?;
+		} else if ended {
+			ignore_not_found(fs::remove_file(duplicate))?;
+			fs::copy(&dedupe.kept, duplicate)?;
+		} else {
+			continue;
+		}
+		if !index.set_linked(duplicate, None) {
+			index.add(duplicate, |_| {})?;
+		}
+	}
+	Journal::append(journal_path)?.write(&DedupeEntry::RolledBack)?;
+	remove_backup(backup)
+}
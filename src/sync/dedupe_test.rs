@@ -0,0 +1,133 @@
+use std::fs;
+use std::path::Path;
+
+use super::dedupe;
+use super::dedupes;
+use super::undo_dedupe;
+use super::Dedupe;
+use super::DedupeEntry;
+use super::DedupeMode;
+use super::Journal;
+use crate::index::model::File;
+use crate::index::PathIndexBuilder;
+use crate::index::RootIndex;
+
+fn new_test_files(file_slice: &[&'static str]) -> Vec<File> {
+	let mut builder = PathIndexBuilder::new();
+	for file in file_slice {
+		builder.add_file(file);
+	}
+	builder.build().files
+}
+
+fn new_dedupe(kept: &str, duplicate: &str) -> Dedupe {
+	Dedupe {
+		kept: kept.to_string(),
+		duplicate: duplicate.to_string(),
+	}
+}
+
+#[test]
+fn dedupes_keep_first() {
+	let files = new_test_files(&[
+		"a.txt",
+		"b.txt",
+		"c.txt",
+		"d.txt",
+	]);
+	let groups = vec![
+		vec![
+			&files[0],
+			&files[2],
+		],
+		vec![
+			&files[1],
+			&files[3],
+		],
+	];
+	assert_eq!(
+		dedupes(&groups),
+		vec![
+			new_dedupe("a.txt", "c.txt"),
+			new_dedupe("b.txt", "d.txt"),
+		]
+	);
+}
+
+#[test]
+fn dedupes_skip_linked() {
+	let mut files = new_test_files(&[
+		"a.txt",
+		"b.txt",
+		"c.txt",
+	]);
+	files[1].linked = Some("a.txt".to_string());
+	files[2].linked = Some("b.txt".to_string());
+	let groups = vec![files.iter().collect()];
+	assert_eq!(dedupes(&groups), vec![new_dedupe("a.txt", "c.txt")]);
+}
+
+// Creates a tree with a kept file and a duplicate of it, returning its index and dedupe.
+fn create_tree(root: &Path) -> (RootIndex, Vec<Dedupe>) {
+	let dir = root.join("photos");
+	fs::create_dir_all(&dir).unwrap();
+	fs::write(dir.join("kept.txt"), "same").unwrap();
+	fs::write(dir.join("copy.txt"), "same").unwrap();
+	let index = RootIndex::from_path(&dir, |_| {}).unwrap();
+	let path = |name| index.files.iter().find(|file| file.meta.name() == name).unwrap().meta.path();
+	let dedupes = vec![new_dedupe(path("kept.txt"), path("copy.txt"))];
+	(index, dedupes)
+}
+
+#[test]
+fn dedupe_hardlink_and_undo() {
+	let root = tempfile::tempdir().unwrap();
+	let (mut index, dedupes) = create_tree(root.path());
+	let journal_path = root.path().join("journal.ron");
+	let duplicate = Path::new(&dedupes[0].duplicate);
+
+	let skipped =
+		dedupe(&dedupes, DedupeMode::Hardlink, &journal_path, &mut index, |_| {}).unwrap();
+	assert!(skipped.is_empty());
+	assert_eq!(fs::read_to_string(duplicate).unwrap(), "same");
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::MetadataExt;
+		let inode = |path| fs::metadata(path).unwrap().ino();
+		assert_eq!(inode(duplicate), inode(Path::new(&dedupes[0].kept)));
+	}
+	assert_eq!(
+		index.file(&dedupes[0].duplicate).unwrap().linked.as_deref(),
+		Some(dedupes[0].kept.as_str())
+	);
+	let entries: Vec<DedupeEntry> = Journal::read(&journal_path).unwrap();
+	assert_eq!(entries.last(), Some(&DedupeEntry::Completed));
+
+	undo_dedupe(&journal_path, &mut index, |_| {}).unwrap();
+	// The restored duplicate is a separate file again.
+	fs::write(&dedupes[0].kept, "changed").unwrap();
+	assert_eq!(fs::read_to_string(duplicate).unwrap(), "same");
+	assert!(index.file(&dedupes[0].duplicate).unwrap().linked.is_none());
+	assert!(undo_dedupe(&journal_path, &mut index, |_| {}).is_err());
+}
+
+#[test]
+fn dedupe_delete_skips_changed() {
+	let root = tempfile::tempdir().unwrap();
+	let (mut index, mut dedupes) = create_tree(root.path());
+	let changed = root.path().join("photos/changed.txt");
+	fs::write(&changed, "other").unwrap();
+	dedupes.push(new_dedupe(&dedupes[0].kept.clone(), &changed.to_string_lossy()));
+	let journal_path = root.path().join("journal.ron");
+
+	let skipped = dedupe(&dedupes, DedupeMode::Delete, &journal_path, &mut index, |_| {}).unwrap();
+	assert_eq!(skipped, vec![&dedupes[1]]);
+	assert!(!Path::new(&dedupes[0].duplicate).exists());
+	assert!(changed.exists());
+	assert!(index.file(&dedupes[0].duplicate).is_none());
+	assert!(!root.path().join("journal.ron.backup").exists());
+
+	undo_dedupe(&journal_path, &mut index, |_| {}).unwrap();
+	assert_eq!(fs::read_to_string(&dedupes[0].duplicate).unwrap(), "same");
+	assert!(index.file(&dedupes[0].duplicate).is_some());
+}
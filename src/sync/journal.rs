@@ -0,0 +1,137 @@
+use std::ffi::OsString;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::Operation;
+
+/// A record of progress while applying a plan, used to roll back an interrupted or failed run.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum JournalEntry {
+	/// The plan started being applied between the given directories. Replaced and deleted files
+	/// are kept in the backup directory until the plan completes.
+	Started {
+		src: String,
+		dst: String,
+		backup: String,
+	},
+	/// The operation is about to run. Files it replaces or deletes are moved to the backup path.
+	Begin {
+		operation: Operation,
+		backup: Option<String>,
+	},
+	/// The last operation that began has finished.
+	End,
+	/// Every operation finished.
+	Completed,
+	/// The operations were undone.
+	RolledBack,
+}
+
+/// An entry of a journal, which records whether the journal is finished.
+pub trait JournalRecord: Serialize + DeserializeOwned {
+	/// Returns whether the entry ends the journal, after which it no longer needs a rollback.
+	fn is_finished(&self) -> bool;
+}
+
+impl JournalRecord for JournalEntry {
+	fn is_finished(&self) -> bool {
+		matches!(self, JournalEntry::Completed | JournalEntry::RolledBack)
+	}
+}
+
+/// An append-only journal file, with one RON entry per line.
+pub struct Journal {
+	file: File,
+}
+
+impl Journal {
+	// Creates a new journal. Fails if the journal already exists.
+	pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+		let file = OpenOptions::new().write(true).create_new(true).open(path)?;
+		Ok(Self {
+			file,
+		})
+	}
+
+	// Opens an existing journal to append to it. A partially written last line, left by an
+	// interruption, is removed so the next entry starts on its own line.
+	pub fn append(path: impl AsRef<Path>) -> io::Result<Self> {
+		let contents = fs::read(path.as_ref())?;
+		let complete =
+			contents.iter().rposition(|byte| *byte == b'\n').map_or(0, |index| index + 1);
+		let file = OpenOptions::new().append(true).open(path)?;
+		if complete < contents.len() {
+			file.set_len(complete as u64)?;
+		}
+		Ok(Self {
+			file,
+		})
+	}
+
+	// Writes the entry and waits until it is stored, so it survives an interruption.
+	pub fn write(&mut self, entry: &impl JournalRecord) -> io::Result<()> {
+		let line = ron::to_string(entry).map_err(io::Error::other)?;
+		writeln!(self.file, "{line}")?;
+		self.file.sync_data()
+	}
+
+	// Reads every entry of the journal. A partially written last line is ignored.
+	pub fn read<T: JournalRecord>(path: impl AsRef<Path>) -> io::Result<Vec<T>> {
+		let reader = BufReader::new(File::open(path)?);
+		let lines = reader.lines().collect::<io::Result<Vec<_>>>()?;
+		let mut entries = Vec::with_capacity(lines.len());
+		for (line_index, line) in lines.iter().enumerate() {
+			match ron::from_str(line) {
+				Ok(entry) => entries.push(entry),
+				Err(_) if line_index + 1 == lines.len() => {}
+				Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+			}
+		}
+		Ok(entries)
+	}
+}
+
+pub(super) fn ignore_not_found(result: io::Result<()>) -> io::Result<()> {
+	match result {
+		Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+		result => result,
+	}
+}
+
+// Returns the directory that replaced and deleted files are kept in for the given journal.
+pub(super) fn backup_path(journal_path: &Path) -> io::Result<PathBuf> {
+	let mut path = OsString::from(journal_path.as_os_str());
+	path.push(".backup");
+	std::path::absolute(PathBuf::from(path))
+}
+
+// Moves a file, copying it instead if the destination is on another filesystem.
+pub(super) fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+	if let Some(parent) = to.parent() {
+		fs::create_dir_all(parent)?;
+	}
+	if fs::rename(from, to).is_ok() {
+		return Ok(());
+	}
+	fs::copy(from, to)?;
+	fs::remove_file(from)
+}
+
+// Removes the backup directory once it is no longer needed for a rollback.
+pub fn remove_backup(backup: impl AsRef<Path>) -> io::Result<()> {
+	match fs::remove_dir_all(backup) {
+		Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+		_ => Ok(()),
+	}
+}
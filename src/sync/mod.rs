@@ -0,0 +1,19 @@
+mod apply;
+#[cfg(test)]
+mod apply_test;
+mod dedupe;
+#[cfg(test)]
+mod dedupe_test;
+mod journal;
+mod plan;
+#[cfg(test)]
+mod plan_test;
+mod quarantine;
+#[cfg(test)]
+mod quarantine_test;
+
+pub use apply::*;
+pub use dedupe::*;
+pub use journal::*;
+pub use plan::*;
+pub use quarantine::*;
@@ -0,0 +1,157 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::index::Diff;
+use crate::index::RootIndex;
+
+/// A single change to the destination. Paths are relative to the source and destination roots.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub enum Operation {
+	/// Creates a directory.
+	Mkdir(String),
+	/// Copies a new file from the source.
+	Copy(String),
+	/// Replaces a changed file with the one from the source.
+	Overwrite(String),
+	/// Renames a file or directory from its current path to its new path.
+	Move(String, String),
+	/// Deletes a file.
+	Delete(String),
+	/// Deletes an empty directory.
+	Rmdir(String),
+}
+
+impl Operation {
+	/// Returns the path that the operation creates or changes.
+	pub fn path(&self) -> &str {
+		match self {
+			Operation::Mkdir(path)
+			| Operation::Copy(path)
+			| Operation::Overwrite(path)
+			| Operation::Move(_, path)
+			| Operation::Delete(path)
+			| Operation::Rmdir(path) => path,
+		}
+	}
+}
+
+/// An ordered list of operations which make the destination match the source.
+#[derive(Serialize, Deserialize)]
+pub struct Plan {
+	pub src: String,
+	pub dst: String,
+	pub operations: Vec<Operation>,
+}
+
+fn is_inside(path: &str, dir: &str) -> bool {
+	path.starts_with(dir) && path.as_bytes().get(dir.len()) == Some(&b'/')
+}
+
+// Returns the path after the given directory moves are applied.
+fn moved_path(path: &str, dir_moves: &[(String, String)]) -> String {
+	for (from, to) in dir_moves {
+		if path == from {
+			return to.clone();
+		}
+		if is_inside(path, from) {
+			return format!("{to}{}", &path[from.len()..]);
+		}
+	}
+	path.to_string()
+}
+
+impl Plan {
+	/// Creates a plan from the differences between the source and destination indexes, whose
+	/// entries must be relative to their roots.
+	///
+	/// Directory moves are applied first, so every other operation refers to paths after the move.
+	/// New directories are created before anything is moved or copied into them, including new
+	/// directories inside moved ones, which are created right after the directory moves.
+	/// Directories which no longer exist in the source are deleted last, once they are empty.
+	pub fn new(src_index: &RootIndex, dst_index: &RootIndex, diff_list: &[Diff]) -> Self {
+		let dst_dirs: BTreeSet<_> = dst_index.dirs.iter().map(|dir| dir.meta.path()).collect();
+
+		let mut dir_moves = Vec::new();
+		let mut file_moves = Vec::new();
+		let mut copies = Vec::new();
+		let mut deletes = Vec::new();
+		for diff in diff_list {
+			match diff {
+				Diff::Added(path) | Diff::Copied(_, path) => {
+					copies.push(Operation::Copy(path.clone()));
+				}
+				Diff::Changed(path) => {
+					copies.push(Operation::Overwrite(path.clone()));
+				}
+				Diff::Moved(to, from) if dst_dirs.contains(from.as_str()) => {
+					dir_moves.push((from.clone(), to.clone()));
+				}
+				Diff::Moved(to, from) => {
+					file_moves.push((from.clone(), to.clone()));
+				}
+				Diff::Removed(path) | Diff::Deduplicated(path, _) => {
+					deletes.push(path.clone());
+				}
+			}
+		}
+		dir_moves.sort();
+		file_moves.sort();
+		copies.sort_by(|lhs, rhs| lhs.path().cmp(rhs.path()));
+		deletes.sort();
+
+		let src_dirs: BTreeSet<_> =
+			src_index.dirs.iter().map(|dir| dir.meta.path().to_string()).collect();
+		let moved_dst_dirs: BTreeSet<_> =
+			dst_dirs.iter().map(|path| moved_path(path, &dir_moves)).collect();
+		let (mkdirs_after_move, mkdirs_before_move): (Vec<_>, Vec<_>) = src_dirs
+			.difference(&moved_dst_dirs)
+			.cloned()
+			.partition(|path| dir_moves.iter().any(|(_, to)| is_inside(path, to)));
+
+		let mut operations = Vec::new();
+		operations.extend(mkdirs_before_move.into_iter().map(Operation::Mkdir));
+		for (from, to) in &dir_moves {
+			operations.push(Operation::Move(from.clone(), to.clone()));
+		}
+		operations.extend(mkdirs_after_move.into_iter().map(Operation::Mkdir));
+		for path in deletes {
+			operations.push(Operation::Delete(moved_path(&path, &dir_moves)));
+		}
+		for (from, to) in file_moves {
+			operations.push(Operation::Move(moved_path(&from, &dir_moves), to));
+		}
+		operations.extend(copies);
+		let rmdirs: Vec<_> = moved_dst_dirs.difference(&src_dirs).cloned().collect();
+		operations.extend(rmdirs.into_iter().rev().map(Operation::Rmdir));
+
+		Self {
+			src: src_index.root().to_string_lossy().to_string(),
+			dst: dst_index.root().to_string_lossy().to_string(),
+			operations,
+		}
+	}
+
+	/// Removes the operations which delete files or directories, so entries of the destination
+	/// that are missing from the source are kept.
+	pub fn keep_extras(&mut self) {
+		self.operations
+			.retain(|operation| !matches!(operation, Operation::Delete(_) | Operation::Rmdir(_)));
+	}
+
+	// Opens a plan stored as RON on the filesystem.
+	pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+		ron::from_str(&fs::read_to_string(path)?)
+			.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+	}
+
+	// Stores the plan as RON on the filesystem.
+	pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+		let ron = ron::ser::to_string_pretty(&self, ron::ser::PrettyConfig::default()).unwrap();
+		fs::write(path, ron)
+	}
+}
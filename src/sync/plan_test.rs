@@ -0,0 +1,137 @@
+use super::Operation;
+use super::Plan;
+use crate::index::Diff;
+use crate::index::PathIndexBuilder;
+use crate::index::RootIndex;
+
+fn new_test_index(file_slice: &[&'static str]) -> RootIndex {
+	let mut builder = PathIndexBuilder::new();
+	for file in file_slice {
+		builder.add_file(file);
+	}
+	builder.build().relative("root").unwrap()
+}
+
+#[test]
+fn plan_copy_into_new_dir() {
+	let src_index = new_test_index(&[
+		"root/a.txt",
+		"root/new/b.txt",
+	]);
+	let dst_index = new_test_index(&["root/a.txt"]);
+	let plan = Plan::new(&src_index, &dst_index, &[Diff::Added("new/b.txt".to_string())]);
+	assert_eq!(
+		plan.operations,
+		vec![
+			Operation::Mkdir("new".to_string()),
+			Operation::Copy("new/b.txt".to_string()),
+		]
+	);
+}
+
+#[test]
+fn plan_delete_removed_dir() {
+	let src_index = new_test_index(&["root/a.txt"]);
+	let dst_index = new_test_index(&[
+		"root/a.txt",
+		"root/old/nested/b.txt",
+	]);
+	let plan = Plan::new(&src_index, &dst_index, &[Diff::Removed("old/nested/b.txt".to_string())]);
+	assert_eq!(
+		plan.operations,
+		vec![
+			Operation::Delete("old/nested/b.txt".to_string()),
+			Operation::Rmdir("old/nested".to_string()),
+			Operation::Rmdir("old".to_string()),
+		]
+	);
+}
+
+#[test]
+fn plan_dir_move_with_changes() {
+	let src_index = new_test_index(&[
+		"root/new/a.txt",
+		"root/new/b.txt",
+		"root/new/sub/c.txt",
+	]);
+	let dst_index = new_test_index(&[
+		"root/old/a.txt",
+		"root/old/b.txt",
+		"root/old/d.txt",
+	]);
+	let plan = Plan::new(
+		&src_index,
+		&dst_index,
+		&[
+			Diff::Changed("new/b.txt".to_string()),
+			Diff::Added("new/sub/c.txt".to_string()),
+			Diff::Removed("old/d.txt".to_string()),
+			Diff::Moved("new".to_string(), "old".to_string()),
+		],
+	);
+	assert_eq!(
+		plan.operations,
+		vec![
+			Operation::Move("old".to_string(), "new".to_string()),
+			Operation::Mkdir("new/sub".to_string()),
+			Operation::Delete("new/d.txt".to_string()),
+			Operation::Overwrite("new/b.txt".to_string()),
+			Operation::Copy("new/sub/c.txt".to_string()),
+		]
+	);
+}
+
+#[test]
+fn plan_file_move_into_new_dir_of_moved_dir() {
+	let src_index = new_test_index(&[
+		"root/new/a.txt",
+		"root/new/b.txt",
+		"root/new/sub/c.txt",
+	]);
+	let dst_index = new_test_index(&[
+		"root/old/a.txt",
+		"root/old/b.txt",
+		"root/old/c.txt",
+	]);
+	let plan = Plan::new(
+		&src_index,
+		&dst_index,
+		&[
+			Diff::Moved("new/sub/c.txt".to_string(), "old/c.txt".to_string()),
+			Diff::Moved("new".to_string(), "old".to_string()),
+		],
+	);
+	assert_eq!(
+		plan.operations,
+		vec![
+			Operation::Move("old".to_string(), "new".to_string()),
+			Operation::Mkdir("new/sub".to_string()),
+			Operation::Move("new/c.txt".to_string(), "new/sub/c.txt".to_string()),
+		]
+	);
+}
+
+#[test]
+fn plan_keep_extras() {
+	let src_index = new_test_index(&["root/new/a.txt"]);
+	let dst_index = new_test_index(&[
+		"root/old/a.txt",
+		"root/extra/b.txt",
+	]);
+	let mut plan = Plan::new(
+		&src_index,
+		&dst_index,
+		&[
+			Diff::Removed("extra/b.txt".to_string()),
+			Diff::Moved("new/a.txt".to_string(), "old/a.txt".to_string()),
+		],
+	);
+	plan.keep_extras();
+	assert_eq!(
+		plan.operations,
+		vec![
+			Operation::Mkdir("new".to_string()),
+			Operation::Move("old/a.txt".to_string(), "new/a.txt".to_string()),
+		]
+	);
+}
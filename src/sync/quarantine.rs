@@ -0,0 +1,218 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::dedupe::verify;
+use super::journal::ignore_not_found;
+use super::journal::move_file;
+use super::Dedupe;
+use super::Journal;
+use super::JournalRecord;
+use crate::index::model::parent_str;
+use crate::index::RootIndex;
+use crate::index::BUF_SIZE;
+
+// Name of the manifest inside the quarantine directory.
+const MANIFEST_NAME: &str = "manifest.ron";
+
+/// A duplicate moved to the quarantine directory.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct QuarantinedFile {
+	/// Path inside the quarantine directory.
+	pub quarantined: String,
+	/// Path the file was moved from.
+	pub original: String,
+	/// Path of the kept file with the same contents.
+	pub kept: String,
+	/// When the file was quarantined.
+	pub time: SystemTime,
+}
+
+// The manifest is a journal that is never finished, as files stay listed until they are restored
+// or purged.
+impl JournalRecord for QuarantinedFile {
+	fn is_finished(&self) -> bool {
+		false
+	}
+}
+
+fn manifest_path(dir: &Path) -> PathBuf {
+	dir.join(MANIFEST_NAME)
+}
+
+// Opens the manifest of the quarantine directory to append to it, creating it if needed.
+fn open_manifest(dir: &Path) -> io::Result<Journal> {
+	let path = manifest_path(dir);
+	if path.exists() {
+		return Journal::append(path);
+	}
+	fs::create_dir_all(dir)?;
+	Journal::create(path)
+}
+
+// Reads the files listed in the manifest of the quarantine directory.
+pub(super) fn read_manifest(dir: &Path) -> io::Result<Vec<QuarantinedFile>> {
+	match Journal::read(manifest_path(dir)) {
+		Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+		files => files,
+	}
+}
+
+// Replaces the manifest with the given files.
+fn write_manifest(dir: &Path, files: &[QuarantinedFile]) -> io::Result<()> {
+	let mut contents = String::new();
+	for file in files {
+		contents.push_str(&ron::to_string(file).map_err(io::Error::other)?);
+		contents.push('\n');
+	}
+	let temp_path = dir.join(format!("{MANIFEST_NAME}.tmp"));
+	fs::write(&temp_path, contents)?;
+	fs::rename(temp_path, manifest_path(dir))
+}
+
+// Removes the directories containing the path which became empty, up to the quarantine directory.
+fn remove_empty_parents(path: &Path, dir: &Path) {
+	let mut current = path.parent();
+	while let Some(parent) = current {
+		if parent == dir || fs::remove_dir(parent).is_err() {
+			break;
+		}
+		current = parent.parent();
+	}
+}
+
+// Returns the path inside the quarantine directory, relative to the parent of the outermost
+// indexed directory so the name of that directory is kept.
+fn quarantined_path(index: &RootIndex, path: &str) -> String {
+	match index.top_dir(path).and_then(parent_str) {
+		Some(parent) => path[(parent.len() + 1)..].to_string(),
+		None => path.replace(':', "").trim_start_matches('/').to_string(),
+	}
+}
+
+/// Moves the duplicates into the quarantine directory, preserving their relative paths. Each one
+/// is listed in the manifest before it is moved, so it can always be restored.
+///
+/// The contents of both files are verified right before acting, and duplicates which changed since
+/// they were indexed are skipped and returned. The index is updated after each duplicate, so its
+/// stats reflect the saved space.
+pub fn quarantine<'a>(
+	dedupes: &'a [Dedupe],
+	dir: &Path,
+	index: &mut RootIndex,
+	mut notifier: impl FnMut(&Dedupe),
+) -> io::Result<Vec<&'a Dedupe>> {
+	let mut manifest = open_manifest(dir)?;
+	let mut skipped = Vec::new();
+	let mut buf = Vec::with_capacity(BUF_SIZE);
+	for dedupe in dedupes {
+		notifier(dedupe);
+		if !verify(index, dedupe, &mut buf)? {
+			skipped.push(dedupe);
+			continue;
+		}
+		let quarantined = quarantined_path(index, &dedupe.duplicate);
+		let target = dir.join(&quarantined);
+		if target.exists() {
+			return Err(io::Error::new(
+				io::ErrorKind::AlreadyExists,
+				format!("A file is already quarantined in {}", target.display()),
+			));
+		}
+		manifest.write(&QuarantinedFile {
+			quarantined,
+			original: dedupe.duplicate.clone(),
+			kept: dedupe.kept.clone(),
+			time: SystemTime::now(),
+		})?;
+		move_file(Path::new(&dedupe.duplicate), &target)?;
+		index.remove(&dedupe.duplicate);
+	}
+	Ok(skipped)
+}
+
+/// Moves quarantined files back to their original paths and removes them from the manifest.
+/// Files whose original path is taken are kept in quarantine and returned. The notifier is called
+/// after each restored file, and the index is updated with it if given.
+pub fn restore(
+	dir: &Path,
+	mut index: Option<&mut RootIndex>,
+	mut notifier: impl FnMut(&QuarantinedFile),
+) -> io::Result<Vec<QuarantinedFile>> {
+	if !manifest_path(dir).exists() {
+		return Ok(Vec::new());
+	}
+	let mut pending = read_manifest(dir)?;
+	let mut skipped = Vec::new();
+	let mut result = Ok(());
+	while let Some(file) = pending.pop() {
+		let original = Path::new(&file.original);
+		let quarantined = dir.join(&file.quarantined);
+		// Files missing from quarantine were already restored or purged.
+		if !quarantined.exists() {
+			continue;
+		}
+		if original.exists() {
+			skipped.push(file);
+			continue;
+		}
+		result = move_file(&quarantined, original);
+		if let (Ok(()), Some(index)) = (&result, index.as_deref_mut()) {
+			result = index.add(original, |_| {});
+		}
+		if result.is_err() {
+			pending.push(file);
+			break;
+		}
+		remove_empty_parents(&quarantined, dir);
+		notifier(&file);
+	}
+	skipped.reverse();
+	pending.extend(skipped.iter().cloned());
+	write_manifest(dir, &pending)?;
+	result?;
+	Ok(skipped)
+}
+
+/// Deletes the files which were quarantined longer than the given duration ago, and removes them
+/// from the manifest. Returns the number of deleted files.
+pub fn purge(
+	dir: &Path,
+	older_than: Duration,
+	mut notifier: impl FnMut(&QuarantinedFile),
+) -> io::Result<usize> {
+	if !manifest_path(dir).exists() {
+		return Ok(0);
+	}
+	let now = SystemTime::now();
+	let (expired, mut kept): (Vec<_>, Vec<_>) = read_manifest(dir)?
+		.into_iter()
+		.partition(|file| now.duration_since(file.time).unwrap_or_default() >= older_than);
+
+	let mut result = Ok(());
+	let mut count = 0;
+	for file in &expired {
+		if result.is_err() {
+			kept.push(file.clone());
+			continue;
+		}
+		notifier(file);
+		let quarantined = dir.join(&file.quarantined);
+		result = ignore_not_found(fs::remove_file(&quarantined));
+		if result.is_err() {
+			kept.push(file.clone());
+			continue;
+		}
+		remove_empty_parents(&quarantined, dir);
+		count += 1;
+	}
+	write_manifest(dir, &kept)?;
+	result?;
+	Ok(count)
+}
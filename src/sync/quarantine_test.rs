@@ -0,0 +1,96 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use super::purge;
+use super::quarantine;
+use super::quarantine::read_manifest;
+use super::restore;
+use super::Dedupe;
+use crate::index::RootIndex;
+
+// Creates a tree with a kept file and two duplicates of it, returning its index and dedupes.
+fn create_tree(root: &Path) -> (RootIndex, Vec<Dedupe>) {
+	let dir = root.join("photos");
+	fs::create_dir_all(dir.join("copies")).unwrap();
+	fs::write(dir.join("kept.txt"), "same").unwrap();
+	fs::write(dir.join("copies/a.txt"), "same").unwrap();
+	fs::write(dir.join("copies/b.txt"), "same").unwrap();
+	let index = RootIndex::from_path(&dir, |_| {}).unwrap();
+	let path = |name| index.files.iter().find(|file| file.meta.name() == name).unwrap().meta.path();
+	let dedupes = [
+		"a.txt",
+		"b.txt",
+	]
+	.map(|name| {
+		Dedupe {
+			kept: path("kept.txt").to_string(),
+			duplicate: path(name).to_string(),
+		}
+	})
+	.to_vec();
+	(index, dedupes)
+}
+
+fn file_names(index: &RootIndex) -> Vec<&str> {
+	index.files.iter().map(|file| file.meta.name()).collect()
+}
+
+#[test]
+fn test_quarantine_and_restore() {
+	let root = tempfile::tempdir().unwrap();
+	let (mut index, dedupes) = create_tree(root.path());
+	let quarantine_dir = root.path().join("quarantine");
+
+	let skipped = quarantine(&dedupes, &quarantine_dir, &mut index, |_| {}).unwrap();
+	assert!(skipped.is_empty());
+	assert!(!Path::new(&dedupes[0].duplicate).exists());
+	assert_eq!(fs::read_to_string(quarantine_dir.join("photos/copies/a.txt")).unwrap(), "same");
+	assert_eq!(file_names(&index), ["kept.txt"]);
+
+	let manifest = read_manifest(&quarantine_dir).unwrap();
+	let originals: Vec<_> = manifest.iter().map(|file| file.original.as_str()).collect();
+	assert_eq!(
+		originals,
+		[
+			dedupes[0].duplicate.as_str(),
+			dedupes[1].duplicate.as_str()
+		]
+	);
+	assert!(manifest.iter().all(|file| file.kept == dedupes[0].kept));
+
+	// A restored file whose original path is taken stays in quarantine.
+	fs::write(&dedupes[1].duplicate, "other").unwrap();
+	let mut restored = Vec::new();
+	let skipped =
+		restore(&quarantine_dir, Some(&mut index), |file| restored.push(file.clone())).unwrap();
+	assert_eq!(skipped, manifest[1..]);
+	assert_eq!(restored, manifest[..1]);
+	assert_eq!(fs::read_to_string(&dedupes[0].duplicate).unwrap(), "same");
+	assert_eq!(
+		file_names(&index),
+		[
+			"a.txt",
+			"kept.txt"
+		]
+	);
+	assert_eq!(read_manifest(&quarantine_dir).unwrap(), manifest[1..]);
+	assert!(!quarantine_dir.join("photos/copies/a.txt").exists());
+}
+
+#[test]
+fn test_purge() {
+	let root = tempfile::tempdir().unwrap();
+	let (mut index, dedupes) = create_tree(root.path());
+	let quarantine_dir = root.path().join("quarantine");
+	quarantine(&dedupes, &quarantine_dir, &mut index, |_| {}).unwrap();
+
+	assert_eq!(purge(&quarantine_dir, Duration::from_hours(24), |_| {}).unwrap(), 0);
+	assert_eq!(read_manifest(&quarantine_dir).unwrap().len(), 2);
+
+	assert_eq!(purge(&quarantine_dir, Duration::ZERO, |_| {}).unwrap(), 2);
+	assert!(read_manifest(&quarantine_dir).unwrap().is_empty());
+	assert!(!quarantine_dir.join("photos").exists());
+	assert!(restore(&quarantine_dir, None, |_| {}).unwrap().is_empty());
+	assert!(!Path::new(&dedupes[0].duplicate).exists());
+}
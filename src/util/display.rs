@@ -4,3 +4,49 @@ pub fn percentage(current: usize, total: usize) -> String {
 	let percent = current as f64 / total as f64 * 100_f64;
 	format!("{percent:04.1}%")
 }
+
+const SIZE_UNITS: [&str; 5] = [
+	"B",
+	"KiB",
+	"MiB",
+	"GiB",
+	"TiB",
+];
+
+/// Formats a size in bytes with binary units, such as `1.5 MiB`.
+pub fn format_size(size: u128) -> String {
+	let mut unit = 0;
+	let mut scaled = size;
+	while scaled >= 1024 && unit < SIZE_UNITS.len() - 1 {
+		scaled /= 1024;
+		unit += 1;
+	}
+	if unit == 0 {
+		return format!("{size} B");
+	}
+	#[allow(clippy::cast_precision_loss)]
+	let value = size as f64 / (1u128 << (10 * unit)) as f64;
+	format!("{value:.1} {}", SIZE_UNITS[unit])
+}
+
+/// Parses a size such as `100`, `512k`, `10M`, `1.5GiB` or `2T`, with binary units.
+pub fn parse_size(value: &str) -> Result<u64, String> {
+	let unit_index = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+	let (amount, unit) = value.split_at(unit_index);
+	let amount: f64 = amount.parse().map_err(|_| format!("Invalid size `{value}`"))?;
+	let exponent = match unit.to_ascii_lowercase().as_str() {
+		"" | "b" => 0,
+		"k" | "kb" | "kib" => 1,
+		"m" | "mb" | "mib" => 2,
+		"g" | "gb" | "gib" => 3,
+		"t" | "tb" | "tib" => 4,
+		_ => return Err(format!("Invalid size `{value}`, expected a unit of B, K, M, G or T")),
+	};
+	#[allow(
+		clippy::cast_possible_truncation,
+		clippy::cast_precision_loss,
+		clippy::cast_sign_loss
+	)]
+	let size = (amount * (1u64 << (10 * exponent)) as f64) as u64;
+	Ok(size)
+}
@@ -0,0 +1,30 @@
+use super::display::format_size;
+use super::display::parse_size;
+
+#[test]
+fn parse_size_units() {
+	assert_eq!(parse_size("100"), Ok(100));
+	assert_eq!(parse_size("100B"), Ok(100));
+	assert_eq!(parse_size("512k"), Ok(512 * 1024));
+	assert_eq!(parse_size("10M"), Ok(10 * 1024 * 1024));
+	assert_eq!(parse_size("1.5GiB"), Ok(3 * 512 * 1024 * 1024));
+	assert_eq!(parse_size("2tb"), Ok(2 * 1024 * 1024 * 1024 * 1024));
+}
+
+#[test]
+fn parse_size_invalid() {
+	assert!(parse_size("").is_err());
+	assert!(parse_size("M").is_err());
+	assert!(parse_size("1.2.3M").is_err());
+	assert!(parse_size("10P").is_err());
+	assert!(parse_size("-1k").is_err());
+}
+
+#[test]
+fn format_size_units() {
+	assert_eq!(format_size(0), "0 B");
+	assert_eq!(format_size(1023), "1023 B");
+	assert_eq!(format_size(1536), "1.5 KiB");
+	assert_eq!(format_size(10 * 1024 * 1024), "10.0 MiB");
+	assert_eq!(format_size(5000 * 1024 * 1024 * 1024 * 1024), "5000.0 TiB");
+}
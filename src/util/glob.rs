@@ -0,0 +1,44 @@
+use regex::Regex;
+
+// Parses a glob such as `*.jpg`, `IMG_????.*` or `[ab]*` into a regex matching whole names. `*`
+// matches any characters, `?` matches one, and `[...]` matches one of a set, negated with `!`.
+pub fn parse_glob(value: &str) -> Result<Regex, String> {
+	let mut pattern = String::from("^");
+	let mut chars = value.chars();
+	while let Some(c) = chars.next() {
+		match c {
+			'*' => pattern.push_str(".*"),
+			'?' => pattern.push('.'),
+			'[' => {
+				let mut set = String::new();
+				let mut closed = false;
+				for c in chars.by_ref() {
+					if c == ']' && !set.is_empty() {
+						closed = true;
+						break;
+					}
+					set.push(c);
+				}
+				if !closed {
+					return Err(format!("Invalid glob `{value}`, expected `]`"));
+				}
+				pattern.push('[');
+				if let Some(negated) = set.strip_prefix('!') {
+					pattern.push('^');
+					set = negated.to_string();
+				}
+				// Ranges are kept, while other characters special in regex sets are escaped.
+				for c in set.chars() {
+					if matches!(c, '\\' | '[' | ']' | '^' | '&' | '~') {
+						pattern.push('\\');
+					}
+					pattern.push(c);
+				}
+				pattern.push(']');
+			}
+			c => pattern.push_str(&regex::escape(&c.to_string())),
+		}
+	}
+	pattern.push('$');
+	Regex::new(&pattern).map_err(|e| format!("Invalid glob `{value}`: {e}"))
+}
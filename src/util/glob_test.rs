@@ -0,0 +1,35 @@
+use super::glob::parse_glob;
+
+#[test]
+fn parse_glob_wildcards() {
+	let glob = parse_glob("*.jpg").unwrap();
+	assert!(glob.is_match("a.jpg"));
+	assert!(glob.is_match(".jpg"));
+	assert!(!glob.is_match("a.jpeg"));
+	assert!(!glob.is_match("a.jpg.txt"));
+
+	let glob = parse_glob("IMG_????.*").unwrap();
+	assert!(glob.is_match("IMG_0001.png"));
+	assert!(!glob.is_match("IMG_01.png"));
+
+	let glob = parse_glob("a+(b).c").unwrap();
+	assert!(glob.is_match("a+(b).c"));
+	assert!(!glob.is_match("aa(b)xc"));
+}
+
+#[test]
+fn parse_glob_sets() {
+	let glob = parse_glob("[a-c]*").unwrap();
+	assert!(glob.is_match("b.txt"));
+	assert!(!glob.is_match("d.txt"));
+
+	let glob = parse_glob("[!a-c]*").unwrap();
+	assert!(!glob.is_match("b.txt"));
+	assert!(glob.is_match("d.txt"));
+
+	let glob = parse_glob("[]^]").unwrap();
+	assert!(glob.is_match("]"));
+	assert!(glob.is_match("^"));
+
+	assert!(parse_glob("[ab").is_err());
+}
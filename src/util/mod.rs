@@ -1,3 +1,16 @@
 pub mod display;
+#[cfg(test)]
+mod display_test;
+pub mod glob;
+#[cfg(test)]
+mod glob_test;
+pub mod output;
+#[cfg(test)]
+mod output_test;
+pub mod script;
+#[cfg(test)]
+mod script_test;
 pub mod terminal;
 pub mod timer;
+#[cfg(test)]
+mod timer_test;
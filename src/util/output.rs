@@ -0,0 +1,84 @@
+use std::io;
+use std::io::Write;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// Format used to print command results.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+	/// Human readable text.
+	#[default]
+	Text,
+	/// A single JSON array.
+	Json,
+	/// One JSON object per line.
+	Ndjson,
+	/// Comma-separated values with a header row.
+	Csv,
+}
+
+// A command result that can be printed in any of the machine-readable formats.
+pub trait Record: Serialize {
+	// Column names of the CSV rows.
+	const CSV_HEADER: &'static [&'static str];
+
+	// Flattens the record into CSV rows, each with a value per header column.
+	fn csv_rows(&self) -> Vec<Vec<String>>;
+}
+
+// Quotes the value if it contains a separator, a quote or a line break, doubling its quotes.
+pub(super) fn csv_field(value: &str) -> String {
+	if value.contains([
+		',',
+		'"',
+		'\n',
+		'\r',
+	]) {
+		return format!("\"{}\"", value.replace('"', "\"\""));
+	}
+	value.to_string()
+}
+
+fn write_csv_row(out: &mut impl Write, row: &[impl AsRef<str>]) -> io::Result<()> {
+	let fields: Vec<_> = row.iter().map(|value| csv_field(value.as_ref())).collect();
+	writeln!(out, "{}", fields.join(","))
+}
+
+// Prints the records to stdout in the given machine-readable format. Does nothing for text output,
+// which is printed by each command instead.
+pub fn print_records<T: Record>(format: OutputFormat, records: &[T]) -> io::Result<()> {
+	let mut out = io::stdout().lock();
+	write_records(&mut out, format, records)?;
+	out.flush()
+}
+
+// Writes the records to the given output, like `print_records`.
+pub(super) fn write_records<T: Record>(
+	out: &mut impl Write,
+	format: OutputFormat,
+	records: &[T],
+) -> io::Result<()> {
+	match format {
+		OutputFormat::Text => {}
+		OutputFormat::Json => {
+			serde_json::to_writer_pretty(&mut *out, records)?;
+			writeln!(out)?;
+		}
+		OutputFormat::Ndjson => {
+			for record in records {
+				serde_json::to_writer(&mut *out, record)?;
+				writeln!(out)?;
+			}
+		}
+		OutputFormat::Csv => {
+			write_csv_row(out, T::CSV_HEADER)?;
+			for record in records {
+				for row in record.csv_rows() {
+					write_csv_row(out, &row)?;
+				}
+			}
+		}
+	}
+	Ok(())
+}
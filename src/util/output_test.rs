@@ -0,0 +1,85 @@
+use serde::Serialize;
+
+use super::output::csv_field;
+use super::output::write_records;
+use super::output::OutputFormat;
+use super::output::Record;
+
+#[derive(Serialize)]
+struct TestRecord {
+	name: &'static str,
+	count: usize,
+}
+
+impl Record for TestRecord {
+	const CSV_HEADER: &'static [&'static str] = &[
+		"name",
+		"count",
+	];
+
+	fn csv_rows(&self) -> Vec<Vec<String>> {
+		vec![
+			vec![
+				self.name.to_string(),
+				self.count.to_string(),
+			],
+		]
+	}
+}
+
+fn write_test_records(format: OutputFormat) -> String {
+	let records = [
+		TestRecord {
+			name: "a, \"b\"",
+			count: 1,
+		},
+		TestRecord {
+			name: "line\nbreak",
+			count: 2,
+		},
+	];
+	let mut out = Vec::new();
+	write_records(&mut out, format, &records).unwrap();
+	String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn csv_field_quoting() {
+	assert_eq!(csv_field("plain"), "plain");
+	assert_eq!(csv_field("a,b"), "\"a,b\"");
+	assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+	assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+	assert_eq!(csv_field("carriage\rreturn"), "\"carriage\rreturn\"");
+	assert_eq!(csv_field(""), "");
+}
+
+#[test]
+fn write_csv_records() {
+	assert_eq!(
+		write_test_records(OutputFormat::Csv),
+		"name,count\n\"a, \"\"b\"\"\",1\n\"line\nbreak\",2\n"
+	);
+}
+
+#[test]
+fn write_ndjson_records() {
+	let output = write_test_records(OutputFormat::Ndjson);
+	let lines: Vec<_> = output.lines().collect();
+	assert_eq!(
+		lines,
+		[
+			r#"{"name":"a, \"b\"","count":1}"#,
+			r#"{"name":"line\nbreak","count":2}"#,
+		]
+	);
+	assert!(output.ends_with('\n'));
+}
+
+#[test]
+fn write_json_and_text_records() {
+	let output = write_test_records(OutputFormat::Json);
+	let values: serde_json::Value = serde_json::from_str(&output).unwrap();
+	assert_eq!(values.as_array().unwrap().len(), 2);
+	assert_eq!(values[1]["name"], "line\nbreak");
+	assert!(write_test_records(OutputFormat::Text).is_empty());
+}
@@ -0,0 +1,227 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use clap::ValueEnum;
+
+use crate::index::model::resolve_lossy_path;
+use crate::index::model::Checksum;
+
+/// Shell for which deletion scripts are written.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptFormat {
+	/// POSIX shell script.
+	Sh,
+	/// PowerShell script.
+	Powershell,
+}
+
+const SH_HEADER: &str = r#"#!/bin/sh
+# Generated by folder-compare. Review it before running: each deletion is preceded by a check of
+# the SHA-512 recorded in the index, which stops the script if the file changed since indexing.
+set -eu
+
+check() {
+	if command -v sha512sum > /dev/null; then
+		actual=$(sha512sum < "$1")
+	else
+		actual=$(shasum -a 512 < "$1")
+	fi
+	if [ "${actual%% *}" != "$2" ]; then
+		echo "Contents changed since indexing, stopping: $1" >&2
+		exit 1
+	fi
+}
+"#;
+
+const POWERSHELL_HEADER: &str = r#"# Generated by folder-compare. Review it before running: each deletion is preceded by a check of
+# the SHA-512 recorded in the index, which stops the script if the file changed since indexing.
+$ErrorActionPreference = 'Stop'
+
+function Test-Checksum([string] $Path, [string] $Expected) {
+	$actual = (Get-FileHash -LiteralPath $Path -Algorithm SHA512).Hash
+	if ($actual -ne $Expected) {
+		throw "Contents changed since indexing, stopping: $Path"
+	}
+}
+"#;
+
+/// A script which deletes files and dirs for a user to review and run.
+pub struct Script {
+	format: ScriptFormat,
+	text: String,
+}
+
+impl Script {
+	pub fn new(format: ScriptFormat) -> Self {
+		let header = match format {
+			ScriptFormat::Sh => SH_HEADER,
+			ScriptFormat::Powershell => POWERSHELL_HEADER,
+		};
+		Self {
+			format,
+			text: header.to_string(),
+		}
+	}
+
+	fn line(&mut self, line: &str) {
+		self.text.push_str(line);
+		self.text.push('\n');
+	}
+
+	/// Adds an empty line.
+	pub fn blank(&mut self) {
+		self.line("");
+	}
+
+	/// Adds a comment. Control characters are replaced so a path cannot end the comment.
+	pub fn comment(&mut self, comment: &str) {
+		let comment = comment.replace(char::is_control, "?");
+		self.line(&format!("# {comment}"));
+	}
+
+	// Returns the quoted path, or `None` after adding a comment if it cannot be written.
+	fn quoted(&mut self, path: &str) -> Option<String> {
+		let quoted = resolve_lossy_path(path).and_then(|path| {
+			match self.format {
+				ScriptFormat::Sh => quote_sh(&path),
+				ScriptFormat::Powershell => quote_powershell(&path),
+			}
+		});
+		if quoted.is_none() {
+			self.comment(&format!("Skipped {path}: its name cannot be written in this script"));
+		}
+		quoted
+	}
+
+	/// Adds the deletion of a file, guarded by a check that it still has the given checksum.
+	pub fn remove_file(&mut self, path: &str, checksum: &Checksum) {
+		if checksum.is_empty() {
+			self.comment(&format!("Skipped {path}: its checksum is not indexed"));
+			return;
+		}
+		let Some(quoted) = self.quoted(path) else {
+			return;
+		};
+		let sha512 = &checksum.sha512;
+		match self.format {
+			ScriptFormat::Sh => {
+				self.line(&format!("check {quoted} '{sha512}'"));
+				self.line(&format!("rm -- {quoted}"));
+			}
+			ScriptFormat::Powershell => {
+				self.line(&format!("Test-Checksum {quoted} '{sha512}'"));
+				self.line(&format!("Remove-Item -LiteralPath {quoted}"));
+			}
+		}
+	}
+
+	/// Adds the deletion of a dir, which fails unless it is empty.
+	pub fn remove_dir(&mut self, path: &str) {
+		let Some(quoted) = self.quoted(path) else {
+			return;
+		};
+		match self.format {
+			ScriptFormat::Sh => self.line(&format!("rmdir -- {quoted}")),
+			ScriptFormat::Powershell => self.line(&format!("Remove-Item -LiteralPath {quoted}")),
+		}
+	}
+
+	pub fn text(&self) -> &str {
+		&self.text
+	}
+}
+
+// Returns an option to match the other platforms, where names must be Unicode.
+#[cfg(unix)]
+#[allow(clippy::unnecessary_wraps)]
+fn quote_sh(path: &Path) -> Option<String> {
+	use std::os::unix::ffi::OsStrExt;
+	Some(quote_sh_bytes(path.as_os_str().as_bytes()))
+}
+
+#[cfg(not(unix))]
+fn quote_sh(path: &Path) -> Option<String> {
+	path.to_str().map(|path| quote_sh_bytes(path.as_bytes()))
+}
+
+// Quotes a name for a POSIX shell. Text is single-quoted, and bytes which are not valid UTF-8 are
+// written as octal escapes for `printf`.
+pub(super) fn quote_sh_bytes(bytes: &[u8]) -> String {
+	let mut quoted = String::new();
+	let mut invalid = Vec::new();
+	let flush_invalid = |quoted: &mut String, invalid: &mut Vec<u8>| {
+		if invalid.is_empty() {
+			return;
+		}
+		quoted.push_str("\"$(printf '");
+		for byte in invalid.drain(..) {
+			let _ = write!(quoted, "\\{byte:03o}");
+		}
+		quoted.push_str("')\"");
+	};
+	for chunk in bytes.utf8_chunks() {
+		if !chunk.valid().is_empty() {
+			flush_invalid(&mut quoted, &mut invalid);
+			quoted.push('\'');
+			quoted.push_str(&chunk.valid().replace('\'', r"'\''"));
+			quoted.push('\'');
+		}
+		invalid.extend_from_slice(chunk.invalid());
+	}
+	flush_invalid(&mut quoted, &mut invalid);
+	if quoted.is_empty() {
+		quoted.push_str("''");
+	}
+	quoted
+}
+
+#[cfg(windows)]
+#[allow(clippy::unnecessary_wraps)]
+fn quote_powershell(path: &Path) -> Option<String> {
+	use std::os::windows::ffi::OsStrExt;
+	Some(quote_powershell_wide(path.as_os_str().encode_wide()))
+}
+
+#[cfg(not(windows))]
+fn quote_powershell(path: &Path) -> Option<String> {
+	path.to_str().map(|path| quote_powershell_wide(path.encode_utf16()))
+}
+
+fn single_quote_powershell(text: &str) -> String {
+	let mut quoted = String::from('\'');
+	for c in text.chars() {
+		// PowerShell also ends single-quoted strings at typographic single quotes.
+		if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+			quoted.push(c);
+		}
+		quoted.push(c);
+	}
+	quoted.push('\'');
+	quoted
+}
+
+// Quotes a name for PowerShell. Text is single-quoted, and unpaired surrogates are concatenated as
+// `[char]` values.
+pub(super) fn quote_powershell_wide(units: impl IntoIterator<Item = u16>) -> String {
+	let mut parts = Vec::new();
+	let mut text = String::new();
+	for unit in char::decode_utf16(units) {
+		match unit {
+			Ok(c) => text.push(c),
+			Err(e) => {
+				if !text.is_empty() {
+					parts.push(single_quote_powershell(&std::mem::take(&mut text)));
+				}
+				parts.push(format!("[char]0x{:04X}", e.unpaired_surrogate()));
+			}
+		}
+	}
+	if parts.is_empty() {
+		return single_quote_powershell(&text);
+	}
+	if !text.is_empty() {
+		parts.push(single_quote_powershell(&text));
+	}
+	// Starting with a string makes `+` concatenate rather than add.
+	format!("(''+{})", parts.join("+"))
+}
@@ -0,0 +1,34 @@
+use super::script::quote_powershell_wide;
+use super::script::quote_sh_bytes;
+
+#[test]
+fn quote_sh_plain() {
+	assert_eq!(quote_sh_bytes(b"a/b c.txt"), "'a/b c.txt'");
+	assert_eq!(quote_sh_bytes(b""), "''");
+}
+
+#[test]
+fn quote_sh_single_quote() {
+	assert_eq!(quote_sh_bytes(b"it's"), r"'it'\''s'");
+}
+
+#[test]
+fn quote_sh_invalid_utf8() {
+	assert_eq!(quote_sh_bytes(b"a\xff\xfeb"), r#"'a'"$(printf '\377\376')"'b'"#);
+}
+
+#[test]
+fn quote_powershell_quotes() {
+	let quote = |text: &str| quote_powershell_wide(text.encode_utf16());
+	assert_eq!(quote("C:/a b"), "'C:/a b'");
+	assert_eq!(quote("it's"), "'it''s'");
+	assert_eq!(quote("it\u{2019}s"), "'it\u{2019}\u{2019}s'");
+}
+
+#[test]
+fn quote_powershell_unpaired_surrogate() {
+	let mut units: Vec<u16> = "a".encode_utf16().collect();
+	units.push(0xD800);
+	units.extend("b".encode_utf16());
+	assert_eq!(quote_powershell_wide(units), "(''+'a'+[char]0xD800+'b')");
+}
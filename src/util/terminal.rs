@@ -1,3 +1,3 @@
 pub fn clear_line() {
-	print!("\r\x1b[2K");
+	eprint!("\r\x1b[2K");
 }
@@ -26,3 +26,109 @@ impl CountdownTimer {
 		false
 	}
 }
+
+// Parses a duration such as `90s`, `30m`, `12h`, `7d` or `2w`.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+	let unit_index = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+	let (amount, unit) = value.split_at(unit_index);
+	let amount: u64 = amount.parse().map_err(|_| format!("Invalid duration `{value}`"))?;
+	let seconds = match unit {
+		"s" => 1,
+		"m" => 60,
+		"h" => 60 * 60,
+		"d" => 24 * 60 * 60,
+		"w" => 7 * 24 * 60 * 60,
+		_ => return Err(format!("Invalid duration `{value}`, expected a unit of s, m, h, d or w")),
+	};
+	let seconds =
+		amount.checked_mul(seconds).ok_or_else(|| format!("Duration `{value}` is too long"))?;
+	Ok(Duration::from_secs(seconds))
+}
+
+// Returns the days since the Unix epoch of a date in the proleptic Gregorian calendar, or `None`
+// if the year is too far from the epoch to count the days.
+fn days_from_civil(year: i64, month: i64, day: i64) -> Option<i64> {
+	let year = if month <= 2 {
+		year.checked_sub(1)?
+	} else {
+		year
+	};
+	let era = year.div_euclid(400);
+	let year_of_era = year.rem_euclid(400);
+	let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+	let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+	era.checked_mul(146_097)?.checked_add(day_of_era - 719_468)
+}
+
+// Returns the date in the proleptic Gregorian calendar of the given days since the Unix epoch.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+	let days = days + 719_468;
+	let era = days.div_euclid(146_097);
+	let day_of_era = days - era * 146_097;
+	let year_of_era =
+		(day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let month_from_march = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+	let month = if month_from_march < 10 {
+		month_from_march + 3
+	} else {
+		month_from_march - 9
+	};
+	let year = year_of_era + era * 400 + i64::from(month <= 2);
+	(year, month, day)
+}
+
+// Returns the number of days in the month of the proleptic Gregorian calendar.
+fn days_in_month(year: i64, month: i64) -> i64 {
+	match month {
+		2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+		2 => 28,
+		4 | 6 | 9 | 11 => 30,
+		_ => 31,
+	}
+}
+
+// Formats a time as a UTC date such as `2024-05-31T12:30:00`, which `parse_time` accepts back.
+pub fn format_time(time: SystemTime) -> String {
+	let seconds =
+		time.duration_since(SystemTime::UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+	let (days, seconds) = (seconds / (24 * 60 * 60), seconds % (24 * 60 * 60));
+	let (year, month, day) = civil_from_days(i64::try_from(days).unwrap_or(i64::MAX));
+	let (hours, minutes, seconds) = (seconds / (60 * 60), seconds / 60 % 60, seconds % 60);
+	format!("{year:04}-{month:02}-{day:02}T{hours:02}:{minutes:02}:{seconds:02}")
+}
+
+// Parses a UTC date such as `2024-05-31` or `2024-05-31T12:30:00`.
+fn parse_date(value: &str) -> Option<SystemTime> {
+	let (date, time) = value.split_once('T').unwrap_or((value, "00:00:00"));
+	let date: Vec<i64> = date.split('-').map(str::parse).collect::<Result<_, _>>().ok()?;
+	let time: Vec<u64> = time.split(':').map(str::parse).collect::<Result<_, _>>().ok()?;
+	let (&[year, month, day], &[hours, minutes, seconds]) = (date.as_slice(), time.as_slice())
+	else {
+		return None;
+	};
+	if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+		return None;
+	}
+	if hours > 23 || minutes > 59 || seconds > 59 {
+		return None;
+	}
+	let days = u64::try_from(days_from_civil(year, month, day)?).ok()?;
+	let seconds =
+		days.checked_mul(24 * 60 * 60)?.checked_add(hours * 60 * 60 + minutes * 60 + seconds)?;
+	SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(seconds))
+}
+
+// Parses a time such as `2024-05-31`, `2024-05-31T12:30:00` in UTC, or a duration before now such
+// as `7d`.
+pub fn parse_time(value: &str) -> Result<SystemTime, String> {
+	if let Some(time) = parse_date(value) {
+		return Ok(time);
+	}
+	let duration =
+		parse_duration(value).map_err(|_| {
+			format!("Invalid time `{value}`, expected a date such as 2024-05-31 or a duration such as 7d")
+		})?;
+	SystemTime::now().checked_sub(duration).ok_or_else(|| format!("Invalid time `{value}`"))
+}
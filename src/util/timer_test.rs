@@ -0,0 +1,69 @@
+use std::time::Duration;
+use std::time::SystemTime;
+
+use super::timer::format_time;
+use super::timer::parse_duration;
+use super::timer::parse_time;
+
+#[test]
+fn parse_duration_units() {
+	assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+	assert_eq!(parse_duration("30m"), Ok(Duration::from_mins(30)));
+	assert_eq!(parse_duration("12h"), Ok(Duration::from_hours(12)));
+	assert_eq!(parse_duration("7d"), Ok(Duration::from_hours(7 * 24)));
+	assert_eq!(parse_duration("2w"), Ok(Duration::from_hours(14 * 24)));
+}
+
+#[test]
+fn parse_duration_invalid() {
+	assert!(parse_duration("").is_err());
+	assert!(parse_duration("30").is_err());
+	assert!(parse_duration("d").is_err());
+	assert!(parse_duration("1y").is_err());
+	assert!(parse_duration("-1d").is_err());
+	assert!(parse_duration("99999999999999999w").is_err());
+}
+
+#[test]
+fn parse_time_dates() {
+	let at = |seconds| Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds));
+	assert_eq!(parse_time("1970-01-01"), at(0));
+	assert_eq!(parse_time("2000-03-01"), at(951_868_800));
+	assert_eq!(parse_time("2024-02-29"), at(1_709_164_800));
+	assert_eq!(parse_time("2024-02-29T12:30:15"), at(1_709_209_815));
+}
+
+#[test]
+fn parse_time_relative() {
+	let time = parse_time("1d").unwrap();
+	let elapsed = time.elapsed().unwrap();
+	assert!(elapsed >= Duration::from_hours(24) && elapsed < Duration::from_hours(25));
+}
+
+#[test]
+fn parse_time_invalid() {
+	assert!(parse_time("").is_err());
+	assert!(parse_time("2024-13-01").is_err());
+	assert!(parse_time("2024-02-30").is_err());
+	assert!(parse_time("2023-02-29").is_err());
+	assert!(parse_time("2024-04-31").is_err());
+	assert!(parse_time("2024-01-01T24:00:00").is_err());
+	assert!(parse_time("1969-12-31").is_err());
+	assert!(parse_time("yesterday").is_err());
+	assert!(parse_time("9223372036854775807-01-01").is_err());
+	assert!(parse_time("99999999999999-01-01").is_err());
+}
+
+#[test]
+fn format_time_round_trip() {
+	let at = |seconds| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+	assert_eq!(format_time(at(0)), "1970-01-01T00:00:00");
+	assert_eq!(format_time(at(951_868_800)), "2000-03-01T00:00:00");
+	assert_eq!(format_time(at(1_709_209_815)), "2024-02-29T12:30:15");
+	for value in [
+		"1999-12-31T23:59:59",
+		"2100-02-28T00:00:01",
+	] {
+		assert_eq!(format_time(parse_time(value).unwrap()), value);
+	}
+}